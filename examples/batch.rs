@@ -1,8 +1,13 @@
 use display_error_chain::DisplayErrorChain;
-use gigachat_rust::{client::GigaChatClientBuilder, error::*, generation::structures::Message};
+use futures::StreamExt;
+use gigachat_rust::{
+    batch::{PollingConfig, handler::BatchCheckResult},
+    client::GigaChatClientBuilder,
+    error::*,
+    generation::structures::Message,
+};
 use snafu::ResultExt;
-use std::{env, process::ExitCode, time::Duration};
-use tokio::time::sleep;
+use std::{env, pin::pin, process::ExitCode};
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
@@ -33,31 +38,36 @@ async fn do_main() -> Result<(), Error> {
 
     tracing::info!(batch.id = handler.id(), "batch job started");
 
-    loop {
-        let status = handler.check().await.context(BatchSnafu)?;
-        match status {
-            gigachat_rust::batch::handler::BatchCheckResult::Pending => {
+    let mut progress = pin!(handler.progress(PollingConfig::default()));
+    while let Some(state) = progress.next().await {
+        match state.context(BatchSnafu)? {
+            BatchCheckResult::Pending => {
                 tracing::info!("batch is pending...");
             }
-            gigachat_rust::batch::handler::BatchCheckResult::InProgress { ready, total } => {
+            BatchCheckResult::InProgress { ready, total } => {
                 tracing::info!(ready = ready, total = total, "batch in progress");
             }
-            gigachat_rust::batch::handler::BatchCheckResult::Success { responses } => {
+            BatchCheckResult::Success { responses } => {
                 tracing::info!("batch completed successfully");
-                for (i, response_result) in responses.into_iter().enumerate() {
-                    match response_result.res() {
+                for (key, response_result) in responses {
+                    match response_result {
                         Ok(response) => {
-                            tracing::info!(response.index = i, response = ?response, "response received")
+                            tracing::info!(response.key = key, response = ?response, "response received")
                         }
                         Err(e) => {
-                            tracing::error!(response.index = i, error = ?e, "response failed")
+                            tracing::error!(response.key = key, error = ?e, "response failed")
                         }
                     }
                 }
-                break;
+            }
+            finished => {
+                tracing::error!(
+                    batch.status = ?finished.status(),
+                    partial = finished.responses().map_or(0, |r| r.len()),
+                    "batch finished unsuccessfully"
+                );
             }
         }
-        sleep(Duration::from_secs(5)).await;
     }
 
     Ok(())
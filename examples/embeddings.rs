@@ -1,5 +1,5 @@
 use display_error_chain::DisplayErrorChain;
-use gigachat_rust::{client::GigaChatClientBuilder, embeddings::Embeddings, error::*};
+use gigachat_rust::{client::GigaChatClientBuilder, error::*};
 use snafu::ResultExt;
 use std::{env, process::ExitCode};
 use tracing::level_filters::LevelFilter;
@@ -12,30 +12,35 @@ async fn do_main() -> Result<(), Error> {
         .await
         .context(ClientSnafu)?;
 
-    let embeddings = Embeddings::new(client);
-
     // Single string embedding
-    let single_embedding = embeddings
-        .create("This is a test string for embedding".to_string(), None)
+    let single_embedding = client
+        .embeddings()
+        .with_input("This is a test string for embedding")
+        .execute()
         .await
         .context(EmbeddingsSnafu)?;
 
     tracing::info!(embedding = ?single_embedding, "single embedding created successfully");
 
     // Multiple strings embedding
-    let multiple_embeddings = embeddings
-        .create(
-            vec![
-                "First test string".to_string(),
-                "Second test string".to_string(),
-                "Third test string".to_string(),
-            ],
-            None,
-        )
+    let multiple_embeddings = client
+        .embeddings()
+        .with_inputs([
+            "First test string",
+            "Second test string",
+            "Third test string",
+        ])
+        .execute()
         .await
         .context(EmbeddingsSnafu)?;
 
-    tracing::info!(embeddings = ?multiple_embeddings, "multiple embeddings created successfully");
+    for item in &multiple_embeddings.items {
+        tracing::info!(
+            text = item.text,
+            dimensions = item.embedding.len(),
+            "embedding created successfully"
+        );
+    }
 
     Ok(())
 }
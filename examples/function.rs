@@ -50,6 +50,9 @@ async fn do_main() -> Result<(), Error> {
         .build();
     let name = function.function_name();
 
+    let warnings = function.validate().context(FunctionSnafu)?;
+    snafu::ensure_whatever!(warnings.is_empty(), "No local warnings expected.");
+
     let warnings = client
         .check_function(&function)
         .await
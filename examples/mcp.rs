@@ -0,0 +1,98 @@
+use display_error_chain::DisplayErrorChain;
+use gigachat_rust::{
+    client::GigaChatClientBuilder,
+    error::*,
+    generation::structures::{FunctionCall, Message},
+    mcp::McpClient,
+};
+use snafu::{OptionExt, ResultExt};
+use std::{env, process::ExitCode};
+use tokio::process::Command;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+/// Максимальное количество вызовов функций за один диалог.
+const MAX_FUNCTION_CALLS: usize = 8;
+
+async fn do_main() -> Result<(), Error> {
+    let token = env::var("GIGACHAT_TOKEN").whatever_context("GIGACHAT_TOKEN must be set")?;
+    let client = GigaChatClientBuilder::new(token)
+        .build()
+        .await
+        .context(ClientSnafu)?;
+
+    // Например: `npx -y @modelcontextprotocol/server-everything`.
+    let server =
+        env::var("MCP_SERVER_COMMAND").whatever_context("MCP_SERVER_COMMAND must be set")?;
+    let mut parts = server.split_whitespace();
+    let mut command = Command::new(
+        parts
+            .next()
+            .whatever_context("MCP_SERVER_COMMAND is empty")?,
+    );
+    command.args(parts);
+
+    let mcp = McpClient::connect_stdio(command).await.context(McpSnafu)?;
+    for function in mcp.functions() {
+        tracing::info!(function.name = function.name(), "mcp tool available");
+    }
+
+    let mut messages = vec![Message::user("Сложи числа 17 и 25 с помощью инструмента.")];
+
+    for _ in 0..MAX_FUNCTION_CALLS {
+        let response = client
+            .generate()
+            .with_messages(messages.clone())
+            .with_user_functions(mcp.functions().iter().cloned())
+            .with_function_call(FunctionCall::Auto)
+            .execute()
+            .await
+            .context(GenerateSnafu)?;
+
+        let message = response
+            .choices
+            .into_iter()
+            .next()
+            .whatever_context("Response must be present.")?
+            .message;
+
+        let Message::Assistant {
+            function_call: Some(function_call),
+            ..
+        } = &message
+        else {
+            tracing::info!(response = ?message, "final answer received");
+            break;
+        };
+
+        tracing::info!(
+            function.name = function_call.name.as_str(),
+            "calling mcp tool"
+        );
+        let result = mcp.call(function_call).await.context(McpSnafu)?;
+        messages.push(message);
+        messages.push(result);
+    }
+
+    mcp.close().await;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::builder()
+                .with_default_directive(LevelFilter::INFO.into())
+                .from_env_lossy(),
+        )
+        .init();
+
+    if let Err(err) = do_main().await {
+        let error_chain = DisplayErrorChain::new(&err).to_string();
+        tracing::error!(error.chain = error_chain, "top level error");
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
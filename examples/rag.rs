@@ -0,0 +1,61 @@
+use display_error_chain::DisplayErrorChain;
+use gigachat_rust::{
+    client::GigaChatClientBuilder,
+    embeddings::Model,
+    error::*,
+    rag::{Chunk, InMemoryRetriever},
+};
+use snafu::ResultExt;
+use std::{env, process::ExitCode};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+async fn do_main() -> Result<(), Error> {
+    let token = env::var("GIGACHAT_TOKEN").whatever_context("GIGACHAT_TOKEN must be set")?;
+    let client = GigaChatClientBuilder::new(token)
+        .build()
+        .await
+        .context(ClientSnafu)?;
+
+    // Every non-empty paragraph becomes a separate chunk
+    let chunks = include_str!("../data/short.txt")
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .enumerate()
+        .map(|(index, paragraph)| Chunk::new(format!("short.txt#{index}"), paragraph))
+        .collect();
+
+    let retriever = InMemoryRetriever::from_chunks(&client, Model::EmbeddingsGigaR, chunks)
+        .await
+        .context(RagSnafu)?;
+
+    let rag = client.rag(retriever).with_top_k(3).build();
+    let answer = rag.ask("Что такое промпт?").await.context(RagSnafu)?;
+
+    tracing::info!(answer = answer.text, "answer generated successfully");
+    for source in answer.cited_sources() {
+        tracing::info!(id = source.chunk.id, score = source.score, "cited source");
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::builder()
+                .with_default_directive(LevelFilter::INFO.into())
+                .from_env_lossy(),
+        )
+        .init();
+
+    if let Err(err) = do_main().await {
+        let error_chain = DisplayErrorChain::new(&err).to_string();
+        tracing::error!(error.chain = error_chain, "top level error");
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
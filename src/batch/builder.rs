@@ -1,76 +1,304 @@
-use snafu::ResultExt;
-use std::io::{BufWriter, Write};
+use serde::Serialize;
+use snafu::{ResultExt, ensure};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufWriter, Write},
+};
 use tokio::task::spawn_blocking;
 use tracing::Span;
 
-use super::{error, handler::BatchHandler, structures::BatchCreateResponse};
-use crate::{client::GigaChatClient, generation::structures::GenerationRequest};
+use super::{
+    error,
+    handler::BatchHandler,
+    retry::{RetryConfig, execute_with_retries, failed_requests},
+    split::{BatchLimits, SplitBatchHandler, submit_split_batch},
+    structures::{BatchCreateResponse, BatchGenerateRequestItem, BatchItemError, BatchKey, Method},
+};
+use crate::{
+    client::GigaChatClient,
+    generation::structures::{GenerationRequest, GenerationResponse},
+};
 
 /// Сборщик пакетных запросов.
-pub struct BatchBuilder {
+///
+/// Каждый запрос пакета имеет ключ типа `K` (см. [`BatchKey`]), по которому
+/// сопоставляются результаты. Запросы, добавленные через
+/// [`BatchBuilder::with_request`], получают ключом свой порядковый номер;
+/// собственные ключи задаются через [`BatchBuilder::with_keyed_request`].
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::batch::handler::BatchCheckResult;
+/// use gigachat_rust::client::GigaChatClientBuilder;
+/// use gigachat_rust::generation::structures::Message;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let request = |text: &str| {
+///         client
+///             .generate()
+///             .with_messages(vec![Message::user(text)])
+///             .build()
+///     };
+///
+///     let handler = client
+///         .keyed_batch::<String>()
+///         .with_keyed_request("france".to_string(), request("Столица Франции?"))
+///         .with_keyed_request("jupiter".to_string(), request("Крупнейшая планета?"))
+///         .execute()
+///         .await
+///         .unwrap();
+///
+///     if let BatchCheckResult::Success { responses } = handler.check().await.unwrap() {
+///         println!("{:?}", responses["france"].as_ref().map(|r| r.text()));
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BatchBuilder<K = usize> {
     pub(crate) client: GigaChatClient,
-    pub(crate) requests: Vec<GenerationRequest>,
+    pub(crate) requests: Vec<(K, GenerationRequest)>,
+}
+
+/// Проверяет уникальность строковых представлений ключей.
+pub(crate) fn ensure_unique_keys<'k, I: IntoIterator<Item = &'k str>>(
+    keys: I,
+) -> Result<(), error::Error> {
+    let mut seen = HashSet::new();
+    for key in keys {
+        ensure!(seen.insert(key), error::DuplicateKeySnafu { key });
+    }
+    Ok(())
+}
+
+/// Создает JSONL представление пакета.
+pub(crate) fn serialize_batch_to_file<I: Serialize>(
+    items: Vec<I>,
+) -> Result<Vec<u8>, error::Error> {
+    let mut result = Vec::with_capacity(std::mem::size_of_val(items.as_slice()));
+    let mut writer = BufWriter::new(&mut result);
+    for item in items {
+        serde_json::to_writer(&mut writer, &item).context(error::BatchSerializationFailedSnafu)?;
+        writer
+            .write_all(b"\n")
+            .expect("vector write should always finish correctly");
+    }
+    drop(writer);
+    Ok(result)
+}
+
+/// Отправляет пакет и возвращает обработчик.
+pub(crate) async fn submit_batch<I: Serialize + Send + 'static, T, K>(
+    client: GigaChatClient,
+    method: Method,
+    items: Vec<I>,
+) -> Result<BatchHandler<T, K>, error::Error> {
+    // [`serialize_batch_to_file`] can block async runtime on large batches.
+    let batch_bytes = spawn_blocking(move || serialize_batch_to_file(items))
+        .await
+        .expect("failed to join blocking thread")?;
+    tracing::debug!("batch evaluated");
+
+    upload_batch(&client, method, batch_bytes).await
+}
+
+/// Загружает готовое JSONL представление пакета.
+#[tracing::instrument(
+    skip_all,
+    fields(url, batch.method = method.as_str(), batch.bytes = batch_bytes.len())
+)]
+pub(crate) async fn upload_batch<T, K>(
+    client: &GigaChatClient,
+    method: Method,
+    batch_bytes: Vec<u8>,
+) -> Result<BatchHandler<T, K>, error::Error> {
+    let url = client
+        .build_url("batches", [("method", method.as_str())].as_slice())
+        .context(error::BuildUrlSnafu)?;
+    Span::current().record("url", url.as_str());
+
+    client
+        .perform_request(
+            |c| {
+                c.post(url)
+                    .body(batch_bytes)
+                    .header("content-type", "application/octet-stream")
+            },
+            async |r| r.json::<BatchCreateResponse>().await,
+        )
+        .await
+        .context(error::BadRequestSnafu)
+        .map(|r| BatchHandler::new(client.clone(), r.id, r.method))
 }
 
 impl BatchBuilder {
-    /// Добавляет запрос в пакет.
+    /// Добавляет запрос в пакет; ключом служит порядковый номер запроса.
     pub fn with_request(mut self, request: GenerationRequest) -> Self {
-        self.requests.push(request);
+        self.requests.push((self.requests.len(), request));
         self
     }
 
-    /// Добавляет запросы в пакет.
+    /// Добавляет запросы в пакет; ключами служат порядковые номера запросов.
     pub fn with_requests(mut self, requests: Vec<GenerationRequest>) -> Self {
-        self.requests.extend_from_slice(&requests);
+        for request in requests {
+            self = self.with_request(request);
+        }
         self
     }
+}
 
-    /// Создает JSONL представление пакета.
-    fn serialize_batch_to_file(requests: Vec<GenerationRequest>) -> Result<Vec<u8>, error::Error> {
-        let mut result = Vec::with_capacity(std::mem::size_of_val(&requests));
-        let mut writer = BufWriter::new(&mut result);
-        for request in requests {
-            serde_json::to_writer(&mut writer, &request)
-                .context(error::BatchSerializationFailedSnafu)?;
-            writer
-                .write_all(b"\n")
-                .expect("vector write should always finish correctly");
-        }
-        drop(writer);
-        Ok(result)
+impl<K> BatchBuilder<K> {
+    /// Количество запросов в пакете.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+impl<K: BatchKey> BatchBuilder<K> {
+    /// Добавляет запрос с ключом в пакет.
+    pub fn with_keyed_request(mut self, key: K, request: GenerationRequest) -> Self {
+        self.requests.push((key, request));
+        self
+    }
+
+    /// Добавляет запросы с ключами в пакет.
+    pub fn with_keyed_requests<I: IntoIterator<Item = (K, GenerationRequest)>>(
+        mut self,
+        requests: I,
+    ) -> Self {
+        self.requests.extend(requests);
+        self
+    }
+
+    /// Преобразует запросы в строки пакета, проверяя уникальность ключей.
+    fn into_items(self) -> Result<(GigaChatClient, Vec<BatchGenerateRequestItem>), error::Error> {
+        let items: Vec<_> = self
+            .requests
+            .into_iter()
+            .map(|(key, request)| BatchGenerateRequestItem {
+                key: key.to_key(),
+                request,
+            })
+            .collect();
+        ensure_unique_keys(items.iter().map(|item| item.key.as_str()))?;
+        Ok((self.client, items))
     }
 
     /// Выполняет пакетный запрос.
-    #[tracing::instrument(skip_all, fields(
-        url,
-        batch.size = self.requests.len(),
-        batch.bytes,
-    ))]
-    pub async fn execute(self) -> Result<BatchHandler, error::Error> {
-        let url = self
-            .client
-            .build_url("batches", [("method", "chat_completions")].as_slice())
-            .context(error::BuildUrlSnafu)?;
-        Span::current().record("url", url.as_str());
-
-        // [`Self::serialize_batch_to_file`] can block async runtime on large batches.
-        let batch_bytes = spawn_blocking(move || Self::serialize_batch_to_file(self.requests))
-            .await
-            .expect("failed to join blocking thread")?;
-        Span::current().record("batch.bytes", batch_bytes.len());
-        tracing::debug!("batch evaluated");
-
-        self.client
-            .perform_request(
-                |c| {
-                    c.post(url)
-                        .body(batch_bytes)
-                        .header("content-type", "application/octet-stream")
-                },
-                async |r| r.json::<BatchCreateResponse>().await,
-            )
-            .await
-            .context(error::BadRequestSnafu)
-            .map(|r| BatchHandler::new(self.client.clone(), r.id))
+    ///
+    /// Возвращает [`error::Error::DuplicateKey`] до отправки пакета,
+    /// если ключи запросов повторяются.
+    #[tracing::instrument(skip_all, fields(batch.size = self.requests.len()))]
+    pub async fn execute(self) -> Result<BatchHandler<GenerationResponse, K>, error::Error> {
+        let (client, items) = self.into_items()?;
+        submit_batch(client, Method::ChatCompletions, items).await
+    }
+
+    /// Выполняет запросы несколькими пакетами, каждый из которых
+    /// укладывается в ограничения `limits`.
+    ///
+    /// Если отправка одного из пакетов не удалась после создания других,
+    /// возвращает [`error::Error::PartialSubmit`] с описаниями созданных пакетов.
+    #[tracing::instrument(skip_all, fields(batch.size = self.requests.len()))]
+    pub async fn execute_split(
+        self,
+        limits: BatchLimits,
+    ) -> Result<SplitBatchHandler<GenerationResponse, K>, error::Error> {
+        let (client, items) = self.into_items()?;
+        submit_split_batch(client, Method::ChatCompletions, items, limits).await
+    }
+
+    /// Создает сборщик из запросов, которые стоит отправить повторно.
+    ///
+    /// В него попадают запросы, завершившиеся ошибкой с
+    /// [`BatchItemError::is_retryable`](super::structures::BatchItemError::is_retryable)
+    /// (429 и 5xx) или отсутствующие в `responses`, с исходными ключами.
+    /// Результаты повторного пакета можно объединить с первыми через
+    /// `responses.extend(...)`.
+    pub fn retry_failed<T>(&self, responses: &HashMap<K, Result<T, BatchItemError>>) -> Self {
+        Self {
+            client: self.client.clone(),
+            requests: failed_requests(&self.requests, responses),
+        }
+    }
+
+    /// Выполняет пакет, дожидается результатов и повторяет неудавшиеся
+    /// запросы не более [`RetryConfig::max_rounds`] раз с паузой
+    /// [`RetryConfig::delay`].
+    ///
+    /// Повторно отправляются только неудавшиеся запросы. Если не удалась
+    /// отправка или ожидание повторного пакета, возвращаются накопленные
+    /// результаты; запросы без результата отмечаются ошибкой со статусом `0`.
+    #[tracing::instrument(skip_all, fields(batch.size = self.requests.len()), err)]
+    pub async fn execute_with_retries(
+        self,
+        config: RetryConfig,
+    ) -> Result<HashMap<K, Result<GenerationResponse, BatchItemError>>, error::Error> {
+        execute_with_retries(self.client, Method::ChatCompletions, self.requests, config).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generation::structures::Message, mock::MockServer};
+
+    #[test]
+    fn unique_keys_are_accepted() {
+        assert!(ensure_unique_keys(["0", "1", "2"]).is_ok());
+        assert!(ensure_unique_keys(Vec::<&str>::new()).is_ok());
+    }
+
+    #[test]
+    fn duplicate_key_is_rejected() {
+        let error = ensure_unique_keys(["a", "b", "a"]).unwrap_err();
+        assert!(matches!(error, error::Error::DuplicateKey { key } if key == "a"));
+    }
+
+    #[test]
+    fn batch_key_round_trip() {
+        for key in [0_usize, 7, usize::MAX] {
+            assert_eq!(usize::from_key(&key.to_key()), Some(key));
+        }
+        for key in ["france", "", "ключ с пробелами"] {
+            let key = key.to_string();
+            assert_eq!(String::from_key(&key.to_key()), Some(key));
+        }
+        assert_eq!(usize::from_key("france"), None);
+        assert_eq!(usize::from_key("-1"), None);
+    }
+
+    #[tokio::test]
+    async fn duplicate_key_is_rejected_before_upload() {
+        let server =
+            MockServer::start(|request| panic!("unexpected request {}", request.path)).await;
+        let client = server.client().await;
+        let request = |text: &str| {
+            client
+                .generate()
+                .with_messages(vec![Message::user(text)])
+                .build()
+        };
+
+        let result = client
+            .keyed_batch::<String>()
+            .with_keyed_request("a".to_string(), request("первый"))
+            .with_keyed_request("b".to_string(), request("второй"))
+            .with_keyed_request("a".to_string(), request("третий"))
+            .execute()
+            .await;
+
+        assert!(matches!(result, Err(error::Error::DuplicateKey { key }) if key == "a"));
+        assert!(server.requests().is_empty());
     }
 }
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use super::{
+    builder::{ensure_unique_keys, submit_batch},
+    error,
+    handler::BatchHandler,
+    retry::{RetryConfig, execute_with_retries, failed_requests},
+    split::{BatchLimits, SplitBatchHandler, submit_split_batch},
+    structures::{BatchEmbeddingRequestItem, BatchItemError, BatchKey, Method},
+};
+use crate::{
+    client::GigaChatClient,
+    embeddings::{
+        Model,
+        structures::{EmbeddingRequest, EmbeddingResponse, Input},
+    },
+};
+
+/// Сборщик пакетных запросов векторизации.
+///
+/// Пакетная обработка дешевле синхронных запросов и подходит для
+/// регулярной переиндексации, не требующей немедленного результата.
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::batch::handler::BatchCheckResult;
+/// use gigachat_rust::client::GigaChatClientBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let handler = client
+///         .embeddings_batch()
+///         .with_input("Первый абзац")
+///         .with_input("Второй абзац")
+///         .execute()
+///         .await
+///         .unwrap();
+///
+///     if let BatchCheckResult::Success { responses } = handler.check().await.unwrap() {
+///         for (index, response) in responses {
+///             println!("{index}: {:?}", response.map(|r| r.data.len()));
+///         }
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct EmbeddingsBatchBuilder<K = usize> {
+    client: GigaChatClient,
+    model: Model,
+    requests: Vec<(K, EmbeddingRequest)>,
+}
+
+impl<K> EmbeddingsBatchBuilder<K> {
+    /// Устанавливает модель для строк, добавленных после вызова.
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Количество запросов в пакете.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Пуст ли пакет.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+impl EmbeddingsBatchBuilder {
+    /// Добавляет в пакет запрос векторизации одной строки;
+    /// ключом служит порядковый номер запроса.
+    pub fn with_input<S: Into<String>>(self, input: S) -> Self {
+        let key = self.requests.len();
+        self.with_keyed_input(key, input)
+    }
+
+    /// Добавляет в пакет запросы векторизации строк, по одному на строку.
+    pub fn with_inputs<I: IntoIterator<Item = S>, S: Into<String>>(mut self, inputs: I) -> Self {
+        for input in inputs {
+            self = self.with_input(input);
+        }
+        self
+    }
+
+    /// Добавляет запрос в пакет; ключом служит порядковый номер запроса.
+    pub fn with_request(mut self, request: EmbeddingRequest) -> Self {
+        self.requests.push((self.requests.len(), request));
+        self
+    }
+
+    /// Добавляет запросы в пакет; ключами служат порядковые номера запросов.
+    pub fn with_requests(mut self, requests: Vec<EmbeddingRequest>) -> Self {
+        for request in requests {
+            self = self.with_request(request);
+        }
+        self
+    }
+}
+
+impl<K: BatchKey> EmbeddingsBatchBuilder<K> {
+    /// Добавляет в пакет запрос векторизации одной строки с ключом.
+    pub fn with_keyed_input<S: Into<String>>(mut self, key: K, input: S) -> Self {
+        let request = EmbeddingRequest {
+            model: self.model.clone(),
+            input: Input::One(input.into()),
+        };
+        self.requests.push((key, request));
+        self
+    }
+
+    /// Добавляет запрос с ключом в пакет.
+    pub fn with_keyed_request(mut self, key: K, request: EmbeddingRequest) -> Self {
+        self.requests.push((key, request));
+        self
+    }
+
+    /// Преобразует запросы в строки пакета, проверяя уникальность ключей.
+    fn into_items(self) -> Result<(GigaChatClient, Vec<BatchEmbeddingRequestItem>), error::Error> {
+        let items: Vec<_> = self
+            .requests
+            .into_iter()
+            .map(|(key, request)| BatchEmbeddingRequestItem {
+                key: key.to_key(),
+                request,
+            })
+            .collect();
+        ensure_unique_keys(items.iter().map(|item| item.key.as_str()))?;
+        Ok((self.client, items))
+    }
+
+    /// Выполняет пакетный запрос.
+    ///
+    /// Возвращает [`error::Error::DuplicateKey`] до отправки пакета,
+    /// если ключи запросов повторяются.
+    #[tracing::instrument(skip_all, fields(batch.size = self.requests.len()))]
+    pub async fn execute(self) -> Result<BatchHandler<EmbeddingResponse, K>, error::Error> {
+        let (client, items) = self.into_items()?;
+        submit_batch(client, Method::Embedder, items).await
+    }
+
+    /// Выполняет запросы несколькими пакетами; см. [`BatchBuilder::execute_split`](super::BatchBuilder::execute_split).
+    #[tracing::instrument(skip_all, fields(batch.size = self.requests.len()))]
+    pub async fn execute_split(
+        self,
+        limits: BatchLimits,
+    ) -> Result<SplitBatchHandler<EmbeddingResponse, K>, error::Error> {
+        let (client, items) = self.into_items()?;
+        submit_split_batch(client, Method::Embedder, items, limits).await
+    }
+
+    /// Создает сборщик из запросов, которые стоит отправить повторно;
+    /// см. [`BatchBuilder::retry_failed`](super::BatchBuilder::retry_failed).
+    pub fn retry_failed<T>(&self, responses: &HashMap<K, Result<T, BatchItemError>>) -> Self {
+        Self {
+            client: self.client.clone(),
+            model: self.model.clone(),
+            requests: failed_requests(&self.requests, responses),
+        }
+    }
+
+    /// Выполняет пакет с повтором неудавшихся запросов;
+    /// см. [`BatchBuilder::execute_with_retries`](super::BatchBuilder::execute_with_retries).
+    #[tracing::instrument(skip_all, fields(batch.size = self.requests.len()), err)]
+    pub async fn execute_with_retries(
+        self,
+        config: RetryConfig,
+    ) -> Result<HashMap<K, Result<EmbeddingResponse, BatchItemError>>, error::Error> {
+        execute_with_retries(self.client, Method::Embedder, self.requests, config).await
+    }
+}
+
+impl GigaChatClient {
+    /// Создает сборщик пакетных запросов векторизации.
+    pub fn embeddings_batch(&self) -> EmbeddingsBatchBuilder {
+        self.keyed_embeddings_batch()
+    }
+
+    /// Создает сборщик пакетных запросов векторизации с ключами типа `K`.
+    pub fn keyed_embeddings_batch<K: BatchKey>(&self) -> EmbeddingsBatchBuilder<K> {
+        EmbeddingsBatchBuilder {
+            client: self.clone(),
+            model: Model::default(),
+            requests: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        batch::handler::BatchCheckResult,
+        mock::{self, MockServer},
+    };
+
+    /// Сервер пакета `b1`, завершенного с выходным файлом `f1`.
+    async fn server() -> MockServer {
+        MockServer::start_raw(|request| match request.path.as_str() {
+            "/batches?method=embedder" => (
+                200,
+                mock::batch("b1", "embedder", "created", None).to_string(),
+            ),
+            "/batches?batch_id=b1" => (
+                200,
+                mock::batch("b1", "embedder", "completed", Some("f1")).to_string(),
+            ),
+            "/files/f1/content" => {
+                let output = mock::jsonl(&[
+                    json!({ "key": "first", "result": mock::embeddings(&[vec![1.0, 0.0]]) }),
+                    json!({ "key": "second", "error": { "status": 400, "message": "bad input" } }),
+                ]);
+                (200, output)
+            }
+            path => panic!("unexpected request {path}"),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn execute_uploads_embedder_batch() {
+        let server = server().await;
+
+        server
+            .client()
+            .await
+            .embeddings_batch()
+            .with_input("первый")
+            .with_model(Model::Embeddings)
+            .with_inputs(["второй", "третий"])
+            .execute()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/batches?method=embedder");
+        assert_eq!(
+            mock::jsonl_lines(&requests[0]),
+            [
+                json!({ "key": "0", "request": { "model": "EmbeddingsGigaR", "input": "первый" } }),
+                json!({ "key": "1", "request": { "model": "Embeddings", "input": "второй" } }),
+                json!({ "key": "2", "request": { "model": "Embeddings", "input": "третий" } }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn check_decodes_results_by_key() {
+        let server = server().await;
+
+        let handler = server
+            .client()
+            .await
+            .keyed_embeddings_batch::<String>()
+            .with_keyed_input("first".to_string(), "первый")
+            .with_keyed_input("second".to_string(), "")
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(handler.method(), Method::Embedder);
+
+        let BatchCheckResult::Success { responses } = handler.check().await.unwrap() else {
+            panic!("batch is not completed");
+        };
+        assert_eq!(responses.len(), 2);
+        let first = responses["first"].as_ref().unwrap();
+        assert_eq!(first.data[0].embedding, [1.0, 0.0]);
+        let second = responses["second"].as_ref().unwrap_err();
+        assert_eq!((second.status, second.message.as_str()), (400, "bad input"));
+    }
+
+    #[tokio::test]
+    async fn duplicate_key_is_rejected_before_upload() {
+        let server = server().await;
+
+        let result = server
+            .client()
+            .await
+            .keyed_embeddings_batch::<String>()
+            .with_keyed_input("a".to_string(), "первый")
+            .with_keyed_input("a".to_string(), "второй")
+            .execute()
+            .await;
+
+        assert!(matches!(result, Err(error::Error::DuplicateKey { key }) if key == "a"));
+        assert!(server.requests().is_empty());
+    }
+}
@@ -16,6 +16,72 @@ pub enum Error {
         source: crate::client::error::RequestError,
     },
 
+    #[snafu(display("duplicate request key '{key}'"))]
+    DuplicateKey { key: String },
+
+    #[snafu(display("batch output contains unparsable key '{key}'"))]
+    UnknownKey { key: String },
+
+    #[snafu(display("batch limits must be positive"))]
+    InvalidLimits,
+
+    #[snafu(display("request {index} takes {bytes} bytes, batch limit is {max_bytes}"))]
+    ItemTooLarge {
+        index: usize,
+        bytes: usize,
+        max_bytes: usize,
+    },
+
+    #[snafu(display("failed to submit batch after {} were created", submitted.len()))]
+    PartialSubmit {
+        /// Пакеты, созданные до ошибки.
+        submitted: Vec<super::structures::BatchDescriptor>,
+        #[snafu(source(from(Error, Box::new)))]
+        source: Box<Error>,
+    },
+
+    #[snafu(display("failed to read batch input file"))]
+    InputIo { source: std::io::Error },
+
+    #[snafu(display("failed to parse batch input line {line}"))]
+    InputParseFailed {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[snafu(display("batch input line {line} has unparsable key '{key}'"))]
+    InvalidInputKey { line: usize, key: String },
+
+    #[snafu(display("batch input contains no requests"))]
+    EmptyInput,
+
+    #[snafu(display("batch method is {actual:?}, expected {expected:?}"))]
+    MethodMismatch {
+        expected: super::structures::Method,
+        actual: super::structures::Method,
+    },
+
+    #[snafu(display("batch did not complete within {timeout:?}"))]
+    WaitTimedOut { timeout: std::time::Duration },
+
+    #[snafu(display("batch finished with status '{status}'"))]
+    BatchTerminated {
+        status: super::structures::Status,
+        failure: Option<super::structures::BatchFailure>,
+    },
+
+    #[snafu(display("completed batch has no output file"))]
+    OutputFileIsMissing,
+
+    #[snafu(display("failed to download batch output"))]
+    OutputDownloadFailed { source: reqwest::Error },
+
+    #[snafu(display("failed to parse batch output line {line}"))]
+    OutputParseFailed {
+        line: usize,
+        source: serde_json::Error,
+    },
+
     #[snafu(display("bad response from server"))]
     BadResponse {
         source: crate::client::CheckResponseError,
@@ -1,39 +1,156 @@
-use snafu::ResultExt;
+use futures::{Stream, StreamExt, stream};
+use serde::de::DeserializeOwned;
+use snafu::{OptionExt, ResultExt};
+use std::{collections::HashMap, marker::PhantomData, ops::Range, pin::Pin};
+use tokio::task::spawn_blocking;
 use tracing::Span;
 
 use super::{
     error,
-    structures::{BatchCheckResponse, BatchResponseResult},
+    structures::{
+        BatchCheckResponse, BatchDescriptor, BatchFailure, BatchItemError, BatchKey,
+        BatchResponseItem, BatchResponseResult, Method,
+    },
 };
-use crate::client::GigaChatClient;
+use crate::{client::GigaChatClient, generation::structures::GenerationResponse};
 
 use super::structures::Status;
 
-pub struct BatchHandler {
+/// Обработчик пакетного запроса.
+///
+/// Параметр `T` — тип ответа на отдельный запрос пакета:
+/// [`GenerationResponse`] для генерации или
+/// [`EmbeddingResponse`](crate::embeddings::structures::EmbeddingResponse)
+/// для векторизации. Параметр `K` — тип ключа запроса (см. [`BatchKey`]);
+/// для запросов без явного ключа это их порядковый номер в пакете.
+///
+/// Обработчик можно сохранить через [`BatchHandler::descriptor`] и
+/// восстановить после перезапуска через [`BatchDescriptor::attach`]
+/// или [`GigaChatClient::batch_by_id`].
+pub struct BatchHandler<T = GenerationResponse, K = usize> {
     client: GigaChatClient,
     id: String,
+    method: Method,
+    response: PhantomData<fn() -> (T, K)>,
 }
 
-pub enum BatchCheckResult {
+#[derive(Debug, Clone)]
+pub enum BatchCheckResult<T = GenerationResponse, K = usize> {
     Pending,
-    Success { responses: Vec<BatchResponseResult> },
-    InProgress { ready: usize, total: usize },
+    /// Результаты по ключам запросов.
+    Success {
+        responses: HashMap<K, Result<T, BatchItemError>>,
+    },
+    InProgress {
+        ready: usize,
+        total: usize,
+    },
+    /// Пакет завершился ошибкой; `responses` содержит результаты запросов,
+    /// обработанных до нее.
+    Failed {
+        error: Option<BatchFailure>,
+        responses: HashMap<K, Result<T, BatchItemError>>,
+    },
+    /// Пакет не был обработан за отведенное время; `responses` содержит
+    /// результаты запросов, обработанных до истечения срока.
+    Expired {
+        error: Option<BatchFailure>,
+        responses: HashMap<K, Result<T, BatchItemError>>,
+    },
+    /// Пакет отменен; `responses` содержит результаты запросов,
+    /// обработанных до отмены.
+    Cancelled {
+        responses: HashMap<K, Result<T, BatchItemError>>,
+    },
 }
 
-impl BatchHandler {
-    pub fn new(client: GigaChatClient, id: String) -> Self {
-        Self { client, id }
+impl<T, K> BatchCheckResult<T, K> {
+    /// Завершена ли обработка пакета, успешно или нет.
+    pub fn is_terminal(&self) -> bool {
+        !matches!(
+            self,
+            BatchCheckResult::Pending | BatchCheckResult::InProgress { .. }
+        )
+    }
+
+    /// Результаты завершенного пакета, в том числе частичные.
+    pub fn responses(&self) -> Option<&HashMap<K, Result<T, BatchItemError>>> {
+        match self {
+            BatchCheckResult::Pending | BatchCheckResult::InProgress { .. } => None,
+            BatchCheckResult::Success { responses }
+            | BatchCheckResult::Failed { responses, .. }
+            | BatchCheckResult::Expired { responses, .. }
+            | BatchCheckResult::Cancelled { responses } => Some(responses),
+        }
+    }
+
+    /// Изменяемые результаты завершенного пакета.
+    pub fn responses_mut(&mut self) -> Option<&mut HashMap<K, Result<T, BatchItemError>>> {
+        match self {
+            BatchCheckResult::Pending | BatchCheckResult::InProgress { .. } => None,
+            BatchCheckResult::Success { responses }
+            | BatchCheckResult::Failed { responses, .. }
+            | BatchCheckResult::Expired { responses, .. }
+            | BatchCheckResult::Cancelled { responses } => Some(responses),
+        }
+    }
+
+    /// Статус, соответствующий результату проверки.
+    ///
+    /// Для [`BatchCheckResult::Pending`] возвращает `None`: ожидание
+    /// объединяет несколько статусов API (`created`, `validating`), точный
+    /// статус доступен через [`BatchHandler::info`].
+    pub fn status(&self) -> Option<Status> {
+        match self {
+            BatchCheckResult::Pending => None,
+            BatchCheckResult::InProgress { .. } => Some(Status::InProgress),
+            BatchCheckResult::Success { .. } => Some(Status::Completed),
+            BatchCheckResult::Failed { .. } => Some(Status::Failed),
+            BatchCheckResult::Expired { .. } => Some(Status::Expired),
+            BatchCheckResult::Cancelled { .. } => Some(Status::Cancelled),
+        }
+    }
+}
+
+impl<T, K> BatchHandler<T, K> {
+    pub fn new(client: GigaChatClient, id: String, method: Method) -> Self {
+        Self {
+            client,
+            id,
+            method,
+            response: PhantomData,
+        }
     }
 
     pub fn id(&self) -> &str {
         &self.id
     }
 
-    #[tracing::instrument(skip_all, fields(url))]
-    async fn check_request(&self) -> Result<BatchCheckResponse, error::Error> {
+    pub fn method(&self) -> Method {
+        self.method
+    }
+
+    /// Возвращает описание пакета для сохранения.
+    pub fn descriptor(&self) -> BatchDescriptor {
+        BatchDescriptor {
+            id: self.id.clone(),
+            method: self.method,
+        }
+    }
+
+    /// Запрашивает текущее состояние пакета.
+    pub async fn info(&self) -> Result<BatchCheckResponse, error::Error> {
+        fetch_batch(&self.client, &self.id).await
+    }
+
+    /// Отменяет пакет.
+    ///
+    /// Запросы, выполненные до отмены, остаются в выходном файле.
+    #[tracing::instrument(skip_all, fields(url, batch.id = self.id), err)]
+    pub async fn cancel(&self) -> Result<BatchCheckResponse, error::Error> {
         let url = self
             .client
-            .build_url("batches", [("batch_id", self.id.as_str())].as_slice())
+            .build_url(&format!("batches/{}/cancel", self.id), None)
             .context(error::BuildUrlSnafu)?;
         Span::current().record("url", url.as_str());
 
@@ -43,17 +160,366 @@ impl BatchHandler {
             .context(error::BadRequestSnafu)
     }
 
-    #[tracing::instrument(skip_all)]
-    pub async fn check(&self) -> Result<BatchCheckResult, error::Error> {
+    async fn check_request(&self) -> Result<BatchCheckResponse, error::Error> {
+        fetch_batch(&self.client, &self.id).await
+    }
+
+    #[tracing::instrument(skip_all, fields(url, batch.output_bytes))]
+    async fn download_output(&self, file_id: &str) -> Result<Vec<u8>, error::Error> {
+        let url = self
+            .client
+            .build_url(&format!("files/{file_id}/content"), None)
+            .context(error::BuildUrlSnafu)?;
+        Span::current().record("url", url.as_str());
+
+        let output = self
+            .client
+            .perform_request(|c| c.get(url), async |r| r.bytes().await.map(Vec::from))
+            .await
+            .context(error::BadRequestSnafu)?;
+        Span::current().record("batch.output_bytes", output.len());
+        Ok(output)
+    }
+}
+
+/// Запрашивает состояние пакета по идентификатору.
+#[tracing::instrument(skip_all, fields(url))]
+async fn fetch_batch(
+    client: &GigaChatClient,
+    id: &str,
+) -> Result<BatchCheckResponse, error::Error> {
+    let url = client
+        .build_url("batches", [("batch_id", id)].as_slice())
+        .context(error::BuildUrlSnafu)?;
+    Span::current().record("url", url.as_str());
+
+    client
+        .perform_request(|c| c.post(url), async |r| r.json().await)
+        .await
+        .context(error::BadRequestSnafu)
+}
+
+impl BatchDescriptor {
+    /// Создает обработчик сохраненного пакета без обращения к API.
+    ///
+    /// Тип ответа `T` должен соответствовать методу пакета:
+    /// [`GenerationResponse`] для [`Method::ChatCompletions`] и
+    /// [`EmbeddingResponse`](crate::embeddings::structures::EmbeddingResponse)
+    /// для [`Method::Embedder`].
+    pub fn attach<T, K>(self, client: &GigaChatClient) -> BatchHandler<T, K> {
+        BatchHandler::new(client.clone(), self.id, self.method)
+    }
+}
+
+impl GigaChatClient {
+    /// Создает обработчик существующего пакета по идентификатору.
+    ///
+    /// Проверяет, что пакет существует, и возвращает
+    /// [`error::Error::MethodMismatch`], если его метод отличается от `method`.
+    #[tracing::instrument(skip_all, fields(batch.id = id), err)]
+    pub async fn batch_by_id<T, K>(
+        &self,
+        id: &str,
+        method: Method,
+    ) -> Result<BatchHandler<T, K>, error::Error> {
+        let info = fetch_batch(self, id).await?;
+        snafu::ensure!(
+            info.method == method,
+            error::MethodMismatchSnafu {
+                expected: method,
+                actual: info.method,
+            }
+        );
+        Ok(BatchHandler::new(self.clone(), info.id, info.method))
+    }
+}
+
+/// Построчное чтение выходного файла по мере загрузки.
+struct OutputLines<S> {
+    body: Pin<Box<S>>,
+    buffer: Vec<u8>,
+    /// Начало неразобранной части буфера.
+    start: usize,
+    /// Позиция, до которой буфер уже просмотрен в поисках перевода строки.
+    scanned: usize,
+    number: usize,
+    finished: bool,
+}
+
+impl<S, B, E> OutputLines<S>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+{
+    fn new(body: S) -> Self {
+        Self {
+            body: Box::pin(body),
+            buffer: Vec::new(),
+            start: 0,
+            scanned: 0,
+            number: 0,
+            finished: false,
+        }
+    }
+
+    /// Возвращает номер и диапазон в буфере следующей непустой строки.
+    ///
+    /// Ошибка загрузки завершает чтение.
+    async fn next_line(&mut self) -> Option<Result<(usize, Range<usize>), E>> {
+        loop {
+            if let Some(offset) = self.buffer[self.scanned..].iter().position(|&b| b == b'\n') {
+                let line = self.start..self.scanned + offset;
+                self.start = line.end + 1;
+                self.scanned = self.start;
+                self.number += 1;
+                if self.buffer[line.clone()].trim_ascii().is_empty() {
+                    continue;
+                }
+                return Some(Ok((self.number, line)));
+            }
+            self.scanned = self.buffer.len();
+
+            if self.finished {
+                // The last line may have no trailing newline.
+                let line = self.start..self.buffer.len();
+                self.start = line.end;
+                if self.buffer[line.clone()].trim_ascii().is_empty() {
+                    return None;
+                }
+                self.number += 1;
+                return Some(Ok((self.number, line)));
+            }
+
+            match self.body.next().await {
+                Some(Ok(chunk)) => {
+                    // Разобранные строки удаляются только перед добавлением данных,
+                    // поэтому каждый байт сдвигается не более одного раза.
+                    self.buffer.drain(..self.start);
+                    self.scanned -= self.start;
+                    self.start = 0;
+                    self.buffer.extend_from_slice(chunk.as_ref());
+                }
+                Some(Err(e)) => {
+                    self.finished = true;
+                    self.buffer.clear();
+                    self.start = 0;
+                    self.scanned = 0;
+                    return Some(Err(e));
+                }
+                None => self.finished = true,
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Send + 'static, K: BatchKey> BatchHandler<T, K> {
+    /// Разбирает выходной файл пакета в формате JSONL.
+    fn parse_output(output: &[u8]) -> Result<HashMap<K, Result<T, BatchItemError>>, error::Error> {
+        output
+            .split(|&b| b == b'\n')
+            .enumerate()
+            .filter(|(_, line)| !line.trim_ascii().is_empty())
+            .map(|(index, line)| {
+                let (key, result) = Self::parse_output_line(line, index + 1)?;
+                Ok((key, result.res()))
+            })
+            .collect()
+    }
+
+    /// Разбирает строку выходного файла с номером `number`.
+    fn parse_output_line(
+        line: &[u8],
+        number: usize,
+    ) -> Result<(K, BatchResponseResult<T>), error::Error> {
+        let item = serde_json::from_slice::<BatchResponseItem<T>>(line)
+            .context(error::OutputParseFailedSnafu { line: number })?;
+        let key = K::from_key(&item.key).context(error::UnknownKeySnafu { key: item.key })?;
+        Ok((key, item.result))
+    }
+
+    /// Поток результатов из выходного файла завершенного пакета.
+    ///
+    /// Файл разбирается по строкам по мере загрузки, не сохраняясь в памяти
+    /// целиком. Ошибка разбора строки возвращается элементом потока и не
+    /// прерывает его; ошибка загрузки завершает поток.
+    ///
+    /// Возвращает [`error::Error::OutputFileIsMissing`], если у пакета нет
+    /// выходного файла.
+    ///
+    /// ## Пример
+    ///
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use gigachat_rust::client::GigaChatClientBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let handler = client.embeddings_batch().with_input("Текст").execute().await.unwrap();
+    ///
+    ///     let mut output = std::pin::pin!(handler.output_stream().await.unwrap());
+    ///     while let Some(item) = output.next().await {
+    ///         match item {
+    ///             Ok((key, result)) => println!("{key}: {:?}", result.res().map(|r| r.data.len())),
+    ///             Err(e) => eprintln!("skipped line: {e}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[tracing::instrument(skip_all, fields(url, batch.id = self.id), err)]
+    pub async fn output_stream(
+        &self,
+    ) -> Result<impl Stream<Item = Result<(K, BatchResponseResult<T>), error::Error>>, error::Error>
+    {
+        let file_id = self
+            .check_request()
+            .await?
+            .output_file_id
+            .context(error::OutputFileIsMissingSnafu)?;
+        let url = self
+            .client
+            .build_url(&format!("files/{file_id}/content"), None)
+            .context(error::BuildUrlSnafu)?;
+        Span::current().record("url", url.as_str());
+
+        let body = self
+            .client
+            .perform_request(|c| c.get(url), async |r| Ok(r.bytes_stream()))
+            .await
+            .context(error::BadRequestSnafu)?;
+
+        Ok(stream::unfold(
+            OutputLines::new(body),
+            |mut lines| async move {
+                let item = match lines.next_line().await? {
+                    Ok((number, line)) => Self::parse_output_line(&lines.buffer[line], number),
+                    Err(e) => Err(e).context(error::OutputDownloadFailedSnafu),
+                };
+                Some((item, lines))
+            },
+        ))
+    }
+
+    /// Загружает и разбирает выходной файл, если он есть.
+    async fn fetch_responses(
+        &self,
+        file_id: Option<String>,
+    ) -> Result<HashMap<K, Result<T, BatchItemError>>, error::Error> {
+        let Some(file_id) = file_id else {
+            return Ok(HashMap::new());
+        };
+        let output = self.download_output(&file_id).await?;
+
+        // Large outputs can block async runtime while parsing.
+        spawn_blocking(move || Self::parse_output(&output))
+            .await
+            .expect("failed to join blocking thread")
+    }
+
+    #[tracing::instrument(skip_all, fields(batch.status))]
+    pub async fn check(&self) -> Result<BatchCheckResult<T, K>, error::Error> {
         let check_response = self.check_request().await?;
+        Span::current().record("batch.status", check_response.status.as_str());
+
+        let in_progress = BatchCheckResult::InProgress {
+            ready: check_response.request_counts.completed + check_response.request_counts.failed,
+            total: check_response.request_counts.total,
+        };
         Ok(match check_response.status {
-            Status::Created => BatchCheckResult::Pending,
-            Status::InProgress => BatchCheckResult::InProgress {
-                ready: check_response.request_counts.completed
-                    + check_response.request_counts.failed,
-                total: check_response.request_counts.total,
+            Status::Created | Status::Validating => BatchCheckResult::Pending,
+            Status::InProgress | Status::Finalizing | Status::Cancelling => in_progress,
+            Status::Completed => {
+                let file_id = check_response
+                    .output_file_id
+                    .context(error::OutputFileIsMissingSnafu)?;
+                let responses = self.fetch_responses(Some(file_id)).await?;
+                BatchCheckResult::Success { responses }
+            }
+            Status::Failed => BatchCheckResult::Failed {
+                error: check_response.error,
+                responses: self.fetch_responses(check_response.output_file_id).await?,
+            },
+            Status::Expired => BatchCheckResult::Expired {
+                error: check_response.error,
+                responses: self.fetch_responses(check_response.output_file_id).await?,
+            },
+            Status::Cancelled => BatchCheckResult::Cancelled {
+                responses: self.fetch_responses(check_response.output_file_id).await?,
             },
-            Status::Completed => todo!(),
+            Status::Unknown(status) => {
+                tracing::warn!(
+                    batch.status = status,
+                    "unknown batch status, treating as in progress"
+                );
+                in_progress
+            }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn lines(chunks: &[&str]) -> Vec<(usize, String)> {
+        let body = stream::iter(chunks.iter().map(|chunk| Ok::<_, ()>(chunk.as_bytes())));
+        let mut lines = OutputLines::new(body);
+        let mut result = Vec::new();
+        while let Some(line) = lines.next_line().await {
+            let (number, range) = line.unwrap();
+            result.push((
+                number,
+                String::from_utf8(lines.buffer[range].to_vec()).unwrap(),
+            ));
+        }
+        result
+    }
+
+    #[tokio::test]
+    async fn lines_across_chunks() {
+        let lines = lines(&["{\"a\"", ":1}\n{\"b\":2}\n\n{\"c", "\":3}"]).await;
+
+        assert_eq!(
+            lines,
+            [
+                (1, r#"{"a":1}"#.to_string()),
+                (2, r#"{"b":2}"#.to_string()),
+                (4, r#"{"c":3}"#.to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn long_line_in_small_chunks() {
+        let line = "x".repeat(10_000);
+        let chunks: Vec<_> = line
+            .as_bytes()
+            .chunks(7)
+            .map(|c| std::str::from_utf8(c).unwrap())
+            .collect();
+        let mut chunks = chunks;
+        chunks.push("\n");
+
+        assert_eq!(lines(&chunks).await, [(1, line)]);
+    }
+
+    #[tokio::test]
+    async fn empty_body() {
+        assert!(lines(&[]).await.is_empty());
+        assert!(lines(&["\n \n"]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn download_error_ends_lines() {
+        let body = stream::iter([Ok(&b"one\ntw"[..]), Err(()), Ok(&b"o\n"[..])]);
+        let mut lines = OutputLines::new(body);
+
+        assert!(matches!(lines.next_line().await, Some(Ok((1, _)))));
+        assert!(matches!(lines.next_line().await, Some(Err(()))));
+        assert!(lines.next_line().await.is_none());
+    }
+}
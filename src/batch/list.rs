@@ -0,0 +1,249 @@
+use futures::{Stream, TryStreamExt, stream};
+use snafu::ResultExt;
+use tracing::Span;
+
+use super::{
+    error,
+    structures::{BatchCheckResponse, BatchListResponse, Method, Status},
+};
+use crate::client::GigaChatClient;
+
+/// Сборщик запроса списка пакетов.
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use futures::TryStreamExt;
+/// use gigachat_rust::batch::structures::{Method, Status};
+/// use gigachat_rust::client::GigaChatClientBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let in_progress: Vec<_> = client
+///         .batches()
+///         .with_status(Status::InProgress)
+///         .with_method(Method::Embedder)
+///         .into_stream()
+///         .try_collect()
+///         .await
+///         .unwrap();
+///
+///     for batch in in_progress {
+///         println!("{}: {}/{}", batch.id, batch.request_counts.completed, batch.request_counts.total);
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct BatchListBuilder {
+    client: GigaChatClient,
+    statuses: Vec<Status>,
+    method: Option<Method>,
+    limit: Option<usize>,
+    after: Option<String>,
+}
+
+impl BatchListBuilder {
+    /// Оставляет пакеты с указанным статусом; можно вызвать несколько раз.
+    pub fn with_status(mut self, status: Status) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    /// Оставляет пакеты с указанным методом.
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// Устанавливает размер страницы.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Начинает страницу после пакета с указанным идентификатором.
+    pub fn after<S: Into<String>>(mut self, id: S) -> Self {
+        self.after = Some(id.into());
+        self
+    }
+
+    /// Запрашивает одну страницу списка.
+    ///
+    /// Следующая страница запрашивается через [`BatchListBuilder::after`]
+    /// с идентификатором [`BatchListResponse::next_after`], пока он задан.
+    /// Если сервер не передает `has_more`, страница считается не последней,
+    /// когда она заполнена до [`BatchListBuilder::with_limit`].
+    #[tracing::instrument(skip_all, fields(url, batch.count), err)]
+    pub async fn execute(&self) -> Result<BatchListResponse, error::Error> {
+        let limit = self.limit.map(|limit| limit.to_string());
+        let mut queries = Vec::new();
+        for status in &self.statuses {
+            queries.push(("status", status.as_str()));
+        }
+        if let Some(method) = &self.method {
+            queries.push(("method", method.as_str()));
+        }
+        if let Some(limit) = &limit {
+            queries.push(("limit", limit.as_str()));
+        }
+        if let Some(after) = &self.after {
+            queries.push(("after", after.as_str()));
+        }
+
+        let url = self
+            .client
+            .build_url("batches", queries.as_slice())
+            .context(error::BuildUrlSnafu)?;
+        Span::current().record("url", url.as_str());
+
+        let mut response: BatchListResponse = self
+            .client
+            .perform_request(|c| c.get(url), async |r| r.json().await)
+            .await
+            .context(error::BadRequestSnafu)?;
+
+        // Без поля `has_more` следующая страница запрашивается, если текущая заполнена.
+        let page_full = self
+            .limit
+            .is_some_and(|limit| response.batches.len() >= limit);
+        if response.has_more.unwrap_or(page_full) {
+            response.next_after = response.batches.last().map(|batch| batch.id.clone());
+        }
+
+        // Фильтры применяются и локально на случай, если сервер их не поддерживает.
+        response.batches.retain(|batch| {
+            (self.statuses.is_empty() || self.statuses.contains(&batch.status))
+                && self.method.is_none_or(|method| batch.method == method)
+        });
+        Span::current().record("batch.count", response.batches.len());
+        Ok(response)
+    }
+
+    /// Возвращает поток всех пакетов, запрашивая страницы по мере чтения.
+    pub fn into_stream(self) -> impl Stream<Item = Result<BatchCheckResponse, error::Error>> {
+        let pages = stream::try_unfold(Some(self), async |builder| {
+            let Some(builder) = builder else {
+                return Ok(None);
+            };
+            let page = builder.execute().await?;
+            let next = page.next_after.map(|id| builder.after(id));
+            Ok(Some((
+                stream::iter(page.batches.into_iter().map(Ok::<_, error::Error>)),
+                next,
+            )))
+        });
+        pages.try_flatten()
+    }
+}
+
+impl GigaChatClient {
+    /// Создает запрос списка пакетов.
+    pub fn batches(&self) -> BatchListBuilder {
+        BatchListBuilder {
+            client: self.clone(),
+            statuses: Vec::new(),
+            method: None,
+            limit: None,
+            after: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::mock::MockServer;
+
+    fn batch(id: &str, status: &str) -> Value {
+        json!({
+            "id": id,
+            "method": "chat_completions",
+            "request_counts": { "total": 1, "completed": 0, "failed": 0 },
+            "status": status,
+            "output_file_id": null,
+            "created_at": 1_700_000_000_000_i64,
+            "updated_at": 1_700_000_000_000_i64,
+        })
+    }
+
+    fn query_after(path: &str) -> Option<&str> {
+        path.split(['?', '&'])
+            .find_map(|pair| pair.strip_prefix("after="))
+    }
+
+    #[tokio::test]
+    async fn cursor_is_taken_before_local_filter() {
+        let server = MockServer::start(|request| {
+            let page = match query_after(&request.path) {
+                None => json!({
+                    "batches": [batch("b1", "completed"), batch("b2", "in_progress")],
+                    "has_more": true,
+                }),
+                Some("b2") => json!({
+                    "batches": [batch("b3", "in_progress"), batch("b4", "completed")],
+                }),
+                Some("b4") => json!({ "batches": [batch("b5", "completed")] }),
+                Some(other) => panic!("unexpected cursor {other}"),
+            };
+            (200, page)
+        })
+        .await;
+
+        let batches: Vec<_> = server
+            .client()
+            .await
+            .batches()
+            .with_status(Status::Completed)
+            .with_limit(2)
+            .into_stream()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let ids: Vec<_> = batches.iter().map(|batch| batch.id.as_str()).collect();
+        assert_eq!(ids, ["b1", "b4", "b5"]);
+        let cursors: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|request| query_after(&request.path).map(str::to_string))
+            .collect();
+        assert_eq!(
+            cursors,
+            [None, Some("b2".to_string()), Some("b4".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn explicit_has_more_wins_over_page_size() {
+        let server = MockServer::start(|_| {
+            let page = json!({
+                "batches": [batch("b1", "completed"), batch("b2", "completed")],
+                "has_more": false,
+            });
+            (200, page)
+        })
+        .await;
+
+        let page = server
+            .client()
+            .await
+            .batches()
+            .with_status(Status::InProgress)
+            .with_limit(2)
+            .execute()
+            .await
+            .unwrap();
+
+        assert!(page.batches.is_empty());
+        assert_eq!(page.has_more, Some(false));
+        assert_eq!(page.next_after, None);
+    }
+}
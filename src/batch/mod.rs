@@ -1,14 +1,33 @@
 use crate::client::GigaChatClient;
+use structures::BatchKey;
 
 mod builder;
 pub use builder::*;
+mod embeddings;
+pub use embeddings::*;
 pub mod error;
 pub mod handler;
+mod list;
+pub use list::*;
+mod polling;
+pub use polling::PollingConfig;
+mod report;
+pub use report::*;
+mod retry;
+pub use retry::RetryConfig;
+mod split;
+pub use split::{BatchLimits, SplitBatchHandler};
 pub mod structures;
+mod upload;
 
 impl GigaChatClient {
     /// Cоздает сборщик пакетных запросов.
     pub fn batch(&self) -> BatchBuilder {
+        self.keyed_batch()
+    }
+
+    /// Создает сборщик пакетных запросов с ключами типа `K`.
+    pub fn keyed_batch<K: BatchKey>(&self) -> BatchBuilder<K> {
         BatchBuilder {
             client: self.clone(),
             requests: Vec::new(),
@@ -0,0 +1,416 @@
+use std::{collections::HashMap, time::Duration};
+
+use futures::{Stream, StreamExt, stream};
+use serde::de::DeserializeOwned;
+use tokio::time::{Instant, sleep};
+
+use super::{
+    error,
+    handler::{BatchCheckResult, BatchHandler},
+    structures::{BatchItemError, BatchKey, Status},
+};
+
+/// Параметры ожидания завершения пакета.
+#[derive(Debug, Clone)]
+pub struct PollingConfig {
+    /// Интервал между первыми проверками состояния.
+    pub initial_interval: Duration,
+    /// Максимальный интервал между проверками.
+    pub max_interval: Duration,
+    /// Множитель интервала для проверок, не обнаруживших изменений.
+    ///
+    /// При изменении состояния пакета интервал сбрасывается до начального.
+    /// Значения меньше `1.0` считаются равными `1.0`, а бесконечные
+    /// и `NaN` — отключают увеличение интервала.
+    pub multiplier: f64,
+    /// Общее время ожидания; `None` — без ограничения.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(60),
+            multiplier: 1.5,
+            timeout: None,
+        }
+    }
+}
+
+impl PollingConfig {
+    /// Интервал после проверки, не обнаружившей изменений.
+    fn next_interval(&self, interval: Duration) -> Duration {
+        let multiplier = if self.multiplier.is_finite() {
+            self.multiplier.max(1.0)
+        } else {
+            1.0
+        };
+        let interval = interval.min(self.max_interval).as_secs_f64() * multiplier;
+        Duration::try_from_secs_f64(interval)
+            .unwrap_or(self.max_interval)
+            .min(self.max_interval)
+    }
+}
+
+/// Снимок состояния для обнаружения изменений.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Progress {
+    Pending,
+    InProgress { ready: usize, total: usize },
+}
+
+struct PollState {
+    interval: Duration,
+    started: Instant,
+    last: Option<Progress>,
+    first: bool,
+    finished: bool,
+}
+
+/// Поток изменений состояния, получаемых вызовами `check`.
+///
+/// Общая реализация для [`BatchHandler::progress`] и составных обработчиков.
+pub(crate) fn poll_progress<'a, T, K, F, Fut>(
+    check: F,
+    config: PollingConfig,
+) -> impl Stream<Item = Result<BatchCheckResult<T, K>, error::Error>> + 'a
+where
+    T: 'a,
+    K: 'a,
+    F: Fn() -> Fut + 'a,
+    Fut: Future<Output = Result<BatchCheckResult<T, K>, error::Error>> + 'a,
+{
+    let state = PollState {
+        interval: config.initial_interval,
+        started: Instant::now(),
+        last: None,
+        first: true,
+        finished: false,
+    };
+
+    stream::unfold((state, check), move |(mut state, check)| {
+        let config = config.clone();
+        async move {
+            loop {
+                if state.finished {
+                    return None;
+                }
+
+                if !state.first {
+                    let elapsed = state.started.elapsed();
+                    let mut delay = state.interval;
+                    if let Some(timeout) = config.timeout {
+                        if elapsed >= timeout {
+                            state.finished = true;
+                            let error = error::WaitTimedOutSnafu { timeout }.build();
+                            return Some((Err(error), (state, check)));
+                        }
+                        delay = delay.min(timeout - elapsed);
+                    }
+                    sleep(delay).await;
+                }
+                state.first = false;
+
+                let progress = match check().await {
+                    Ok(BatchCheckResult::Pending) => Progress::Pending,
+                    Ok(BatchCheckResult::InProgress { ready, total }) => {
+                        Progress::InProgress { ready, total }
+                    }
+                    Ok(finished) => {
+                        state.finished = true;
+                        return Some((Ok(finished), (state, check)));
+                    }
+                    Err(e) => {
+                        state.finished = true;
+                        return Some((Err(e), (state, check)));
+                    }
+                };
+
+                if state.last == Some(progress) {
+                    state.interval = config.next_interval(state.interval);
+                    continue;
+                }
+
+                tracing::debug!(?progress, "batch progress changed");
+                state.last = Some(progress);
+                state.interval = config.initial_interval;
+                let result = match progress {
+                    Progress::Pending => BatchCheckResult::Pending,
+                    Progress::InProgress { ready, total } => {
+                        BatchCheckResult::InProgress { ready, total }
+                    }
+                };
+                return Some((Ok(result), (state, check)));
+            }
+        }
+    })
+}
+
+/// Дожидается итогового состояния из потока изменений состояния.
+pub(crate) async fn wait_finished<T, K>(
+    progress: impl Stream<Item = Result<BatchCheckResult<T, K>, error::Error>>,
+) -> Result<BatchCheckResult<T, K>, error::Error> {
+    let mut progress = std::pin::pin!(progress);
+    while let Some(state) = progress.next().await {
+        let state = state?;
+        if state.is_terminal() {
+            return Ok(state);
+        }
+    }
+    unreachable!("progress stream always ends with a terminal state or an error")
+}
+
+/// Дожидается результатов успешно завершенного пакета.
+pub(crate) async fn wait_for<T, K>(
+    progress: impl Stream<Item = Result<BatchCheckResult<T, K>, error::Error>>,
+) -> Result<HashMap<K, Result<T, BatchItemError>>, error::Error> {
+    match wait_finished(progress).await? {
+        BatchCheckResult::Success { responses } => Ok(responses),
+        BatchCheckResult::Failed { error, .. } => error::BatchTerminatedSnafu {
+            status: Status::Failed,
+            failure: error,
+        }
+        .fail(),
+        BatchCheckResult::Expired { error, .. } => error::BatchTerminatedSnafu {
+            status: Status::Expired,
+            failure: error,
+        }
+        .fail(),
+        BatchCheckResult::Cancelled { .. } => error::BatchTerminatedSnafu {
+            status: Status::Cancelled,
+            failure: None,
+        }
+        .fail(),
+        BatchCheckResult::Pending | BatchCheckResult::InProgress { .. } => {
+            unreachable!("wait_finished returns only terminal states")
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Send + 'static, K: BatchKey> BatchHandler<T, K> {
+    /// Поток изменений состояния пакета.
+    ///
+    /// Возвращает состояние при каждом его изменении: ожидание, выполнение
+    /// с количеством готовых запросов и, последним элементом, итоговое
+    /// состояние с результатами (см. [`BatchCheckResult::is_terminal`]).
+    /// Поток завершается после итогового состояния или первой ошибки, в том числе
+    /// [`error::Error::WaitTimedOut`] по истечении
+    /// [`PollingConfig::timeout`].
+    ///
+    /// ## Пример
+    ///
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use gigachat_rust::batch::{PollingConfig, handler::BatchCheckResult};
+    /// use gigachat_rust::client::GigaChatClientBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let handler = client.embeddings_batch().with_input("Текст").execute().await.unwrap();
+    ///
+    ///     let mut progress = std::pin::pin!(handler.progress(PollingConfig::default()));
+    ///     while let Some(state) = progress.next().await {
+    ///         match state.unwrap() {
+    ///             BatchCheckResult::Pending => println!("pending"),
+    ///             BatchCheckResult::InProgress { ready, total } => println!("{ready}/{total}"),
+    ///             BatchCheckResult::Success { responses } => println!("{} done", responses.len()),
+    ///             finished => println!("finished with status {:?}", finished.status()),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn progress(
+        &self,
+        config: PollingConfig,
+    ) -> impl Stream<Item = Result<BatchCheckResult<T, K>, error::Error>> + '_ {
+        poll_progress(move || self.check(), config)
+    }
+
+    /// Ожидает завершения пакета и возвращает результаты.
+    ///
+    /// Если пакет завершился ошибкой, истек или был отменен, возвращает
+    /// [`error::Error::BatchTerminated`]; частичные результаты таких пакетов
+    /// доступны через [`BatchHandler::wait_finished`].
+    #[tracing::instrument(skip_all, fields(batch.id = self.id()), err)]
+    pub async fn wait(
+        &self,
+        config: PollingConfig,
+    ) -> Result<HashMap<K, Result<T, BatchItemError>>, error::Error> {
+        wait_for(self.progress(config)).await
+    }
+
+    /// Ожидает завершения пакета и возвращает итоговое состояние,
+    /// в том числе частичные результаты неуспешно завершенного пакета.
+    #[tracing::instrument(skip_all, fields(batch.id = self.id()), err)]
+    pub async fn wait_finished(
+        &self,
+        config: PollingConfig,
+    ) -> Result<BatchCheckResult<T, K>, error::Error> {
+        wait_finished(self.progress(config)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::TryStreamExt;
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{
+        batch::structures::Method,
+        embeddings::structures::EmbeddingResponse,
+        mock::{self, MockServer},
+    };
+
+    /// Состояние пакета `b1` с `ready` готовыми запросами из двух.
+    fn state(status: &str, ready: usize) -> Value {
+        let output = (status == "completed").then_some("f1");
+        let mut batch = mock::batch("b1", "embedder", status, output);
+        batch["request_counts"] = json!({ "total": 2, "completed": ready, "failed": 0 });
+        batch
+    }
+
+    /// Сервер, отвечающий на проверки пакета состояниями `states` по очереди;
+    /// последнее состояние повторяется.
+    async fn server(states: Vec<Value>) -> (MockServer, BatchHandler<EmbeddingResponse>) {
+        let checks = AtomicUsize::new(0);
+        let server = MockServer::start_raw(move |request| match request.path.as_str() {
+            "/batches?batch_id=b1" => {
+                let check = checks.fetch_add(1, Ordering::SeqCst);
+                (200, states[check.min(states.len() - 1)].to_string())
+            }
+            "/files/f1/content" => {
+                let output = mock::jsonl(&[
+                    json!({ "key": "0", "result": mock::embeddings(&[vec![1.0]]) }),
+                    json!({ "key": "1", "error": { "status": 429, "message": "rate limited" } }),
+                ]);
+                (200, output)
+            }
+            path => panic!("unexpected request {path}"),
+        })
+        .await;
+        let handler = BatchHandler::new(server.client().await, "b1".to_string(), Method::Embedder);
+        (server, handler)
+    }
+
+    fn fast() -> PollingConfig {
+        PollingConfig {
+            initial_interval: Duration::from_millis(1),
+            max_interval: Duration::from_millis(5),
+            multiplier: 2.0,
+            timeout: Some(Duration::from_secs(10)),
+        }
+    }
+
+    fn sequence() -> Vec<Value> {
+        vec![
+            state("created", 0),
+            state("validating", 0),
+            state("in_progress", 0),
+            state("in_progress", 0),
+            state("in_progress", 1),
+            state("finalizing", 1),
+            state("completed", 2),
+        ]
+    }
+
+    fn config(multiplier: f64) -> PollingConfig {
+        PollingConfig {
+            initial_interval: Duration::from_secs(2),
+            max_interval: Duration::from_secs(10),
+            multiplier,
+            timeout: None,
+        }
+    }
+
+    #[test]
+    fn interval_grows_up_to_max() {
+        let config = config(2.0);
+
+        assert_eq!(
+            config.next_interval(Duration::from_secs(2)),
+            Duration::from_secs(4)
+        );
+        assert_eq!(
+            config.next_interval(Duration::from_secs(8)),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            config.next_interval(Duration::from_secs(30)),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn invalid_multiplier_does_not_panic() {
+        let interval = Duration::from_secs(2);
+
+        for multiplier in [-1.0, 0.0, 0.5, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(config(multiplier).next_interval(interval), interval);
+        }
+        assert_eq!(
+            config(f64::MAX).next_interval(interval),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[tokio::test]
+    async fn progress_reports_changes_only() {
+        let (server, handler) = server(sequence()).await;
+
+        let states: Vec<_> = handler.progress(fast()).try_collect().await.unwrap();
+
+        let states: Vec<_> = states
+            .iter()
+            .map(|state| match state {
+                BatchCheckResult::Pending => "pending".to_string(),
+                BatchCheckResult::InProgress { ready, total } => format!("{ready}/{total}"),
+                BatchCheckResult::Success { responses } => format!("done {}", responses.len()),
+                other => panic!("unexpected state {:?}", other.status()),
+            })
+            .collect();
+        assert_eq!(states, ["pending", "0/2", "1/2", "done 2"]);
+        let checks = server
+            .requests()
+            .iter()
+            .filter(|request| request.path == "/batches?batch_id=b1")
+            .count();
+        assert_eq!(checks, 7);
+    }
+
+    #[tokio::test]
+    async fn wait_returns_results() {
+        let (_server, handler) = server(sequence()).await;
+
+        let responses = handler.wait(fast()).await.unwrap();
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[&0].as_ref().unwrap().data[0].embedding, [1.0]);
+        assert_eq!(responses[&1].as_ref().unwrap_err().status, 429);
+    }
+
+    #[tokio::test]
+    async fn wait_times_out() {
+        let (server, handler) = server(vec![state("in_progress", 0)]).await;
+        let config = PollingConfig {
+            timeout: Some(Duration::from_millis(50)),
+            ..fast()
+        };
+
+        let result = handler.wait(config).await;
+
+        assert!(matches!(
+            result,
+            Err(error::Error::WaitTimedOut { timeout }) if timeout == Duration::from_millis(50)
+        ));
+        assert!(server.requests().len() > 1);
+    }
+}
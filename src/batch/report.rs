@@ -0,0 +1,483 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::structures::BatchItemError;
+use crate::generation::{
+    Model,
+    structures::{FinishReason, GenerationResponse, Usage},
+};
+
+/// Потребление токенов одной моделью.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// Количество успешных ответов.
+    pub responses: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub precached_prompt_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl ModelUsage {
+    fn add(&mut self, usage: &Usage) {
+        self.responses += 1;
+        self.prompt_tokens += u64::from(usage.prompt_tokens);
+        self.completion_tokens += u64::from(usage.completion_tokens);
+        self.precached_prompt_tokens += u64::from(usage.precached_prompt_tokens);
+        self.total_tokens += u64::from(usage.total_tokens);
+    }
+
+    fn merge(&mut self, other: &ModelUsage) {
+        self.responses += other.responses;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.precached_prompt_tokens += other.precached_prompt_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// Сводка потребления по результатам генерации.
+///
+/// Собирается из результатов пакета через [`UsageReport::from_batch`] или
+/// из любого набора ответов через [`UsageReport::from_responses`]; отчеты
+/// нескольких пакетов объединяются через [`UsageReport::merge`].
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::batch::{PollingConfig, PriceTable, TokenPrice, UsageReport};
+/// use gigachat_rust::client::GigaChatClientBuilder;
+/// use gigachat_rust::generation::{Model, structures::Message};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let request = client
+///         .generate()
+///         .with_messages(vec![Message::user("Привет!")])
+///         .build();
+///     let handler = client.batch().with_request(request).execute().await.unwrap();
+///     let responses = handler.wait(PollingConfig::default()).await.unwrap();
+///
+///     let report = UsageReport::from_batch(&responses);
+///     println!("{:?}", report.total());
+///
+///     // Цена за миллион токенов по вашему тарифу.
+///     let price_per_million = 1000.0;
+///     let prices = PriceTable::new().with_price(
+///         Model::GigaChat2Max,
+///         TokenPrice::flat(price_per_million),
+///     );
+///     println!("{:.2}", report.cost(&prices).total);
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Потребление по моделям, ответившим на запросы.
+    pub models: HashMap<Model, ModelUsage>,
+    /// Количество вариантов ответа по причинам завершения генерации.
+    pub finish_reasons: HashMap<FinishReason, usize>,
+    /// Количество неудавшихся запросов по HTTP статусам.
+    pub errors: HashMap<u16, usize>,
+}
+
+impl UsageReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Собирает сводку по ответам.
+    pub fn from_responses<'r, I: IntoIterator<Item = &'r GenerationResponse>>(
+        responses: I,
+    ) -> Self {
+        let mut report = Self::new();
+        for response in responses {
+            report.add_response(response);
+        }
+        report
+    }
+
+    /// Собирает сводку по результатам пакета, включая ошибки.
+    pub fn from_batch<'r, K: 'r, I>(results: I) -> Self
+    where
+        I: IntoIterator<Item = (&'r K, &'r Result<GenerationResponse, BatchItemError>)>,
+    {
+        let mut report = Self::new();
+        for (_, result) in results {
+            report.add_result(result);
+        }
+        report
+    }
+
+    /// Учитывает ответ.
+    ///
+    /// Потребление относится к [`GenerationResponse::model`], то есть к модели,
+    /// фактически обработавшей запрос.
+    pub fn add_response(&mut self, response: &GenerationResponse) {
+        self.models
+            .entry(response.model.clone())
+            .or_default()
+            .add(&response.usage);
+        for choice in &response.choices {
+            *self.finish_reasons.entry(choice.finish_reason).or_default() += 1;
+        }
+    }
+
+    /// Учитывает неудавшийся запрос.
+    pub fn add_error(&mut self, error: &BatchItemError) {
+        *self.errors.entry(error.status).or_default() += 1;
+    }
+
+    /// Учитывает результат запроса пакета.
+    pub fn add_result(&mut self, result: &Result<GenerationResponse, BatchItemError>) {
+        match result {
+            Ok(response) => self.add_response(response),
+            Err(error) => self.add_error(error),
+        }
+    }
+
+    /// Добавляет данные другой сводки.
+    pub fn merge(&mut self, other: &UsageReport) {
+        for (model, usage) in &other.models {
+            self.models.entry(model.clone()).or_default().merge(usage);
+        }
+        for (reason, count) in &other.finish_reasons {
+            *self.finish_reasons.entry(*reason).or_default() += count;
+        }
+        for (status, count) in &other.errors {
+            *self.errors.entry(*status).or_default() += count;
+        }
+    }
+
+    /// Суммарное потребление по всем моделям.
+    pub fn total(&self) -> ModelUsage {
+        let mut total = ModelUsage::default();
+        for usage in self.models.values() {
+            total.merge(usage);
+        }
+        total
+    }
+
+    /// Количество неудавшихся запросов.
+    pub fn failed(&self) -> usize {
+        self.errors.values().sum()
+    }
+
+    /// Оценивает стоимость потребленных токенов по таблице цен.
+    ///
+    /// Модели без цены в таблице не учитываются в стоимости и
+    /// перечисляются в [`CostEstimate::unpriced`].
+    pub fn cost(&self, prices: &PriceTable) -> CostEstimate {
+        let mut estimate = CostEstimate::default();
+        for (model, usage) in &self.models {
+            match prices.price(model) {
+                Some(price) => {
+                    let cost = price.cost(usage);
+                    estimate.total += cost;
+                    estimate.models.insert(model.clone(), cost);
+                }
+                None => estimate.unpriced.push(model.clone()),
+            }
+        }
+        estimate
+    }
+}
+
+/// Цена токенов модели за миллион токенов.
+///
+/// Закэшированные токены входящего сообщения тарифицируются по
+/// [`TokenPrice::precached_prompt`] вместо [`TokenPrice::prompt`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TokenPrice {
+    pub prompt: f64,
+    pub completion: f64,
+    pub precached_prompt: f64,
+}
+
+impl TokenPrice {
+    /// Единая цена входящих и сгенерированных токенов; закэшированные
+    /// токены не оплачиваются.
+    pub fn flat(per_million: f64) -> Self {
+        Self {
+            prompt: per_million,
+            completion: per_million,
+            precached_prompt: 0.0,
+        }
+    }
+
+    /// Стоимость потребления по этой цене.
+    pub fn cost(&self, usage: &ModelUsage) -> f64 {
+        let precached = usage.precached_prompt_tokens.min(usage.prompt_tokens);
+        let prompt = usage.prompt_tokens - precached;
+        (prompt as f64 * self.prompt
+            + precached as f64 * self.precached_prompt
+            + usage.completion_tokens as f64 * self.completion)
+            / 1_000_000.0
+    }
+}
+
+/// Таблица цен моделей.
+///
+/// Цены не входят в библиотеку, поскольку зависят от тарифа;
+/// их нужно заполнить по актуальному прайс-листу.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceTable {
+    prices: HashMap<Model, TokenPrice>,
+    fallback: Option<TokenPrice>,
+}
+
+impl PriceTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Устанавливает цену модели.
+    pub fn with_price(mut self, model: Model, price: TokenPrice) -> Self {
+        self.prices.insert(model, price);
+        self
+    }
+
+    /// Устанавливает цену для моделей, отсутствующих в таблице.
+    pub fn with_fallback(mut self, price: TokenPrice) -> Self {
+        self.fallback = Some(price);
+        self
+    }
+
+    /// Цена модели, если она известна.
+    ///
+    /// Ответы API содержат версию модели (`GigaChat-2-Max:2.0.28.2`),
+    /// поэтому при отсутствии точного совпадения цена ищется по имени
+    /// модели без версии.
+    pub fn price(&self, model: &Model) -> Option<&TokenPrice> {
+        self.prices
+            .get(model)
+            .or_else(|| self.prices.get(&base_model(model)?))
+            .or(self.fallback.as_ref())
+    }
+}
+
+/// Модель без версии для идентификаторов вида `<имя>:<версия>`.
+fn base_model(model: &Model) -> Option<Model> {
+    let Model::Custom(id) = model else {
+        return None;
+    };
+    let (name, _version) = id.split_once(':')?;
+    Some(Model::from(name))
+}
+
+/// Оценка стоимости по [`PriceTable`].
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostEstimate {
+    /// Общая стоимость.
+    pub total: f64,
+    /// Стоимость по моделям.
+    pub models: HashMap<Model, f64>,
+    /// Модели, для которых в таблице нет цены.
+    pub unpriced: Vec<Model>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::mock;
+
+    fn model(id: &str) -> Model {
+        serde_json::from_value(serde_json::Value::String(id.to_string())).unwrap()
+    }
+
+    #[test]
+    fn versioned_model_uses_base_price() {
+        let prices = PriceTable::new()
+            .with_price(Model::GigaChat2Max, TokenPrice::flat(2.0))
+            .with_price(Model::Custom("Custom".into()), TokenPrice::flat(3.0));
+
+        let versioned = model("GigaChat-2-Max:2.0.28.2");
+        assert_eq!(versioned, Model::Custom("GigaChat-2-Max:2.0.28.2".into()));
+        assert_eq!(prices.price(&versioned), Some(&TokenPrice::flat(2.0)));
+        assert_eq!(
+            prices.price(&model("Custom:1")),
+            Some(&TokenPrice::flat(3.0))
+        );
+        assert_eq!(prices.price(&model("GigaChat-2-Pro:2.0.28.2")), None);
+    }
+
+    #[test]
+    fn exact_price_takes_precedence() {
+        let versioned = model("GigaChat-2-Max:2.0.28.2");
+        let prices = PriceTable::new()
+            .with_price(Model::GigaChat2Max, TokenPrice::flat(2.0))
+            .with_price(versioned.clone(), TokenPrice::flat(5.0))
+            .with_fallback(TokenPrice::flat(1.0));
+
+        assert_eq!(prices.price(&versioned), Some(&TokenPrice::flat(5.0)));
+        assert_eq!(
+            prices.price(&model("Unknown")),
+            Some(&TokenPrice::flat(1.0))
+        );
+    }
+
+    #[test]
+    fn token_cost() {
+        let usage = ModelUsage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 500_000,
+            precached_prompt_tokens: 200_000,
+            ..Default::default()
+        };
+        let price = TokenPrice {
+            prompt: 1.0,
+            completion: 2.0,
+            precached_prompt: 0.5,
+        };
+
+        assert!((price.cost(&usage) - 1.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn model_from_name_matches_serde() {
+        for id in [
+            "GigaChat-2",
+            "GigaChat-2-Pro",
+            "GigaChat-2-Max",
+            "GigaChat-3",
+        ] {
+            assert_eq!(Model::from(id), model(id));
+        }
+        assert_eq!(
+            base_model(&model("GigaChat-2-Pro:2.0.28.2")),
+            Some(Model::GigaChat2Pro)
+        );
+        assert_eq!(base_model(&Model::GigaChat2Pro), None);
+        assert_eq!(base_model(&model("Custom")), None);
+    }
+
+    /// Ответ модели `model` с потреблением `[prompt, completion, precached, total]`
+    /// и вариантами, завершенными по причинам `reasons`.
+    fn response(model: &str, usage: [u32; 4], reasons: &[&str]) -> GenerationResponse {
+        let mut response = mock::completion(model, "ответ");
+        let choice = response["choices"][0].clone();
+        response["choices"] = Value::Array(
+            reasons
+                .iter()
+                .enumerate()
+                .map(|(index, reason)| {
+                    let mut choice = choice.clone();
+                    choice["index"] = json!(index);
+                    choice["finish_reason"] = json!(reason);
+                    choice
+                })
+                .collect(),
+        );
+        response["usage"] = json!({
+            "prompt_tokens": usage[0],
+            "completion_tokens": usage[1],
+            "precached_prompt_tokens": usage[2],
+            "total_tokens": usage[3],
+        });
+        serde_json::from_value(response).unwrap()
+    }
+
+    fn error(status: u16) -> Result<GenerationResponse, BatchItemError> {
+        Err(BatchItemError {
+            status,
+            message: format!("status {status}"),
+        })
+    }
+
+    #[test]
+    fn batch_report_aggregates_results() {
+        let max = model("GigaChat-2-Max:2.0");
+        let results = HashMap::from([
+            (
+                0,
+                Ok(response("GigaChat-2-Max:2.0", [10, 5, 2, 15], &["stop"])),
+            ),
+            (
+                1,
+                Ok(response(
+                    "GigaChat-2-Max:2.0",
+                    [20, 10, 0, 30],
+                    &["stop", "length"],
+                )),
+            ),
+            (
+                2,
+                Ok(response("GigaChat-2-Pro", [1, 1, 0, 2], &["function_call"])),
+            ),
+            (3, error(429)),
+            (4, error(429)),
+            (5, error(500)),
+        ]);
+
+        let report = UsageReport::from_batch(&results);
+
+        let max_usage = ModelUsage {
+            responses: 2,
+            prompt_tokens: 30,
+            completion_tokens: 15,
+            precached_prompt_tokens: 2,
+            total_tokens: 45,
+        };
+        let pro_usage = ModelUsage {
+            responses: 1,
+            prompt_tokens: 1,
+            completion_tokens: 1,
+            precached_prompt_tokens: 0,
+            total_tokens: 2,
+        };
+        assert_eq!(
+            report.models,
+            HashMap::from([(max.clone(), max_usage), (Model::GigaChat2Pro, pro_usage)])
+        );
+        assert_eq!(
+            report.finish_reasons,
+            HashMap::from([
+                (FinishReason::Stop, 2),
+                (FinishReason::Length, 1),
+                (FinishReason::FunctionCall, 1),
+            ])
+        );
+        assert_eq!(report.errors, HashMap::from([(429, 2), (500, 1)]));
+        assert_eq!(report.failed(), 3);
+        assert_eq!(
+            report.total(),
+            ModelUsage {
+                responses: 3,
+                prompt_tokens: 31,
+                completion_tokens: 16,
+                precached_prompt_tokens: 2,
+                total_tokens: 47,
+            }
+        );
+
+        let responses =
+            UsageReport::from_responses(results.values().filter_map(|r| r.as_ref().ok()));
+        assert_eq!(responses.models, report.models);
+        assert_eq!(responses.finish_reasons, report.finish_reasons);
+        assert!(responses.errors.is_empty());
+        assert_eq!(responses.failed(), 0);
+
+        let prices =
+            PriceTable::new().with_price(Model::GigaChat2Max, TokenPrice::flat(1_000_000.0));
+        let cost = report.cost(&prices);
+        assert_eq!(cost.total, 43.0);
+        assert_eq!(cost.models, HashMap::from([(max.clone(), 43.0)]));
+        assert_eq!(cost.unpriced, [Model::GigaChat2Pro]);
+
+        let mut merged = report.clone();
+        merged.merge(&report);
+        assert_eq!(merged.models[&max].responses, 4);
+        assert_eq!(merged.models[&max].total_tokens, 90);
+        assert_eq!(merged.models[&Model::GigaChat2Pro].prompt_tokens, 2);
+        assert_eq!(merged.finish_reasons[&FinishReason::Stop], 4);
+        assert_eq!(merged.errors, HashMap::from([(429, 4), (500, 2)]));
+        assert_eq!(merged.failed(), 6);
+        assert_eq!(merged.total().total_tokens, 94);
+    }
+}
@@ -0,0 +1,407 @@
+use serde::{Serialize, de::DeserializeOwned};
+use std::{collections::HashMap, time::Duration};
+use tokio::{task::spawn_blocking, time::sleep};
+
+use super::{
+    builder::{ensure_unique_keys, serialize_batch_to_file, upload_batch},
+    error,
+    handler::{BatchCheckResult, BatchHandler},
+    polling::PollingConfig,
+    structures::{BatchItemError, BatchKey, Method},
+};
+use crate::client::GigaChatClient;
+
+/// Параметры повторной отправки неудавшихся запросов пакета.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Максимальное количество повторных пакетов.
+    pub max_rounds: usize,
+    /// Пауза перед отправкой каждого повторного пакета.
+    pub delay: Duration,
+    /// Параметры ожидания каждого пакета.
+    pub polling: PollingConfig,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_rounds: 3,
+            delay: Duration::from_secs(10),
+            polling: PollingConfig::default(),
+        }
+    }
+}
+
+type Responses<T, K> = HashMap<K, Result<T, BatchItemError>>;
+
+/// Отбирает запросы, которые стоит отправить повторно: завершившиеся
+/// ошибкой с [`BatchItemError::is_retryable`] или отсутствующие в результатах.
+pub(crate) fn failed_requests<K: BatchKey, R: Clone, T>(
+    requests: &[(K, R)],
+    responses: &Responses<T, K>,
+) -> Vec<(K, R)> {
+    requests
+        .iter()
+        .filter(|(key, _)| should_retry(responses.get(key)))
+        .cloned()
+        .collect()
+}
+
+fn should_retry<T>(response: Option<&Result<T, BatchItemError>>) -> bool {
+    match response {
+        Some(Ok(_)) => false,
+        Some(Err(e)) => e.is_retryable(),
+        None => true,
+    }
+}
+
+/// Строка пакета, ссылающаяся на запрос без копирования.
+#[derive(Serialize)]
+struct RequestItem<'a, R> {
+    key: String,
+    request: &'a R,
+}
+
+/// Отправляет запросы пакетом и дожидается его завершения.
+///
+/// Запросы возвращаются вместе с результатами, чтобы неудавшиеся можно
+/// было отправить повторно без копирования.
+async fn execute_round<R, T, K>(
+    client: &GigaChatClient,
+    method: Method,
+    requests: Vec<(K, R)>,
+    polling: &PollingConfig,
+) -> (Vec<(K, R)>, Result<Responses<T, K>, error::Error>)
+where
+    R: Serialize + Send + 'static,
+    T: DeserializeOwned + Send + 'static,
+    K: BatchKey,
+{
+    // Serialization can block async runtime on large batches.
+    let (requests, bytes) = spawn_blocking(move || {
+        let items = requests
+            .iter()
+            .map(|(key, request)| RequestItem {
+                key: key.to_key(),
+                request,
+            })
+            .collect();
+        let bytes = serialize_batch_to_file(items);
+        (requests, bytes)
+    })
+    .await
+    .expect("failed to join blocking thread");
+
+    let result = async {
+        let handler = upload_batch(client, method, bytes?).await?;
+        finished_responses(&handler, polling).await
+    }
+    .await;
+    (requests, result)
+}
+
+/// Дожидается завершения пакета и возвращает его результаты, в том числе
+/// частичные: запросы, не попавшие в результаты, будут отправлены повторно.
+async fn finished_responses<T: DeserializeOwned + Send + 'static, K: BatchKey>(
+    handler: &BatchHandler<T, K>,
+    polling: &PollingConfig,
+) -> Result<Responses<T, K>, error::Error> {
+    let mut finished = handler.wait_finished(polling.clone()).await?;
+    if !matches!(finished, BatchCheckResult::Success { .. }) {
+        tracing::warn!(
+            batch.id = handler.id(),
+            batch.status = ?finished.status(),
+            "batch finished unsuccessfully, keeping partial results"
+        );
+    }
+    Ok(finished
+        .responses_mut()
+        .map(std::mem::take)
+        .unwrap_or_default())
+}
+
+/// Выполняет пакет и повторяет неудавшиеся запросы, пока они есть
+/// и не исчерпан лимит раундов.
+///
+/// Результаты каждого повтора заменяют результаты предыдущих раундов
+/// по ключам. Ошибка первого пакета возвращается как есть; если не удался
+/// повторный пакет, возвращаются накопленные результаты. Запросы, так и не
+/// получившие результата, отмечаются ошибкой со статусом `0`.
+pub(crate) async fn execute_with_retries<R, T, K>(
+    client: GigaChatClient,
+    method: Method,
+    mut requests: Vec<(K, R)>,
+    config: RetryConfig,
+) -> Result<Responses<T, K>, error::Error>
+where
+    R: Serialize + Send + 'static,
+    T: DeserializeOwned + Send + 'static,
+    K: BatchKey,
+{
+    let keys: Vec<String> = requests.iter().map(|(key, _)| key.to_key()).collect();
+    ensure_unique_keys(keys.iter().map(String::as_str))?;
+    drop(keys);
+
+    let mut responses: Responses<T, K> = HashMap::with_capacity(requests.len());
+    let mut failure = None;
+    for round in 0..=config.max_rounds {
+        if round > 0 {
+            requests.retain(|(key, _)| should_retry(responses.get(key)));
+            if requests.is_empty() {
+                break;
+            }
+            tracing::info!(
+                round,
+                batch.size = requests.len(),
+                "resubmitting failed batch requests"
+            );
+            sleep(config.delay).await;
+        }
+
+        let result;
+        (requests, result) = execute_round(&client, method, requests, &config.polling).await;
+        match result {
+            Ok(round_responses) => merge_round(&mut responses, round_responses),
+            Err(e) if round == 0 => return Err(e),
+            Err(e) => {
+                tracing::warn!(
+                    round,
+                    error = %e,
+                    "retry batch failed, returning accumulated results"
+                );
+                failure = Some(e.to_string());
+                break;
+            }
+        }
+    }
+
+    let message = failure.unwrap_or_else(|| "no result after all retry rounds".to_string());
+    mark_unanswered(&mut responses, requests, &message);
+    Ok(responses)
+}
+
+/// Добавляет результаты раунда, заменяя результаты предыдущих раундов
+/// по ключам.
+fn merge_round<T, K: BatchKey>(responses: &mut Responses<T, K>, round: Responses<T, K>) {
+    responses.extend(round);
+}
+
+/// Отмечает запросы без результата ошибкой со статусом `0`.
+fn mark_unanswered<R, T, K: BatchKey>(
+    responses: &mut Responses<T, K>,
+    requests: Vec<(K, R)>,
+    message: &str,
+) {
+    for (key, _) in requests {
+        responses.entry(key).or_insert_with(|| {
+            Err(BatchItemError {
+                status: 0,
+                message: message.to_string(),
+            })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{
+        generation::structures::{GenerationResponse, Message},
+        mock::{self, MockServer},
+    };
+
+    fn error(status: u16) -> Result<&'static str, BatchItemError> {
+        Err(BatchItemError {
+            status,
+            message: format!("status {status}"),
+        })
+    }
+
+    fn requests(count: usize) -> Vec<(usize, &'static str)> {
+        (0..count).map(|key| (key, "request")).collect()
+    }
+
+    fn statuses(responses: &Responses<&'static str, usize>) -> Vec<(usize, u16)> {
+        let mut statuses: Vec<_> = responses
+            .iter()
+            .map(|(&key, response)| (key, response.as_ref().map_or_else(|e| e.status, |_| 200)))
+            .collect();
+        statuses.sort();
+        statuses
+    }
+
+    #[test]
+    fn should_retry_by_error_class() {
+        assert!(!should_retry(Some(&Ok("ok"))));
+        assert!(should_retry(Some(&error(429))));
+        assert!(should_retry(Some(&error(500))));
+        assert!(should_retry(Some(&error(503))));
+        assert!(!should_retry(Some(&error(400))));
+        assert!(!should_retry(Some(&error(404))));
+        assert!(should_retry::<&str>(None));
+    }
+
+    #[test]
+    fn failed_requests_include_retryable_and_missing() {
+        let responses = HashMap::from([
+            (0, Ok("ok")),
+            (1, error(429)),
+            (2, error(400)),
+            (3, error(502)),
+        ]);
+
+        let keys: Vec<_> = failed_requests(&requests(5), &responses)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, [1, 3, 4]);
+    }
+
+    #[test]
+    fn successful_retry_overwrites_earlier_error() {
+        let mut responses = HashMap::from([(0, Ok("ok")), (1, error(429)), (2, error(503))]);
+
+        merge_round(
+            &mut responses,
+            HashMap::from([(1, Ok("retried")), (2, error(500))]),
+        );
+
+        assert_eq!(statuses(&responses), [(0, 200), (1, 200), (2, 500)]);
+        assert_eq!(responses[&1].as_ref().unwrap(), &"retried");
+    }
+
+    #[test]
+    fn unanswered_requests_get_placeholder() {
+        let mut responses = HashMap::from([(0, Ok("ok")), (1, error(429))]);
+
+        mark_unanswered(&mut responses, requests(3), "batch failed");
+
+        assert_eq!(statuses(&responses), [(0, 200), (1, 429), (2, 0)]);
+        assert_eq!(responses[&2].as_ref().unwrap_err().message, "batch failed");
+    }
+
+    fn success(key: &str, content: &str) -> Value {
+        json!({ "key": key, "result": mock::completion("GigaChat-2-Max", content) })
+    }
+
+    fn failure(key: &str, status: u16) -> Value {
+        json!({ "key": key, "error": { "status": status, "message": format!("status {status}") } })
+    }
+
+    /// Сервер пакетов: на первый пакет ключ `1` получает 429, а ключ `2` — 400;
+    /// повторные пакеты для ключа `1` завершаются ответами `rounds` по очереди.
+    async fn server(rounds: Vec<Value>) -> MockServer {
+        let uploads = AtomicUsize::new(0);
+        MockServer::start_raw(move |request| {
+            let path = request.path.as_str();
+            if path == "/batches?method=chat_completions" {
+                let round = uploads.fetch_add(1, Ordering::SeqCst);
+                let batch = mock::batch(&format!("b{round}"), "chat_completions", "created", None);
+                return (200, batch.to_string());
+            }
+            if let Some(round) = path.strip_prefix("/batches?batch_id=b") {
+                let output = format!("f{round}");
+                let batch = mock::batch(
+                    &format!("b{round}"),
+                    "chat_completions",
+                    "completed",
+                    Some(&output),
+                );
+                return (200, batch.to_string());
+            }
+            let round: usize = path
+                .strip_prefix("/files/f")
+                .and_then(|rest| rest.strip_suffix("/content"))
+                .and_then(|round| round.parse().ok())
+                .unwrap_or_else(|| panic!("unexpected request {path}"));
+            let lines = match round {
+                0 => vec![success("0", "первый"), failure("1", 429), failure("2", 400)],
+                round => vec![rounds[round - 1].clone()],
+            };
+            (200, mock::jsonl(&lines))
+        })
+        .await
+    }
+
+    async fn run(server: &MockServer, max_rounds: usize) -> Responses<GenerationResponse, usize> {
+        let client = server.client().await;
+        let request = |text: &str| {
+            client
+                .generate()
+                .with_messages(vec![Message::user(text)])
+                .build()
+        };
+        let config = RetryConfig {
+            max_rounds,
+            delay: Duration::ZERO,
+            polling: PollingConfig {
+                initial_interval: Duration::from_millis(1),
+                max_interval: Duration::from_millis(1),
+                multiplier: 1.0,
+                timeout: Some(Duration::from_secs(10)),
+            },
+        };
+
+        client
+            .batch()
+            .with_requests(vec![
+                request("первый"),
+                request("второй"),
+                request("третий"),
+            ])
+            .execute_with_retries(config)
+            .await
+            .unwrap()
+    }
+
+    /// Ключи запросов в каждом отправленном пакете.
+    fn uploaded_keys(server: &MockServer) -> Vec<Vec<String>> {
+        server
+            .requests()
+            .iter()
+            .filter(|request| request.path == "/batches?method=chat_completions")
+            .map(|request| {
+                mock::jsonl_lines(request)
+                    .iter()
+                    .map(|line| line["key"].as_str().unwrap().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn text(response: &Result<GenerationResponse, BatchItemError>) -> String {
+        response.as_ref().unwrap().text()
+    }
+
+    #[tokio::test]
+    async fn retry_resubmits_only_failed_and_merges_results() {
+        let server = server(vec![failure("1", 503), success("1", "повтор")]).await;
+
+        let responses = run(&server, 3).await;
+
+        assert_eq!(
+            uploaded_keys(&server),
+            [vec!["0", "1", "2"], vec!["1"], vec!["1"]]
+        );
+        assert_eq!(responses.len(), 3);
+        assert_eq!(text(&responses[&0]), "первый");
+        assert_eq!(text(&responses[&1]), "повтор");
+        assert_eq!(responses[&2].as_ref().unwrap_err().status, 400);
+    }
+
+    #[tokio::test]
+    async fn retry_stops_at_round_cap() {
+        let server = server(vec![failure("1", 503), success("1", "повтор")]).await;
+
+        let responses = run(&server, 1).await;
+
+        assert_eq!(uploaded_keys(&server), [vec!["0", "1", "2"], vec!["1"]]);
+        assert_eq!(text(&responses[&0]), "первый");
+        assert_eq!(responses[&1].as_ref().unwrap_err().status, 503);
+        assert_eq!(responses[&2].as_ref().unwrap_err().status, 400);
+    }
+}
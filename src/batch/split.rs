@@ -0,0 +1,467 @@
+use serde::{Serialize, de::DeserializeOwned};
+use snafu::{ResultExt, ensure};
+use std::{collections::HashMap, sync::Mutex};
+use tokio::task::spawn_blocking;
+
+use super::{
+    builder::upload_batch,
+    error,
+    handler::{BatchCheckResult, BatchHandler},
+    polling::{PollingConfig, poll_progress, wait_finished, wait_for},
+    structures::{BatchCheckResponse, BatchDescriptor, BatchItemError, BatchKey, Method},
+};
+use crate::{client::GigaChatClient, generation::structures::GenerationResponse};
+
+/// Ограничения одного пакета для разбиения на несколько.
+///
+/// Значения по умолчанию консервативны; при необходимости их следует
+/// уточнить по ограничениям API для используемого тарифа.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchLimits {
+    /// Максимальный размер входного файла в байтах.
+    pub max_bytes: usize,
+    /// Максимальное количество запросов в пакете.
+    pub max_items: usize,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 100 * 1024 * 1024,
+            max_items: 10_000,
+        }
+    }
+}
+
+type Responses<T, K> = HashMap<K, Result<T, BatchItemError>>;
+
+/// Разбивает запросы на JSONL файлы, укладывающиеся в ограничения.
+///
+/// Возвращает содержимое файлов и количество запросов в каждом.
+fn split_batch_to_files<I: Serialize>(
+    items: Vec<I>,
+    limits: BatchLimits,
+) -> Result<Vec<(Vec<u8>, usize)>, error::Error> {
+    ensure!(
+        limits.max_bytes > 0 && limits.max_items > 0,
+        error::InvalidLimitsSnafu
+    );
+
+    let mut files = Vec::new();
+    let mut current = Vec::new();
+    let mut count = 0;
+    for (index, item) in items.into_iter().enumerate() {
+        let mut line = serde_json::to_vec(&item).context(error::BatchSerializationFailedSnafu)?;
+        line.push(b'\n');
+        ensure!(
+            line.len() <= limits.max_bytes,
+            error::ItemTooLargeSnafu {
+                index,
+                bytes: line.len(),
+                max_bytes: limits.max_bytes,
+            }
+        );
+
+        if count == limits.max_items || current.len() + line.len() > limits.max_bytes {
+            files.push((std::mem::take(&mut current), count));
+            count = 0;
+        }
+        current.extend_from_slice(&line);
+        count += 1;
+    }
+    if count > 0 {
+        files.push((current, count));
+    }
+    Ok(files)
+}
+
+/// Разбивает запросы на несколько пакетов и отправляет их.
+#[tracing::instrument(skip_all, fields(batch.method = method.as_str(), batch.parts), err)]
+pub(crate) async fn submit_split_batch<I: Serialize + Send + 'static, T, K>(
+    client: GigaChatClient,
+    method: Method,
+    items: Vec<I>,
+    limits: BatchLimits,
+) -> Result<SplitBatchHandler<T, K>, error::Error> {
+    // Serialization can block async runtime on large batches.
+    let files = spawn_blocking(move || split_batch_to_files(items, limits))
+        .await
+        .expect("failed to join blocking thread")?;
+    tracing::Span::current().record("batch.parts", files.len());
+
+    let mut handlers: Vec<BatchHandler<T, K>> = Vec::with_capacity(files.len());
+    let mut sizes = Vec::with_capacity(files.len());
+    for (file, count) in files {
+        let handler = match upload_batch(&client, method, file).await {
+            Ok(handler) => handler,
+            Err(e) if handlers.is_empty() => return Err(e),
+            Err(e) => {
+                let submitted: Vec<_> = handlers.iter().map(BatchHandler::descriptor).collect();
+                return Err(e).context(error::PartialSubmitSnafu { submitted });
+            }
+        };
+        handlers.push(handler);
+        sizes.push(count);
+    }
+
+    Ok(SplitBatchHandler::new(handlers, sizes))
+}
+
+/// Обработчик запросов, разбитых на несколько пакетов.
+///
+/// Объединяет прогресс всех пакетов и их результаты по ключам. Ключи
+/// уникальны в пределах исходного набора запросов, поэтому для ключей
+/// по умолчанию (порядковых номеров) результаты сопоставляются с
+/// исходным порядком запросов независимо от разбиения.
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::batch::{BatchLimits, PollingConfig};
+/// use gigachat_rust::client::GigaChatClientBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let texts = (0..50_000).map(|i| format!("Документ {i}"));
+///     let handler = client
+///         .embeddings_batch()
+///         .with_inputs(texts)
+///         .execute_split(BatchLimits::default())
+///         .await
+///         .unwrap();
+///     println!("{} batches", handler.handlers().len());
+///
+///     let responses = handler.wait(PollingConfig::default()).await.unwrap();
+///     println!("{} responses", responses.len());
+/// }
+/// ```
+pub struct SplitBatchHandler<T = GenerationResponse, K = usize> {
+    handlers: Vec<BatchHandler<T, K>>,
+    sizes: Vec<usize>,
+    /// Блокировка не удерживается во время сетевых запросов.
+    state: Mutex<SplitState<T, K>>,
+}
+
+/// Загруженные результаты пакетов.
+struct SplitState<T, K> {
+    /// Итоговые состояния завершенных пакетов до объединения.
+    parts: Vec<Option<BatchCheckResult<T, K>>>,
+    /// Объединенное итоговое состояние после завершения всех пакетов.
+    merged: Option<BatchCheckResult<T, K>>,
+}
+
+impl<T, K: BatchKey> SplitState<T, K> {
+    /// Объединяет результаты всех пакетов.
+    ///
+    /// Если какой-либо пакет завершился неуспешно, итоговое состояние
+    /// берется от первого такого пакета.
+    fn merge(&mut self, total: usize) -> BatchCheckResult<T, K> {
+        let mut responses = HashMap::with_capacity(total);
+        let mut outcome = None;
+        for part in &mut self.parts {
+            let Some(mut part) = part.take() else {
+                continue;
+            };
+            responses.extend(part.responses_mut().map(std::mem::take).unwrap_or_default());
+            if !matches!(part, BatchCheckResult::Success { .. }) && outcome.is_none() {
+                outcome = Some(part);
+            }
+        }
+
+        let mut outcome = outcome.unwrap_or(BatchCheckResult::Success {
+            responses: HashMap::new(),
+        });
+        if let Some(merged) = outcome.responses_mut() {
+            *merged = responses;
+        }
+        outcome
+    }
+}
+
+impl<T, K> SplitBatchHandler<T, K> {
+    fn new(handlers: Vec<BatchHandler<T, K>>, sizes: Vec<usize>) -> Self {
+        let state = Mutex::new(SplitState {
+            parts: handlers.iter().map(|_| None).collect(),
+            merged: None,
+        });
+        Self {
+            handlers,
+            sizes,
+            state,
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SplitState<T, K>> {
+        self.state.lock().expect("split batch state lock poisoned")
+    }
+
+    /// Обработчики отдельных пакетов в порядке отправки.
+    pub fn handlers(&self) -> &[BatchHandler<T, K>] {
+        &self.handlers
+    }
+
+    /// Описания пакетов для сохранения.
+    pub fn descriptors(&self) -> Vec<BatchDescriptor> {
+        self.handlers.iter().map(BatchHandler::descriptor).collect()
+    }
+
+    /// Общее количество запросов во всех пакетах.
+    pub fn total(&self) -> usize {
+        self.sizes.iter().sum()
+    }
+
+    /// Отменяет все пакеты.
+    ///
+    /// Пытается отменить каждый пакет и возвращает первую ошибку, если она была.
+    pub async fn cancel(&self) -> Result<Vec<BatchCheckResponse>, error::Error> {
+        let mut responses = Vec::with_capacity(self.handlers.len());
+        let mut first_error = None;
+        for handler in &self.handlers {
+            match handler.cancel().await {
+                Ok(response) => responses.push(response),
+                Err(e) => {
+                    tracing::warn!(batch.id = handler.id(), error = %e, "failed to cancel batch");
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(responses),
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Clone + Send + 'static, K: BatchKey> SplitBatchHandler<T, K> {
+    /// Проверяет состояние всех пакетов.
+    ///
+    /// `ready` и `total` суммируются по пакетам; результаты возвращаются,
+    /// когда завершены все пакеты. Результаты завершенных пакетов
+    /// загружаются один раз и повторно не запрашиваются.
+    ///
+    /// Если какой-либо пакет завершился неуспешно, итоговое состояние
+    /// берется от первого такого пакета, а результаты объединяются по всем.
+    #[tracing::instrument(skip_all, fields(batch.parts = self.handlers.len()))]
+    pub async fn check(&self) -> Result<BatchCheckResult<T, K>, error::Error> {
+        let (pending, mut ready) = {
+            let state = self.lock_state();
+            if let Some(merged) = &state.merged {
+                return Ok(merged.clone());
+            }
+            let mut pending = Vec::new();
+            let mut ready = 0;
+            for (index, (part, size)) in state.parts.iter().zip(&self.sizes).enumerate() {
+                match part {
+                    Some(_) => ready += size,
+                    None => pending.push(index),
+                }
+            }
+            (pending, ready)
+        };
+
+        let mut started = pending.len() < self.handlers.len();
+        let mut finished = true;
+        let mut completed = Vec::new();
+        for index in pending {
+            match self.handlers[index].check().await? {
+                BatchCheckResult::Pending => finished = false,
+                BatchCheckResult::InProgress {
+                    ready: part_ready, ..
+                } => {
+                    ready += part_ready;
+                    started = true;
+                    finished = false;
+                }
+                result => {
+                    ready += self.sizes[index];
+                    started = true;
+                    completed.push((index, result));
+                }
+            }
+        }
+
+        let mut state = self.lock_state();
+        if let Some(merged) = &state.merged {
+            return Ok(merged.clone());
+        }
+        for (index, result) in completed {
+            state.parts[index].get_or_insert(result);
+        }
+
+        Ok(if finished {
+            let merged = state.merge(self.total());
+            state.merged.insert(merged).clone()
+        } else if started {
+            BatchCheckResult::InProgress {
+                ready,
+                total: self.total(),
+            }
+        } else {
+            BatchCheckResult::Pending
+        })
+    }
+
+    /// Поток изменений общего состояния пакетов; см. [`BatchHandler::progress`].
+    pub fn progress(
+        &self,
+        config: PollingConfig,
+    ) -> impl futures::Stream<Item = Result<BatchCheckResult<T, K>, error::Error>> + '_ {
+        poll_progress(move || self.check(), config)
+    }
+
+    /// Ожидает завершения всех пакетов и возвращает объединенные результаты;
+    /// см. [`BatchHandler::wait`].
+    #[tracing::instrument(skip_all, fields(batch.parts = self.handlers.len()), err)]
+    pub async fn wait(&self, config: PollingConfig) -> Result<Responses<T, K>, error::Error> {
+        wait_for(self.progress(config)).await
+    }
+
+    /// Ожидает завершения всех пакетов и возвращает итоговое состояние;
+    /// см. [`BatchHandler::wait_finished`].
+    #[tracing::instrument(skip_all, fields(batch.parts = self.handlers.len()), err)]
+    pub async fn wait_finished(
+        &self,
+        config: PollingConfig,
+    ) -> Result<BatchCheckResult<T, K>, error::Error> {
+        wait_finished(self.progress(config)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Запрос, строка которого вместе с переводом строки занимает 10 байт.
+    fn item(key: usize) -> String {
+        format!("key-{key:03}")
+    }
+
+    fn keys(files: &[(Vec<u8>, usize)]) -> Vec<Vec<String>> {
+        files
+            .iter()
+            .map(|(file, count)| {
+                let lines: Vec<String> = file
+                    .split(|&b| b == b'\n')
+                    .filter(|line| !line.is_empty())
+                    .map(|line| serde_json::from_slice(line).unwrap())
+                    .collect();
+                assert_eq!(lines.len(), *count);
+                lines
+            })
+            .collect()
+    }
+
+    #[test]
+    fn files_fill_up_to_exact_byte_limit() {
+        let items: Vec<_> = (0..5).map(item).collect();
+        let limits = BatchLimits {
+            max_bytes: 20,
+            max_items: 100,
+        };
+
+        let files = split_batch_to_files(items, limits).unwrap();
+        let sizes: Vec<_> = files.iter().map(|(file, _)| file.len()).collect();
+        assert_eq!(sizes, [20, 20, 10]);
+    }
+
+    #[test]
+    fn files_fill_up_to_exact_item_limit() {
+        let items: Vec<_> = (0..4).map(item).collect();
+        let limits = BatchLimits {
+            max_bytes: 1024,
+            max_items: 2,
+        };
+
+        let files = split_batch_to_files(items, limits).unwrap();
+        let counts: Vec<_> = files.iter().map(|(_, count)| *count).collect();
+        assert_eq!(counts, [2, 2]);
+    }
+
+    #[test]
+    fn item_of_exact_limit_fits() {
+        let limits = BatchLimits {
+            max_bytes: 10,
+            max_items: 1,
+        };
+
+        let files = split_batch_to_files(vec![item(0), item(1)], limits).unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn oversized_item_is_rejected() {
+        let limits = BatchLimits {
+            max_bytes: 9,
+            max_items: 10,
+        };
+
+        let error = split_batch_to_files(vec![item(0)], limits).unwrap_err();
+        assert!(matches!(
+            error,
+            error::Error::ItemTooLarge {
+                index: 0,
+                bytes: 10,
+                max_bytes: 9
+            }
+        ));
+    }
+
+    #[test]
+    fn zero_limits_are_rejected() {
+        let limits = BatchLimits {
+            max_bytes: 0,
+            max_items: 0,
+        };
+
+        let error = split_batch_to_files(vec![item(0)], limits).unwrap_err();
+        assert!(matches!(error, error::Error::InvalidLimits));
+    }
+
+    #[test]
+    fn order_is_preserved_across_files() {
+        let items: Vec<_> = (0..7).map(item).collect();
+        let limits = BatchLimits {
+            max_bytes: 30,
+            max_items: 100,
+        };
+
+        let files = split_batch_to_files(items.clone(), limits).unwrap();
+        assert_eq!(keys(&files).concat(), items);
+    }
+
+    #[test]
+    fn merge_combines_parts_and_keeps_first_failure() {
+        let part = |keys: std::ops::Range<usize>| {
+            keys.map(|key| (key, Ok(key * 10)))
+                .collect::<HashMap<usize, Result<usize, BatchItemError>>>()
+        };
+        let mut state = SplitState {
+            parts: vec![
+                Some(BatchCheckResult::Success {
+                    responses: part(0..2),
+                }),
+                Some(BatchCheckResult::Expired {
+                    error: None,
+                    responses: part(2..3),
+                }),
+                Some(BatchCheckResult::Cancelled {
+                    responses: part(3..5),
+                }),
+            ],
+            merged: None,
+        };
+
+        let merged = state.merge(5);
+        assert!(matches!(merged, BatchCheckResult::Expired { .. }));
+        let responses = merged.responses().unwrap();
+        assert_eq!(responses.len(), 5);
+        for key in 0..5 {
+            assert_eq!(responses[&key].as_ref().unwrap(), &(key * 10));
+        }
+    }
+}
@@ -1,30 +1,81 @@
+use crate::client::error::ErrorClass;
+use crate::embeddings::structures::EmbeddingRequest;
 use crate::generation::structures::{GenerationRequest, GenerationResponse};
 use crate::serialization::string_to_usize;
 use serde::{Deserialize, Serialize};
+use std::{fmt::Display, hash::Hash, str::FromStr};
 use time::OffsetDateTime;
 
+/// Ключ запроса в пакете.
+///
+/// Ключ передается в API строкой и используется для сопоставления
+/// результатов с запросами. Реализован для всех типов с [`Display`] и
+/// [`FromStr`], например `String`, `usize` или `Uuid`; для собственных
+/// типов достаточно реализовать эти трейты с взаимно обратным преобразованием.
+pub trait BatchKey: Clone + Eq + Hash + Send + Sync + 'static {
+    /// Строковое представление ключа.
+    fn to_key(&self) -> String;
+
+    /// Восстанавливает ключ из строкового представления.
+    fn from_key(key: &str) -> Option<Self>;
+}
+
+impl<T> BatchKey for T
+where
+    T: Display + FromStr + Clone + Eq + Hash + Send + Sync + 'static,
+{
+    fn to_key(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        key.parse().ok()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BatchGenerateRequestItem {
-    #[serde(with = "string_to_usize")]
-    pub key: usize,
+    pub key: String,
     pub request: GenerationRequest,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchEmbeddingRequestItem {
+    pub key: String,
+    pub request: EmbeddingRequest,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchItemError {
     pub status: u16,
     pub message: String,
 }
 
+impl BatchItemError {
+    /// Возвращает класс ошибки, если он определен.
+    pub fn class(&self) -> Option<ErrorClass> {
+        match self.status {
+            429 => Some(ErrorClass::RateLimited),
+            500..=599 => Some(ErrorClass::ServerError),
+            _ => None,
+        }
+    }
+
+    /// Имеет ли смысл повторить запрос.
+    pub fn is_retryable(&self) -> bool {
+        self.class().is_some()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
-pub enum BatchResponseResult {
-    Result(GenerationResponse),
+pub enum BatchResponseResult<T = GenerationResponse> {
+    Result(T),
     Error(BatchItemError),
 }
 
-impl From<BatchResponseResult> for Result<GenerationResponse, BatchItemError> {
-    fn from(result: BatchResponseResult) -> Self {
+impl<T> From<BatchResponseResult<T>> for Result<T, BatchItemError> {
+    fn from(result: BatchResponseResult<T>) -> Self {
         match result {
             BatchResponseResult::Result(response) => Ok(response),
             BatchResponseResult::Error(err) => Err(err),
@@ -32,12 +83,21 @@ impl From<BatchResponseResult> for Result<GenerationResponse, BatchItemError> {
     }
 }
 
-impl BatchResponseResult {
-    pub fn res(self) -> Result<GenerationResponse, BatchItemError> {
+impl<T> BatchResponseResult<T> {
+    pub fn res(self) -> Result<T, BatchItemError> {
         self.into()
     }
 }
 
+/// Строка выходного файла пакета.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResponseItem<T = GenerationResponse> {
+    /// Ключ запроса во входном файле.
+    pub key: String,
+    #[serde(flatten)]
+    pub result: BatchResponseResult<T>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchGenerateResponseItem {
     #[serde(with = "string_to_usize")]
@@ -45,21 +105,122 @@ pub struct BatchGenerateResponseItem {
     pub response: GenerationResponse,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+/// Статус пакета.
+///
+/// Статусы, неизвестные этой версии библиотеки, сохраняются в
+/// [`Status::Unknown`], чтобы проверка пакета не завершалась ошибкой.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Status {
+    /// Пакет создан и ожидает обработки.
     Created,
+    /// Входной файл проверяется.
+    Validating,
+    /// Запросы выполняются.
     InProgress,
+    /// Формируется выходной файл.
+    Finalizing,
+    /// Все запросы обработаны.
     Completed,
+    /// Пакет не удалось обработать.
+    Failed,
+    /// Пакет не был обработан за отведенное время.
+    Expired,
+    /// Пакет отменяется.
+    Cancelling,
+    /// Пакет отменен.
+    Cancelled,
+    /// Статус, неизвестный этой версии библиотеки, в исходном виде.
+    Unknown(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Status {
+    /// Возвращает статус в виде строки для параметра `status`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Status::Created => "created",
+            Status::Validating => "validating",
+            Status::InProgress => "in_progress",
+            Status::Finalizing => "finalizing",
+            Status::Completed => "completed",
+            Status::Failed => "failed",
+            Status::Expired => "expired",
+            Status::Cancelling => "cancelling",
+            Status::Cancelled => "cancelled",
+            Status::Unknown(status) => status,
+        }
+    }
+
+    /// Завершена ли обработка пакета, успешно или нет.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Status::Completed | Status::Failed | Status::Expired | Status::Cancelled
+        )
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Status {
+    fn from(status: &str) -> Self {
+        match status {
+            "created" => Status::Created,
+            "validating" => Status::Validating,
+            "in_progress" => Status::InProgress,
+            "finalizing" => Status::Finalizing,
+            "completed" => Status::Completed,
+            "failed" => Status::Failed,
+            "expired" => Status::Expired,
+            "cancelling" => Status::Cancelling,
+            "cancelled" | "canceled" => Status::Cancelled,
+            other => Status::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl Serialize for Status {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let status = std::borrow::Cow::<str>::deserialize(deserializer)?;
+        Ok(Status::from(status.as_ref()))
+    }
+}
+
+/// Описание ошибки, с которой завершился пакет.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchFailure {
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Method {
     ChatCompletions,
     Embedder,
 }
 
+impl Method {
+    /// Возвращает метод в виде строки для параметра `method`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::ChatCompletions => "chat_completions",
+            Method::Embedder => "embedder",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct BatchCreateCounts {
     pub total: usize,
@@ -90,9 +251,113 @@ pub struct BatchCheckResponse {
     pub method: Method,
     pub request_counts: BatchCheckCounts,
     pub status: Status,
+    /// Выходной файл; у завершившихся с ошибкой пакетов может
+    /// содержать результаты обработанных запросов.
     pub output_file_id: Option<String>,
+    /// Причина ошибки для пакетов в статусах `failed` и `expired`.
+    #[serde(default)]
+    pub error: Option<BatchFailure>,
     #[serde(with = "time::serde::timestamp::milliseconds")]
     pub created_at: OffsetDateTime,
     #[serde(with = "time::serde::timestamp::milliseconds")]
     pub updated_at: OffsetDateTime,
 }
+
+/// Страница списка пакетов.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchListResponse {
+    pub batches: Vec<BatchCheckResponse>,
+    /// Есть ли пакеты после последнего на странице; `None`, если сервер
+    /// не передал поле.
+    #[serde(default)]
+    pub has_more: Option<bool>,
+    /// Идентификатор для запроса следующей страницы через
+    /// [`BatchListBuilder::after`](super::list::BatchListBuilder::after);
+    /// `None` на последней странице.
+    ///
+    /// Берется из страницы до локальной фильтрации, поэтому пакеты,
+    /// не прошедшие фильтр, не повторяются и не пропускаются.
+    #[serde(skip)]
+    pub next_after: Option<String>,
+}
+
+/// Описание пакета, достаточное для повторного подключения к нему.
+///
+/// Сохраняется вместе с состоянием приложения, чтобы после перезапуска
+/// продолжить ожидание пакета через [`BatchDescriptor::attach`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchDescriptor {
+    pub id: String,
+    pub method: Method,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(status: &str) -> Status {
+        serde_json::from_value(serde_json::Value::String(status.to_string())).unwrap()
+    }
+
+    #[test]
+    fn status_round_trip() {
+        let statuses = [
+            ("created", Status::Created),
+            ("validating", Status::Validating),
+            ("in_progress", Status::InProgress),
+            ("finalizing", Status::Finalizing),
+            ("completed", Status::Completed),
+            ("failed", Status::Failed),
+            ("expired", Status::Expired),
+            ("cancelling", Status::Cancelling),
+            ("cancelled", Status::Cancelled),
+        ];
+
+        for (text, status) in statuses {
+            assert_eq!(parse(text), status);
+            assert_eq!(serde_json::to_value(&status).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn american_spelling_of_cancelled() {
+        assert_eq!(parse("canceled"), Status::Cancelled);
+    }
+
+    #[test]
+    fn unknown_status_is_preserved() {
+        let status = parse("archived");
+
+        assert_eq!(status, Status::Unknown("archived".to_string()));
+        assert_eq!(status.to_string(), "archived");
+        assert!(!status.is_terminal());
+    }
+
+    #[test]
+    fn terminal_statuses() {
+        let terminal: Vec<_> = [
+            Status::Created,
+            Status::Validating,
+            Status::InProgress,
+            Status::Finalizing,
+            Status::Completed,
+            Status::Failed,
+            Status::Expired,
+            Status::Cancelling,
+            Status::Cancelled,
+        ]
+        .into_iter()
+        .filter(Status::is_terminal)
+        .collect();
+
+        assert_eq!(
+            terminal,
+            [
+                Status::Completed,
+                Status::Failed,
+                Status::Expired,
+                Status::Cancelled
+            ]
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+use serde::Deserialize;
+use snafu::{OptionExt, ResultExt, ensure};
+use std::{
+    collections::HashSet,
+    marker::PhantomData,
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, BufReader},
+};
+use tracing::Span;
+
+use super::{
+    error,
+    handler::BatchHandler,
+    structures::{BatchCreateResponse, BatchGenerateRequestItem, BatchKey, Method},
+};
+use crate::{
+    client::GigaChatClient,
+    generation::structures::{GenerationRequest, GenerationResponse},
+};
+
+/// Строка входного файла пакета.
+///
+/// Тело запроса проверяется только структурно: поля, не известные
+/// [`GenerationRequest`], и необязательные поля, которые он требует,
+/// допустимы в файлах, подготовленных другими инструментами.
+#[derive(Deserialize)]
+struct InputLine {
+    key: String,
+    #[serde(rename = "request")]
+    _request: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Проверка строк входного файла пакета по мере чтения.
+struct LineValidator<K> {
+    line: usize,
+    keys: HashSet<String>,
+    key: PhantomData<K>,
+}
+
+impl<K: BatchKey> LineValidator<K> {
+    fn new() -> Self {
+        Self {
+            line: 0,
+            keys: HashSet::new(),
+            key: PhantomData,
+        }
+    }
+
+    /// Проверяет очередную строку файла.
+    ///
+    /// Возвращает `false` для пустой строки, которая не отправляется.
+    fn check(&mut self, line: &str) -> Result<bool, error::Error> {
+        self.line += 1;
+        if line.trim().is_empty() {
+            return Ok(false);
+        }
+
+        let InputLine { key, .. } =
+            serde_json::from_str(line).context(error::InputParseFailedSnafu { line: self.line })?;
+        K::from_key(&key).context(error::InvalidInputKeySnafu {
+            line: self.line,
+            key: key.as_str(),
+        })?;
+        ensure!(
+            self.keys.insert(key.clone()),
+            error::DuplicateKeySnafu { key }
+        );
+        Ok(true)
+    }
+
+    /// Проверяет, что во входных данных был хотя бы один запрос.
+    fn finish(&self) -> Result<(), error::Error> {
+        ensure!(!self.keys.is_empty(), error::EmptyInputSnafu);
+        Ok(())
+    }
+}
+
+/// Отправляет пакет, передавая тело запроса потоком строк JSONL.
+///
+/// Ошибка проверки строки прерывает загрузку и возвращается вместо
+/// ошибки отправки запроса. Пустой поток отклоняется с
+/// [`error::Error::EmptyInput`] до отправки запроса.
+#[tracing::instrument(skip_all, fields(url, batch.method = method.as_str(), batch.size), err)]
+async fn submit_batch_stream<T, K, S>(
+    client: &GigaChatClient,
+    method: Method,
+    lines: S,
+) -> Result<BatchHandler<T, K>, error::Error>
+where
+    S: Stream<Item = Result<Vec<u8>, error::Error>> + Send + 'static,
+{
+    let url = client
+        .build_url("batches", [("method", method.as_str())].as_slice())
+        .context(error::BuildUrlSnafu)?;
+    Span::current().record("url", url.as_str());
+
+    // Первая строка читается до отправки, чтобы не создавать пустой пакет.
+    let mut lines = Box::pin(lines);
+    let first = lines.next().await.context(error::EmptyInputSnafu)??;
+    let lines = stream::once(async move { Ok(first) }).chain(lines);
+
+    let failure = Arc::new(Mutex::new(None));
+    let count = Arc::new(AtomicUsize::new(0));
+    let body = lines.map({
+        let failure = failure.clone();
+        let count = count.clone();
+        move |line| match line {
+            Ok(mut line) => {
+                count.fetch_add(1, Ordering::Relaxed);
+                line.push(b'\n');
+                Ok(line)
+            }
+            Err(e) => {
+                let message = e.to_string();
+                *failure.lock().expect("upload failure lock is poisoned") = Some(e);
+                Err(std::io::Error::other(message))
+            }
+        }
+    });
+
+    let response = client
+        .perform_request(
+            |c| {
+                c.post(url)
+                    .body(reqwest::Body::wrap_stream(body))
+                    .header("content-type", "application/octet-stream")
+            },
+            async |r| r.json::<BatchCreateResponse>().await,
+        )
+        .await;
+    if let Some(e) = failure
+        .lock()
+        .expect("upload failure lock is poisoned")
+        .take()
+    {
+        return Err(e);
+    }
+    let response = response.context(error::BadRequestSnafu)?;
+
+    let count = count.load(Ordering::Relaxed);
+    Span::current().record("batch.size", count);
+    if response.counts.total != count {
+        tracing::warn!(
+            batch.total = response.counts.total,
+            "server counted a different number of requests"
+        );
+    }
+    Ok(BatchHandler::new(
+        client.clone(),
+        response.id,
+        response.method,
+    ))
+}
+
+impl GigaChatClient {
+    /// Отправляет пакет из готового файла JSONL, не загружая его в память.
+    ///
+    /// Каждая непустая строка файла — объект `{"key": ..., "request": {...}}`,
+    /// где `request` соответствует методу `method`. Строки проверяются по мере
+    /// чтения: строка другой структуры, ключ, не разбираемый как `K`,
+    /// повторяющийся ключ или файл без запросов прерывают загрузку
+    /// с соответствующей ошибкой.
+    ///
+    /// ## Пример
+    ///
+    /// ```rust,no_run
+    /// use gigachat_rust::batch::structures::Method;
+    /// use gigachat_rust::client::GigaChatClientBuilder;
+    /// use gigachat_rust::generation::structures::GenerationResponse;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     let handler = client
+    ///         .submit_batch_file::<GenerationResponse, String>("requests.jsonl", Method::ChatCompletions)
+    ///         .await
+    ///         .unwrap();
+    ///     println!("{}", handler.id());
+    /// }
+    /// ```
+    pub async fn submit_batch_file<T, K: BatchKey>(
+        &self,
+        path: impl AsRef<Path>,
+        method: Method,
+    ) -> Result<BatchHandler<T, K>, error::Error> {
+        let file = File::open(path.as_ref())
+            .await
+            .context(error::InputIoSnafu)?;
+
+        let state = (BufReader::new(file).lines(), LineValidator::<K>::new());
+        let lines = stream::try_unfold(state, move |(mut lines, mut validator)| async move {
+            loop {
+                let Some(line) = lines.next_line().await.context(error::InputIoSnafu)? else {
+                    validator.finish()?;
+                    return Ok(None);
+                };
+                if validator.check(&line)? {
+                    return Ok(Some((line.into_bytes(), (lines, validator))));
+                }
+            }
+        });
+
+        submit_batch_stream(self, method, lines).await
+    }
+
+    /// Отправляет пакет запросов генерации из потока, сериализуя их по мере отправки.
+    ///
+    /// Ключом служит порядковый номер запроса в потоке. Пустой поток
+    /// отклоняется с [`error::Error::EmptyInput`] до отправки запроса.
+    pub async fn submit_batch_stream<S>(
+        &self,
+        requests: S,
+    ) -> Result<BatchHandler<GenerationResponse, usize>, error::Error>
+    where
+        S: Stream<Item = GenerationRequest> + Send + 'static,
+    {
+        self.submit_keyed_batch_stream(requests.enumerate()).await
+    }
+
+    /// Отправляет пакет запросов генерации с ключами из потока.
+    ///
+    /// Повторяющийся ключ прерывает загрузку с [`error::Error::DuplicateKey`],
+    /// пустой поток отклоняется с [`error::Error::EmptyInput`] до отправки запроса.
+    pub async fn submit_keyed_batch_stream<K: BatchKey, S>(
+        &self,
+        requests: S,
+    ) -> Result<BatchHandler<GenerationResponse, K>, error::Error>
+    where
+        S: Stream<Item = (K, GenerationRequest)> + Send + 'static,
+    {
+        let mut keys = HashSet::new();
+        let lines = requests.map(Ok).and_then(move |(key, request)| {
+            let item = BatchGenerateRequestItem {
+                key: key.to_key(),
+                request,
+            };
+            let line = if keys.insert(item.key.clone()) {
+                serde_json::to_vec(&item).context(error::BatchSerializationFailedSnafu)
+            } else {
+                error::DuplicateKeySnafu { key: item.key }.fail()
+            };
+            async move { line }
+        });
+
+        submit_batch_stream(self, Method::ChatCompletions, lines).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        generation::structures::Message,
+        mock::{self, MockServer},
+    };
+
+    async fn server() -> MockServer {
+        MockServer::start(|request| {
+            assert_eq!(request.path, "/batches?method=chat_completions");
+            (200, mock::batch("b1", "chat_completions", "created", None))
+        })
+        .await
+    }
+
+    fn check_all<K: BatchKey>(lines: &[&str]) -> Result<usize, error::Error> {
+        let mut validator = LineValidator::<K>::new();
+        let mut accepted = 0;
+        for line in lines {
+            if validator.check(line)? {
+                accepted += 1;
+            }
+        }
+        validator.finish()?;
+        Ok(accepted)
+    }
+
+    #[test]
+    fn valid_lines_are_accepted() {
+        let lines = [
+            r#"{"key": "1", "request": {"model": "GigaChat"}}"#,
+            "",
+            r#"{"key": "2", "request": {}, "extra": true}"#,
+        ];
+
+        assert_eq!(check_all::<usize>(&lines).unwrap(), 2);
+    }
+
+    #[test]
+    fn malformed_line_reports_its_number() {
+        let lines = [r#"{"key": "a", "request": {}}"#, "  ", "{not json"];
+
+        let error = check_all::<String>(&lines).unwrap_err();
+        assert!(
+            matches!(error, error::Error::InputParseFailed { line: 3, .. }),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn line_without_key_is_rejected() {
+        let lines = [r#"{"request": {}}"#];
+
+        let error = check_all::<String>(&lines).unwrap_err();
+        assert!(
+            matches!(error, error::Error::InputParseFailed { line: 1, .. }),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn unparsable_key_is_rejected() {
+        let lines = [
+            r#"{"key": "1", "request": {}}"#,
+            r#"{"key": "x", "request": {}}"#,
+        ];
+
+        let error = check_all::<usize>(&lines).unwrap_err();
+        assert!(
+            matches!(&error, error::Error::InvalidInputKey { line: 2, key } if key == "x"),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn duplicate_key_is_rejected() {
+        let lines = [
+            r#"{"key": "a", "request": {}}"#,
+            r#"{"key": "b", "request": {}}"#,
+            r#"{"key": "a", "request": {}}"#,
+        ];
+
+        let error = check_all::<String>(&lines).unwrap_err();
+        assert!(
+            matches!(&error, error::Error::DuplicateKey { key } if key == "a"),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn empty_input_is_rejected() {
+        for lines in [&[][..], &["", "   "][..]] {
+            let error = check_all::<String>(lines).unwrap_err();
+            assert!(
+                matches!(error, error::Error::EmptyInput),
+                "{lines:?}: {error:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_is_uploaded_line_by_line() {
+        let server = server().await;
+        let client = server.client().await;
+        let request = client
+            .generate()
+            .with_messages(vec![Message::user("привет")])
+            .build();
+
+        let handler = client
+            .submit_batch_stream(stream::iter([request.clone(), request]))
+            .await
+            .unwrap();
+
+        assert_eq!(handler.id(), "b1");
+        let keys: Vec<_> = mock::jsonl_lines(&server.requests()[0])
+            .into_iter()
+            .map(|line| line["key"].clone())
+            .collect();
+        assert_eq!(keys, ["0", "1"]);
+    }
+
+    #[tokio::test]
+    async fn empty_stream_is_rejected_before_upload() {
+        let server = server().await;
+        let client = server.client().await;
+
+        let result = client.submit_batch_stream(stream::empty()).await;
+        assert!(matches!(result, Err(error::Error::EmptyInput)));
+
+        let result = client
+            .submit_keyed_batch_stream::<String, _>(stream::empty())
+            .await;
+        assert!(matches!(result, Err(error::Error::EmptyInput)));
+
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn blank_file_is_rejected_before_upload() {
+        let server = server().await;
+        let path = std::env::temp_dir().join(format!("gigachat-{}.jsonl", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "\n  \n").unwrap();
+
+        let result = server
+            .client()
+            .await
+            .submit_batch_file::<GenerationResponse, String>(&path, Method::ChatCompletions)
+            .await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(error::Error::EmptyInput)));
+        assert!(server.requests().is_empty());
+    }
+}
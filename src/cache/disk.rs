@@ -0,0 +1,182 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use snafu::prelude::*;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::{CacheKey, CacheStore, error};
+
+/// Размер заголовка записи: время истечения в миллисекундах (`i64`, little-endian),
+/// `0` означает бессрочную запись.
+const HEADER_LEN: usize = 8;
+
+/// Хранилище кэша на диске: каждая запись хранится в отдельном файле.
+#[derive(Debug, Clone)]
+pub struct DiskStore {
+    directory: PathBuf,
+}
+
+impl DiskStore {
+    /// Создает хранилище в указанной директории.
+    ///
+    /// Директория создается при первой записи.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Директория хранилища.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        self.directory.join(key.as_str().replace('/', "-"))
+    }
+}
+
+#[async_trait::async_trait]
+impl CacheStore for DiskStore {
+    async fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>, error::Error> {
+        let path = self.entry_path(key);
+        let mut bytes = match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).context(error::ReadEntrySnafu { path }),
+        };
+
+        ensure!(
+            bytes.len() >= HEADER_LEN,
+            error::CorruptedEntrySnafu { path }
+        );
+        let header: [u8; HEADER_LEN] = bytes[..HEADER_LEN]
+            .try_into()
+            .expect("header slice has fixed length");
+        let expires_at = i64::from_le_bytes(header);
+
+        let now = (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64;
+        if expires_at != 0 && expires_at <= now {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e).context(error::RemoveEntrySnafu { path }),
+            }
+            return Ok(None);
+        }
+
+        bytes.drain(..HEADER_LEN);
+        Ok(Some(bytes))
+    }
+
+    async fn put(
+        &self,
+        key: &CacheKey,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> Result<(), error::Error> {
+        let path = self.entry_path(key);
+        tokio::fs::create_dir_all(&self.directory)
+            .await
+            .context(error::WriteEntrySnafu {
+                path: self.directory.clone(),
+            })?;
+
+        let expires_at = ttl.map_or(0, |ttl| {
+            ((OffsetDateTime::now_utc() + ttl).unix_timestamp_nanos() / 1_000_000) as i64
+        });
+        let mut bytes = Vec::with_capacity(HEADER_LEN + value.len());
+        bytes.extend_from_slice(&expires_at.to_le_bytes());
+        bytes.extend_from_slice(&value);
+
+        // Запись через временный файл, чтобы читатели не увидели частичную запись.
+        // Имя файла уникально, чтобы параллельные записи одного ключа
+        // не мешали друг другу.
+        let temporary = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        tokio::fs::write(&temporary, bytes)
+            .await
+            .context(error::WriteEntrySnafu {
+                path: temporary.clone(),
+            })?;
+        tokio::fs::rename(&temporary, &path)
+            .await
+            .context(error::WriteEntrySnafu { path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> DiskStore {
+        DiskStore::new(std::env::temp_dir().join(format!("gigachat-{}", Uuid::new_v4())))
+    }
+
+    fn key() -> CacheKey {
+        CacheKey::new("test", &"запрос").unwrap()
+    }
+
+    /// Записывает файл записи с заданным временем истечения в миллисекундах.
+    fn write_entry(store: &DiskStore, expires_at: i64, value: &[u8]) {
+        std::fs::create_dir_all(store.directory()).unwrap();
+        let mut bytes = expires_at.to_le_bytes().to_vec();
+        bytes.extend_from_slice(value);
+        std::fs::write(store.entry_path(&key()), bytes).unwrap();
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let store = store();
+        store
+            .put(&key(), b"value".to_vec(), Some(Duration::from_secs(3600)))
+            .await
+            .unwrap();
+
+        assert_eq!(store.get(&key()).await.unwrap(), Some(b"value".to_vec()));
+        let files = std::fs::read_dir(store.directory()).unwrap().count();
+        assert_eq!(files, 1, "temporary files left behind");
+
+        std::fs::remove_dir_all(store.directory()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn expiry_header() {
+        let store = store();
+        let now = (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64;
+
+        write_entry(&store, 0, b"forever");
+        assert_eq!(store.get(&key()).await.unwrap(), Some(b"forever".to_vec()));
+
+        write_entry(&store, now + 3_600_000, b"fresh");
+        assert_eq!(store.get(&key()).await.unwrap(), Some(b"fresh".to_vec()));
+
+        write_entry(&store, now - 1, b"expired");
+        assert_eq!(store.get(&key()).await.unwrap(), None);
+        assert!(!store.entry_path(&key()).exists());
+
+        std::fs::remove_dir_all(store.directory()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn truncated_entry_is_rejected() {
+        let store = store();
+        std::fs::create_dir_all(store.directory()).unwrap();
+
+        for length in [1, HEADER_LEN - 1] {
+            std::fs::write(store.entry_path(&key()), vec![0u8; length]).unwrap();
+            assert!(matches!(
+                store.get(&key()).await,
+                Err(error::Error::CorruptedEntry { .. })
+            ));
+        }
+
+        std::fs::write(store.entry_path(&key()), [0u8; HEADER_LEN]).unwrap();
+        assert_eq!(store.get(&key()).await.unwrap(), Some(Vec::new()));
+
+        std::fs::remove_dir_all(store.directory()).unwrap();
+    }
+}
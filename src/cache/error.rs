@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display("failed to read cache entry {path:?}"))]
+    ReadEntry {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("failed to write cache entry {path:?}"))]
+    WriteEntry {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("failed to remove cache entry {path:?}"))]
+    RemoveEntry {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("cache entry {path:?} is corrupted"))]
+    CorruptedEntry { path: PathBuf },
+
+    #[snafu(display("failed to serialize cache value"))]
+    Serialize { source: serde_json::Error },
+
+    #[snafu(display("cache store error"))]
+    Store {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
@@ -0,0 +1,99 @@
+use std::{
+    num::NonZeroUsize,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+
+use super::{CacheKey, CacheStore, error};
+
+/// Запись кэша: время истечения и значение.
+type Entry = (Option<Instant>, Vec<u8>);
+
+/// Хранилище кэша в памяти с вытеснением давно не использованных записей.
+pub struct MemoryStore {
+    entries: Mutex<LruCache<CacheKey, Entry>>,
+}
+
+impl MemoryStore {
+    /// Создает хранилище, вмещающее не более `capacity` записей.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CacheStore for MemoryStore {
+    async fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>, error::Error> {
+        let mut entries = self.entries.lock().expect("cache mutex poisoned");
+        match entries.get(key) {
+            Some((Some(expires_at), _)) if *expires_at <= Instant::now() => {
+                entries.pop(key);
+                Ok(None)
+            }
+            Some((_, value)) => Ok(Some(value.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn put(
+        &self,
+        key: &CacheKey,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> Result<(), error::Error> {
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        self.entries
+            .lock()
+            .expect("cache mutex poisoned")
+            .put(key.clone(), (expires_at, value));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> CacheKey {
+        CacheKey::new("test", &name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_entry_is_evicted() {
+        let store = MemoryStore::new(NonZeroUsize::new(2).unwrap());
+        store.put(&key("a"), b"a".to_vec(), None).await.unwrap();
+        store.put(&key("b"), b"b".to_vec(), None).await.unwrap();
+
+        assert_eq!(store.get(&key("a")).await.unwrap(), Some(b"a".to_vec()));
+        store.put(&key("c"), b"c".to_vec(), None).await.unwrap();
+
+        assert_eq!(store.get(&key("a")).await.unwrap(), Some(b"a".to_vec()));
+        assert_eq!(store.get(&key("b")).await.unwrap(), None);
+        assert_eq!(store.get(&key("c")).await.unwrap(), Some(b"c".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn expired_entry_is_removed() {
+        let store = MemoryStore::new(NonZeroUsize::new(2).unwrap());
+        store
+            .put(&key("expired"), b"1".to_vec(), Some(Duration::ZERO))
+            .await
+            .unwrap();
+        store
+            .put(
+                &key("fresh"),
+                b"2".to_vec(),
+                Some(Duration::from_secs(3600)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(store.get(&key("expired")).await.unwrap(), None);
+        assert_eq!(store.entries.lock().unwrap().len(), 1);
+        assert_eq!(store.get(&key("fresh")).await.unwrap(), Some(b"2".to_vec()));
+    }
+}
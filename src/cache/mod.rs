@@ -0,0 +1,310 @@
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+
+mod disk;
+pub mod error;
+mod memory;
+
+pub use disk::DiskStore;
+pub use memory::MemoryStore;
+
+/// Версия схемы ключей; увеличивается при изменении способа хэширования.
+const KEY_VERSION: u32 = 1;
+
+/// Ключ записи в кэше.
+///
+/// Состоит из пространства имен (`generation`, `embeddings`) и
+/// SHA-256 канонического JSON-представления запроса.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// Строит ключ по каноническому представлению запроса.
+    ///
+    /// Поля объектов сортируются, поэтому ключ не зависит от порядка
+    /// сериализации полей.
+    pub fn new<T: Serialize>(namespace: &str, request: &T) -> Result<Self, error::Error> {
+        let value = serde_json::to_value(request).context(error::SerializeSnafu)?;
+        let canonical = canonicalize(value);
+        let bytes = serde_json::to_vec(&canonical).context(error::SerializeSnafu)?;
+
+        let digest = Sha256::digest(&bytes);
+        let hash: String = digest.iter().map(|b| format!("{b:02x}")).collect();
+        Ok(Self(format!("{namespace}/v{KEY_VERSION}/{hash}")))
+    }
+
+    /// Возвращает ключ в виде строки.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Рекурсивно сортирует поля объектов.
+///
+/// Порядок полей в `serde_json::Map` зависит от фичи `preserve_order`,
+/// которую может включить любая зависимость, поэтому он задается явно.
+fn canonicalize(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(canonicalize).collect())
+        }
+        other => other,
+    }
+}
+
+/// Хранилище кэша ответов.
+///
+/// Реализации отвечают за соблюдение времени жизни записей: просроченная
+/// запись должна возвращаться как отсутствующая.
+#[async_trait::async_trait]
+pub trait CacheStore: Send + Sync {
+    /// Возвращает значение по ключу.
+    async fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>, error::Error>;
+
+    /// Сохраняет значение по ключу с необязательным временем жизни.
+    async fn put(
+        &self,
+        key: &CacheKey,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> Result<(), error::Error>;
+}
+
+/// Режим использования кэша для отдельного запроса.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Кэшировать только детерминированные запросы.
+    #[default]
+    Auto,
+    /// Кэшировать запрос независимо от параметров генерации.
+    Force,
+    /// Не использовать кэш.
+    Bypass,
+}
+
+/// Кэш ответов генерации и векторизации.
+///
+/// Подключается к клиенту через
+/// [`GigaChatClientBuilder::cache`](crate::client::GigaChatClientBuilder::cache).
+/// Потоковая генерация никогда не кэшируется; генерация с температурой
+/// больше нуля (или не заданной, т.е. с температурой модели по умолчанию)
+/// кэшируется только при [`ResponseCache::with_nondeterministic`] или
+/// [`CacheMode::Force`].
+///
+/// Ошибки хранилища не прерывают запрос: они записываются в лог,
+/// а запрос выполняется без кэша.
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use std::{num::NonZeroUsize, time::Duration};
+/// use gigachat_rust::{
+///     cache::{MemoryStore, ResponseCache},
+///     client::GigaChatClientBuilder,
+/// };
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let cache = ResponseCache::new(MemoryStore::new(NonZeroUsize::new(1024).unwrap()))
+///         .with_ttl(Duration::from_secs(3600));
+///
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .cache(cache)
+///         .build()
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ResponseCache {
+    store: Arc<dyn CacheStore>,
+    ttl: Option<Duration>,
+    nondeterministic: bool,
+}
+
+impl Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("ttl", &self.ttl)
+            .field("nondeterministic", &self.nondeterministic)
+            .finish()
+    }
+}
+
+impl ResponseCache {
+    /// Создает кэш поверх указанного хранилища.
+    pub fn new<S: CacheStore + 'static>(store: S) -> Self {
+        Self {
+            store: Arc::new(store),
+            ttl: None,
+            nondeterministic: false,
+        }
+    }
+
+    /// Устанавливает время жизни записей.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Разрешает кэширование генерации с температурой больше нуля.
+    pub fn with_nondeterministic(mut self, enabled: bool) -> Self {
+        self.nondeterministic = enabled;
+        self
+    }
+
+    /// Проверяет, нужно ли кэшировать генерацию с указанной температурой.
+    pub(crate) fn allows(&self, mode: CacheMode, temperature: Option<f32>) -> bool {
+        match mode {
+            CacheMode::Bypass => false,
+            CacheMode::Force => true,
+            CacheMode::Auto => self.nondeterministic || temperature.is_some_and(|t| t <= 0.0),
+        }
+    }
+
+    /// Строит ключ запроса; при ошибке кэш не используется.
+    pub(crate) fn key<T: Serialize>(&self, namespace: &str, request: &T) -> Option<CacheKey> {
+        CacheKey::new(namespace, request)
+            .inspect_err(|e| tracing::warn!(error = %e, "failed to build cache key"))
+            .ok()
+    }
+
+    #[tracing::instrument(skip_all, fields(cache.key = key.as_str(), cache.hit))]
+    pub(crate) async fn get<T: DeserializeOwned>(&self, key: &CacheKey) -> Option<T> {
+        let value = match self.store.get(key).await {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!(error = %e, "cache lookup failed");
+                None
+            }
+        };
+
+        let value = value.and_then(|bytes| {
+            serde_json::from_slice(&bytes)
+                .inspect_err(|e| tracing::warn!(error = %e, "failed to decode cached value"))
+                .ok()
+        });
+        tracing::Span::current().record("cache.hit", value.is_some());
+        value
+    }
+
+    #[tracing::instrument(skip_all, fields(cache.key = key.as_str()))]
+    pub(crate) async fn put<T: Serialize>(&self, key: &CacheKey, value: &T) {
+        let bytes = match serde_json::to_vec(value) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to encode cache value");
+                return;
+            }
+        };
+
+        if let Err(e) = self.store.put(key, bytes, self.ttl).await {
+            tracing::warn!(error = %e, "cache store failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Inner {
+        x: u32,
+        y: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct InnerReversed {
+        y: &'static str,
+        x: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Request<I> {
+        model: &'static str,
+        items: Vec<I>,
+    }
+
+    #[derive(Serialize)]
+    struct RequestReversed<I> {
+        items: Vec<I>,
+        model: &'static str,
+    }
+
+    #[test]
+    fn key_ignores_field_order() {
+        let forward = Request {
+            model: "GigaChat",
+            items: vec![Inner {
+                x: 1, y: "один"
+            }],
+        };
+        let reversed = RequestReversed {
+            items: vec![InnerReversed {
+                y: "один", x: 1
+            }],
+            model: "GigaChat",
+        };
+
+        let key = CacheKey::new("generation", &forward).unwrap();
+        assert_eq!(key, CacheKey::new("generation", &reversed).unwrap());
+        assert_ne!(key, CacheKey::new("embeddings", &forward).unwrap());
+
+        let other = Request {
+            model: "GigaChat",
+            items: vec![Inner {
+                x: 2, y: "один"
+            }],
+        };
+        assert_ne!(key, CacheKey::new("generation", &other).unwrap());
+
+        let hash = key.as_str().strip_prefix("generation/v1/").unwrap();
+        assert_eq!(hash.len(), 64);
+        assert!(hash.bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn canonical_form_sorts_nested_objects() {
+        let value = json!({ "b": 1, "a": { "d": 2, "c": [{ "f": 3, "e": 4 }] } });
+
+        assert_eq!(
+            serde_json::to_string(&canonicalize(value)).unwrap(),
+            r#"{"a":{"c":[{"e":4,"f":3}],"d":2},"b":1}"#
+        );
+    }
+
+    #[test]
+    fn cache_mode_decides_caching() {
+        let cache = ResponseCache::new(MemoryStore::new(NonZeroUsize::new(1).unwrap()));
+
+        assert!(cache.allows(CacheMode::Auto, Some(0.0)));
+        assert!(!cache.allows(CacheMode::Auto, Some(0.7)));
+        assert!(!cache.allows(CacheMode::Auto, None));
+        assert!(cache.allows(CacheMode::Force, Some(0.7)));
+        assert!(cache.allows(CacheMode::Force, None));
+        assert!(!cache.allows(CacheMode::Bypass, Some(0.0)));
+
+        let cache = cache.with_nondeterministic(true);
+        assert!(cache.allows(CacheMode::Auto, Some(0.7)));
+        assert!(cache.allows(CacheMode::Auto, None));
+        assert!(!cache.allows(CacheMode::Bypass, None));
+    }
+}
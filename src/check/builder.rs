@@ -1,14 +1,56 @@
-use snafu::{OptionExt, ResultExt};
+use futures::{StreamExt, stream};
+use snafu::{OptionExt, ResultExt, ensure};
+use std::num::NonZeroUsize;
 use tracing::Span;
 
-use super::{Model, error::*, structures::*};
+use super::{MIN_WORDS, Model, error::*, structures::*};
 use crate::client::GigaChatClient;
 
+/// Проверяет длину текста до отправки запроса.
+///
+/// Длина в словах сравнивается с [`MIN_WORDS`], длина в символах — с `max_characters`.
+fn validate_text(text: &str, max_characters: usize) -> Result<(), Error> {
+    let words = text.split_whitespace().count();
+    ensure!(words >= MIN_WORDS, TextIsTooShortSnafu { length: words });
+
+    let characters = text.chars().count();
+    ensure!(
+        characters <= max_characters,
+        TextIsTooLongSnafu {
+            length: characters,
+            max: max_characters,
+        }
+    );
+    Ok(())
+}
+
+/// Выполняет запрос проверки одного текста.
+#[tracing::instrument(skip_all, fields(url, text.bytes = text.len()))]
+async fn check_text(
+    client: &GigaChatClient,
+    model: Model,
+    text: String,
+    max_characters: usize,
+) -> Result<CheckResponse, Error> {
+    validate_text(&text, max_characters)?;
+    let reqwest = CheckRequest { input: text, model };
+
+    let url = client.build_url("ai/check", None).context(BuildUrlSnafu)?;
+    Span::current().record("url", url.as_str());
+    tracing::debug!("URL constructed successfully");
+
+    client
+        .perform_request(|c| c.post(url).json(&reqwest), async |r| r.json().await)
+        .await
+        .context(BadRequestSnafu)
+}
+
 /// Сборщик запроса проверки текста.
 pub struct CheckBuilder {
     pub(crate) client: GigaChatClient,
     pub(crate) model: Model,
     pub(crate) text: Option<String>,
+    pub(crate) max_characters: usize,
 }
 
 impl CheckBuilder {
@@ -24,24 +66,204 @@ impl CheckBuilder {
         self
     }
 
+    /// Устанавливает максимальную длину текста в символах.
+    pub fn with_max_characters(mut self, max_characters: usize) -> Self {
+        self.max_characters = max_characters;
+        self
+    }
+
+    /// Переходит к проверке нескольких текстов; см. [`CheckManyBuilder`].
+    ///
+    /// Текст, заданный через [`CheckBuilder::with_text`], проверяется первым.
+    pub fn with_texts<I: IntoIterator<Item = String>>(self, texts: I) -> CheckManyBuilder {
+        CheckManyBuilder {
+            client: self.client,
+            model: self.model,
+            texts: self.text.into_iter().chain(texts).collect(),
+            max_characters: self.max_characters,
+            concurrency: NonZeroUsize::new(4).expect("concurrency is non-zero"),
+        }
+    }
+
     /// Выполняет запрос проверки текста.
-    #[tracing::instrument(skip_all, fields(url))]
+    ///
+    /// Возвращает [`Error::TextIsTooShort`] или [`Error::TextIsTooLong`]
+    /// без отправки запроса, если длина текста выходит за ограничения.
     pub async fn execute(self) -> Result<CheckResponse, Error> {
-        let reqwest = CheckRequest {
-            input: self.text.context(TextIsMissingSnafu)?,
-            model: self.model,
-        };
+        let text = self.text.context(TextIsMissingSnafu)?;
+        check_text(&self.client, self.model, text, self.max_characters).await
+    }
+}
+
+/// Сборщик проверки нескольких текстов.
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use std::num::NonZeroUsize;
+/// use gigachat_rust::client::GigaChatClientBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let documents = vec!["Первый документ…".to_string(), "Второй документ…".to_string()];
+///     let results = client
+///         .check()
+///         .with_texts(documents)
+///         .with_concurrency(NonZeroUsize::new(8).unwrap())
+///         .execute()
+///         .await;
+///
+///     for result in results {
+///         println!("{:?}", result.map(|r| r.category));
+///     }
+/// }
+/// ```
+pub struct CheckManyBuilder {
+    client: GigaChatClient,
+    model: Model,
+    texts: Vec<String>,
+    max_characters: usize,
+    concurrency: NonZeroUsize,
+}
+
+impl CheckManyBuilder {
+    /// Добавляет текст для проверки.
+    pub fn with_text(mut self, text: String) -> Self {
+        self.texts.push(text);
+        self
+    }
+
+    /// Устанавливает модель для проверки.
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
 
-        let url = self
-            .client
-            .build_url("ai/check", None)
-            .context(BuildUrlSnafu)?;
-        Span::current().record("url", url.as_str());
-        tracing::debug!("URL constructed successfully");
+    /// Устанавливает максимальную длину текста в символах.
+    pub fn with_max_characters(mut self, max_characters: usize) -> Self {
+        self.max_characters = max_characters;
+        self
+    }
 
-        self.client
-            .perform_request(|c| c.post(url).json(&reqwest), async |r| r.json().await)
+    /// Устанавливает максимальное количество одновременных запросов.
+    pub fn with_concurrency(mut self, concurrency: NonZeroUsize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Проверяет тексты и возвращает результаты в порядке текстов.
+    ///
+    /// Ошибка проверки одного текста, в том числе ошибка длины,
+    /// не прерывает проверку остальных.
+    #[tracing::instrument(skip_all, fields(check.texts = self.texts.len()))]
+    pub async fn execute(self) -> Vec<Result<CheckResponse, Error>> {
+        let Self {
+            client,
+            model,
+            texts,
+            max_characters,
+            concurrency,
+        } = self;
+
+        stream::iter(texts)
+            .map(|text| check_text(&client, model.clone(), text, max_characters))
+            .buffered(concurrency.get())
+            .collect()
             .await
-            .context(BadRequestSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::mock::MockServer;
+
+    fn words(count: usize) -> String {
+        vec!["слово"; count].join(" ")
+    }
+
+    #[test]
+    fn short_text_is_rejected() {
+        let error = validate_text(&words(MIN_WORDS - 1), 10_000).unwrap_err();
+        assert!(matches!(error, Error::TextIsTooShort { length } if length == MIN_WORDS - 1));
+    }
+
+    #[test]
+    fn long_text_is_rejected_by_characters() {
+        let text = words(MIN_WORDS);
+        let length = text.chars().count();
+
+        validate_text(&text, length).unwrap();
+        let error = validate_text(&text, length - 1).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::TextIsTooLong { length: l, max } if l == length && max == length - 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn many_texts_keep_order_and_skip_invalid() {
+        let server = MockServer::start(|request| {
+            assert_eq!(request.path, "/ai/check");
+            let input = request.json()["input"].as_str().unwrap().to_string();
+            let response = json!({
+                "category": "human",
+                "characters": input.chars().count(),
+                "tokens": 1,
+                "ai_intervals": [],
+            });
+            (200, response)
+        })
+        .await;
+        let long = words(MIN_WORDS);
+        let short = vec!["да"; MIN_WORDS].join(" ");
+
+        let results = server
+            .client()
+            .await
+            .check()
+            .with_text(long.clone())
+            .with_texts([words(MIN_WORDS - 15), short.clone(), words(30)])
+            .with_max_characters(150)
+            .execute()
+            .await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            results[0].as_ref().unwrap().characters,
+            long.chars().count()
+        );
+        assert!(matches!(
+            results[1],
+            Err(Error::TextIsTooShort { length: 5 })
+        ));
+        assert_eq!(
+            results[2].as_ref().unwrap().characters,
+            short.chars().count()
+        );
+        assert!(matches!(
+            results[3],
+            Err(Error::TextIsTooLong {
+                length: 179,
+                max: 150
+            })
+        ));
+
+        let mut inputs: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|request| request.json()["input"].as_str().unwrap().to_string())
+            .collect();
+        inputs.sort();
+        let mut expected = vec![long, short];
+        expected.sort();
+        assert_eq!(inputs, expected);
     }
 }
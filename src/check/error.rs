@@ -7,6 +7,8 @@ pub enum Error {
     TextIsMissing,
     #[snafu(display("text is too short, length: {length}"))]
     TextIsTooShort { length: usize },
+    #[snafu(display("text is too long, length: {length}, maximum: {max}"))]
+    TextIsTooLong { length: usize, max: usize },
     #[snafu(display("failed to build url"))]
     BuildUrl {
         source: crate::client::BuildUrlError,
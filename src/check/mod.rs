@@ -22,6 +22,16 @@ pub enum Model {
     GigaCheckDetection,
 }
 
+/// Минимальная длина проверяемого текста в словах.
+pub const MIN_WORDS: usize = 20;
+
+/// Ограничение длины проверяемого текста в символах по умолчанию.
+///
+/// Это значение библиотеки, а не опубликованный предел API: более длинные
+/// тексты отклоняются до отправки запроса. Переопределяется через
+/// [`CheckBuilder::with_max_characters`](builder::CheckBuilder::with_max_characters).
+pub const MAX_CHARACTERS: usize = 10_000;
+
 impl GigaChatClient {
     /// Cоздает сборщик запроса проверки текста.
     ///
@@ -40,7 +50,6 @@ impl GigaChatClient {
     ///
     ///     let response = client
     ///         .check()
-    ///         .await
     ///         .with_text("Этот текст был написан человеком, а не искусственным интеллектом. Он содержит более двадцати слов, чтобы соответствовать требованиям API.".to_string())
     ///         .with_model(Model::GigaCheckClassification)
     ///         .execute()
@@ -50,11 +59,12 @@ impl GigaChatClient {
     ///     println!("{:?}", response);
     /// }
     /// ```
-    pub async fn check(&self) -> builder::CheckBuilder {
+    pub fn check(&self) -> builder::CheckBuilder {
         builder::CheckBuilder {
             client: self.clone(),
             model: Model::default(),
             text: None,
+            max_characters: MAX_CHARACTERS,
         }
     }
 }
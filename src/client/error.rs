@@ -38,3 +38,29 @@ pub enum RequestError {
     #[snafu(display("failed to parse response"))]
     ParseResponse { source: reqwest::Error },
 }
+
+/// Класс ошибки запроса, используемый для принятия решения о повторе.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// Превышен лимит запросов (HTTP 429).
+    RateLimited,
+    /// Ошибка на стороне сервера (HTTP 5xx).
+    ServerError,
+    /// Не удалось отправить запрос или получить ответ.
+    Connection,
+}
+
+impl RequestError {
+    /// Возвращает класс ошибки, если он определен.
+    pub fn class(&self) -> Option<ErrorClass> {
+        match self {
+            RequestError::SendRequest { .. } => Some(ErrorClass::Connection),
+            RequestError::BadResponse { status_code, .. } => match status_code {
+                429 => Some(ErrorClass::RateLimited),
+                500..=599 => Some(ErrorClass::ServerError),
+                _ => None,
+            },
+            RequestError::ParseResponse { .. } => None,
+        }
+    }
+}
@@ -9,6 +9,7 @@ use tracing::{Level, Span};
 pub mod credentials_provider;
 pub mod error;
 
+use crate::cache::ResponseCache;
 use credentials_provider::{SberTokenProvider, SberTokenSource, TokenScope};
 
 /// URL для аутентификации по умолчанию.
@@ -44,6 +45,7 @@ pub struct GigaChatClientBuilder {
     auth_url: Url,
     gigachat_base_url: Url,
     token: String,
+    cache: Option<ResponseCache>,
 }
 
 impl GigaChatClientBuilder {
@@ -64,6 +66,7 @@ impl GigaChatClientBuilder {
             auth_url: DEFAULT_AUTH_URL.clone(),
             gigachat_base_url: DEFAULT_GIGACHAT_BASE_URL.clone(),
             token,
+            cache: None,
         }
     }
 
@@ -73,6 +76,27 @@ impl GigaChatClientBuilder {
         self
     }
 
+    /// Устанавливает URL для аутентификации.
+    pub fn auth_url(mut self, auth_url: Url) -> Self {
+        self.auth_url = auth_url;
+        self
+    }
+
+    /// Устанавливает базовый URL GigaChat API.
+    ///
+    /// URL должен оканчиваться на `/`, иначе последний сегмент пути будет
+    /// заменен при построении адресов методов.
+    pub fn base_url(mut self, base_url: Url) -> Self {
+        self.gigachat_base_url = base_url;
+        self
+    }
+
+    /// Подключает кэш ответов генерации и векторизации.
+    pub fn cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     /// Собирает клиент GigaChat.
     #[rustfmt::skip]
     pub async fn build(self) -> Result<GigaChatClient, error::ClientError> {
@@ -88,6 +112,7 @@ impl GigaChatClientBuilder {
         let inner = GigaChatClientInner {
             client,
             base_url: self.gigachat_base_url,
+            cache: self.cache,
         };
 
         Ok(GigaChatClient {
@@ -99,6 +124,7 @@ impl GigaChatClientBuilder {
 pub(crate) struct GigaChatClientInner {
     pub(crate) client: ClientWithMiddleware,
     pub(crate) base_url: Url,
+    pub(crate) cache: Option<ResponseCache>,
 }
 
 /// Клиент GigaChat.
@@ -124,6 +150,10 @@ pub struct BuildUrlError {
 }
 
 impl GigaChatClient {
+    pub(crate) fn cache(&self) -> Option<&ResponseCache> {
+        self.inner.cache.as_ref()
+    }
+
     #[tracing::instrument(skip_all, fields(
         url.base = self.inner.base_url.as_str(),
         url.path = path,
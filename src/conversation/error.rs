@@ -0,0 +1,24 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display("generation failed"))]
+    Generate {
+        source: crate::generation::error::Error,
+    },
+
+    #[snafu(display("history summarization failed"))]
+    Summarize {
+        source: crate::generation::error::Error,
+    },
+
+    #[snafu(display("response contains no choices"))]
+    EmptyResponse,
+
+    #[snafu(display("fork index {index} is out of range; history length: {length}"))]
+    ForkIndexOutOfRange { index: usize, length: usize },
+
+    #[snafu(display("unsupported snapshot version {version}"))]
+    UnsupportedSnapshotVersion { version: u32 },
+}
@@ -0,0 +1,837 @@
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    client::GigaChatClient,
+    function::{FunctionName, UserFunction},
+    generation::{
+        Model,
+        builder::GenerationBuilder,
+        structures::{Function, FunctionCall, GenerationConfig, GenerationResponse, Message},
+    },
+};
+
+pub mod error;
+pub mod structures;
+use structures::{ConversationEntry, ConversationSnapshot, ForkOrigin, SNAPSHOT_VERSION};
+
+/// Начальная оценка количества токенов на символ до первой калибровки.
+const DEFAULT_TOKENS_PER_CHAR: f64 = 0.3;
+
+/// Инструкция для пересказа вытесняемой части диалога.
+const SUMMARY_PROMPT: &str = "Кратко перескажи диалог между пользователем и ассистентом. \
+    Сохрани важные факты, договоренности и результаты вызовов функций.";
+
+/// Заголовок с идентификатором запроса.
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Стратегия сокращения истории при превышении бюджета токенов.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStrategy {
+    /// Удаляет самые старые реплики.
+    #[default]
+    Trim,
+    /// Заменяет самые старые реплики кратким пересказом,
+    /// который добавляется к системному промпту.
+    Summarize,
+}
+
+/// Сборщик диалога.
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::client::GigaChatClientBuilder;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let mut conversation = client
+///         .conversation()
+///         .with_system_prompt("Ты — вежливый ассистент.")
+///         .with_token_budget(4096)
+///         .build();
+///
+///     let response = conversation.send("Привет! Как дела?").await.unwrap();
+///     println!("{}", response.text());
+///
+///     let response = conversation.send("Что я спросил в прошлый раз?").await.unwrap();
+///     println!("{}", response.text());
+/// }
+/// ```
+pub struct ConversationBuilder {
+    client: GigaChatClient,
+    session_id: Option<String>,
+    model: Model,
+    system_prompt: Option<String>,
+    token_budget: Option<usize>,
+    strategy: CompactionStrategy,
+    config: GenerationConfig,
+    functions: Vec<Function>,
+    function_call: FunctionCall,
+}
+
+impl ConversationBuilder {
+    /// Устанавливает идентификатор сессии.
+    ///
+    /// По умолчанию генерируется случайный UUID.
+    pub fn with_session_id<S: Into<String>>(mut self, session_id: S) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Устанавливает модель для генерации ответов.
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Устанавливает системный промпт, который сохраняется при любом сокращении истории.
+    pub fn with_system_prompt<S: Into<String>>(mut self, system_prompt: S) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    /// Устанавливает бюджет токенов для истории сообщений.
+    ///
+    /// Перед каждым запросом история сокращается согласно
+    /// [`CompactionStrategy`], пока ее оценка не уложится в бюджет.
+    pub fn with_token_budget(mut self, token_budget: usize) -> Self {
+        self.token_budget = Some(token_budget);
+        self
+    }
+
+    /// Устанавливает стратегию сокращения истории.
+    pub fn with_compaction_strategy(mut self, strategy: CompactionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Устанавливает параметры генерации.
+    pub fn with_config(mut self, config: GenerationConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Добавляет пользовательскую функцию.
+    pub fn with_user_function(mut self, user_function: UserFunction) -> Self {
+        self.functions.push(Function::User(user_function));
+        self
+    }
+
+    /// Добавляет встроенную функцию.
+    pub fn with_builtin_function(mut self, function: FunctionName) -> Self {
+        self.functions.push(Function::BuiltIn(function));
+        self
+    }
+
+    /// Устанавливает режим вызова функций.
+    pub fn with_function_call(mut self, function_call: FunctionCall) -> Self {
+        self.function_call = function_call;
+        self
+    }
+
+    /// Собирает диалог.
+    pub fn build(self) -> Conversation {
+        Conversation {
+            client: self.client,
+            session_id: self
+                .session_id
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            forked_from: None,
+            model: self.model,
+            system_prompt: self.system_prompt,
+            summary: None,
+            entries: Vec::new(),
+            token_budget: self.token_budget,
+            strategy: self.strategy,
+            config: self.config,
+            functions: self.functions,
+            function_call: self.function_call,
+            tokens_per_char: DEFAULT_TOKENS_PER_CHAR,
+        }
+    }
+}
+
+/// Диалог с GigaChat, хранящий историю сообщений и идентификатор сессии.
+///
+/// Состояние диалога можно сохранить при помощи [`Conversation::snapshot`]
+/// в любом формате, поддерживаемом `serde`, и восстановить через
+/// [`Conversation::restore`]. [`Conversation::fork`] создает ответвление,
+/// позволяющее получить альтернативный ответ на любом шаге.
+pub struct Conversation {
+    client: GigaChatClient,
+    session_id: String,
+    forked_from: Option<ForkOrigin>,
+    model: Model,
+    system_prompt: Option<String>,
+    summary: Option<String>,
+    entries: Vec<ConversationEntry>,
+    token_budget: Option<usize>,
+    strategy: CompactionStrategy,
+    config: GenerationConfig,
+    functions: Vec<Function>,
+    function_call: FunctionCall,
+    tokens_per_char: f64,
+}
+
+/// Количество символов, которое сообщение занимает в контексте.
+fn message_chars(message: &Message) -> usize {
+    match message {
+        Message::System { content } | Message::User { content, .. } => content.chars().count(),
+        Message::Assistant {
+            content,
+            function_call,
+        } => {
+            content.chars().count()
+                + function_call
+                    .as_ref()
+                    .map_or(0, |call| call.arguments.to_string().chars().count())
+        }
+        Message::Function { content, .. } => content.to_string().chars().count(),
+    }
+}
+
+impl Conversation {
+    /// Идентификатор сессии.
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Происхождение диалога, если он является ответвлением.
+    pub fn forked_from(&self) -> Option<&ForkOrigin> {
+        self.forked_from.as_ref()
+    }
+
+    /// История сообщений с метаданными, без системного промпта.
+    pub fn entries(&self) -> &[ConversationEntry] {
+        &self.entries
+    }
+
+    /// Краткий пересказ вытесненной части диалога.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    /// Добавляет сообщение в историю, например результат вызова функции.
+    pub fn push(&mut self, message: Message) {
+        self.entries.push(ConversationEntry::new(message));
+    }
+
+    /// Сообщения, которые будут отправлены модели: системный промпт
+    /// с пересказом и история.
+    pub fn messages(&self) -> Vec<Message> {
+        let system = match (&self.system_prompt, &self.summary) {
+            (Some(prompt), Some(summary)) => Some(format!(
+                "{prompt}\n\nКраткое содержание предыдущей части диалога:\n{summary}"
+            )),
+            (Some(prompt), None) => Some(prompt.clone()),
+            (None, Some(summary)) => Some(format!(
+                "Краткое содержание предыдущей части диалога:\n{summary}"
+            )),
+            (None, None) => None,
+        };
+
+        system
+            .map(Message::system)
+            .into_iter()
+            .chain(self.entries.iter().map(|entry| entry.message.clone()))
+            .collect()
+    }
+
+    /// Оценка количества токенов в сообщениях, отправляемых модели.
+    ///
+    /// Коэффициент пересчета символов в токены калибруется по
+    /// [`Usage`](crate::generation::structures::Usage) каждого ответа.
+    pub fn estimated_tokens(&self) -> usize {
+        self.estimate(self.messages().iter().map(message_chars).sum())
+    }
+
+    fn estimate(&self, chars: usize) -> usize {
+        (chars as f64 * self.tokens_per_char).ceil() as usize
+    }
+
+    /// Индексы начала реплик: каждая реплика начинается с сообщения
+    /// пользователя и включает все последующие ответы и вызовы функций.
+    fn turn_starts(&self) -> Vec<usize> {
+        let mut starts: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| matches!(entry.message, Message::User { .. }))
+            .map(|(index, _)| index)
+            .collect();
+        if starts.first() != Some(&0) && !self.entries.is_empty() {
+            starts.insert(0, 0);
+        }
+        starts
+    }
+
+    /// Сокращает историю, пока ее оценка не уложится в бюджет токенов.
+    ///
+    /// Последняя реплика никогда не удаляется, а вызовы функций
+    /// удаляются только вместе с их результатами. При стратегии
+    /// [`CompactionStrategy::Summarize`] история меняется только после
+    /// успешного пересказа, поэтому ошибка не приводит к потере сообщений.
+    #[tracing::instrument(skip_all, fields(session.id = self.session_id, dropped))]
+    async fn compact(&mut self) -> Result<(), error::Error> {
+        let Some(budget) = self.token_budget else {
+            return Ok(());
+        };
+
+        let starts = self.turn_starts();
+        let mut total = self.estimated_tokens();
+        let mut cut = 0;
+        for window in starts.windows(2) {
+            if total <= budget {
+                break;
+            }
+            let chars = self.entries[window[0]..window[1]]
+                .iter()
+                .map(|entry| message_chars(&entry.message))
+                .sum();
+            total = total.saturating_sub(self.estimate(chars));
+            cut = window[1];
+        }
+
+        if cut == 0 {
+            return Ok(());
+        }
+        tracing::Span::current().record("dropped", cut);
+
+        if self.strategy == CompactionStrategy::Summarize {
+            self.summary = Some(self.summarize(&self.entries[..cut]).await?);
+        }
+        self.entries.drain(..cut);
+        Ok(())
+    }
+
+    /// Пересказывает вытесняемые сообщения вместе с предыдущим пересказом.
+    async fn summarize(&self, dropped: &[ConversationEntry]) -> Result<String, error::Error> {
+        let mut transcript = self
+            .summary
+            .as_ref()
+            .map(|summary| format!("Ранее: {summary}\n"))
+            .unwrap_or_default();
+
+        for entry in dropped {
+            let line = match &entry.message {
+                Message::System { content } => format!("Система: {content}"),
+                Message::User { content, .. } => format!("Пользователь: {content}"),
+                Message::Assistant {
+                    content,
+                    function_call: Some(call),
+                } => format!(
+                    "Ассистент: {content} [вызов {}: {}]",
+                    call.name.as_str(),
+                    call.arguments
+                ),
+                Message::Assistant { content, .. } => format!("Ассистент: {content}"),
+                Message::Function { name, content } => {
+                    format!("Функция {}: {content}", name.as_str())
+                }
+            };
+            transcript.push_str(&line);
+            transcript.push('\n');
+        }
+
+        let response = self
+            .client
+            .generate()
+            .with_model(self.model.clone())
+            .with_session_id(&self.session_id)
+            .with_messages(vec![
+                Message::system(SUMMARY_PROMPT),
+                Message::user(transcript),
+            ])
+            .execute()
+            .await
+            .context(error::SummarizeSnafu)?;
+
+        Ok(response.text())
+    }
+
+    /// Отправляет сообщение пользователя и добавляет ответ ассистента в историю.
+    ///
+    /// При ошибке сообщение пользователя остается в истории, и запрос
+    /// можно повторить через [`Conversation::reply`].
+    pub async fn send<S: Into<String>>(
+        &mut self,
+        user_text: S,
+    ) -> Result<GenerationResponse, error::Error> {
+        self.push(Message::user(user_text));
+        self.reply().await
+    }
+
+    /// Запрашивает ответ ассистента на текущую историю.
+    ///
+    /// Используется, например, после добавления результата вызова функции
+    /// через [`Conversation::push`].
+    #[tracing::instrument(skip_all, fields(session.id = self.session_id))]
+    pub async fn reply(&mut self) -> Result<GenerationResponse, error::Error> {
+        self.compact().await?;
+
+        let chars: usize = self.messages().iter().map(message_chars).sum();
+        let request_id = Uuid::new_v4().to_string();
+
+        let response = self
+            .generation()
+            .with_header(
+                REQUEST_ID_HEADER,
+                HeaderValue::from_str(&request_id).expect("UUID is a valid header value"),
+            )
+            .execute()
+            .await
+            .context(error::GenerateSnafu)?;
+
+        let message = response
+            .choices
+            .first()
+            .context(error::EmptyResponseSnafu)?
+            .message
+            .clone();
+        self.entries.push(ConversationEntry {
+            message,
+            created_at: response.created,
+            model: Some(response.model.clone()),
+            usage: Some(response.usage.clone()),
+            request_id: Some(request_id),
+        });
+
+        let prompt_tokens = response.usage.prompt_tokens + response.usage.precached_prompt_tokens;
+        if chars > 0 && prompt_tokens > 0 {
+            self.tokens_per_char = f64::from(prompt_tokens) / chars as f64;
+        }
+
+        Ok(response)
+    }
+
+    /// Создает сборщик генерации, воспроизводящий текущее состояние диалога:
+    /// модель, параметры, функции, идентификатор сессии и сообщения.
+    pub fn generation(&self) -> GenerationBuilder {
+        self.client
+            .generate()
+            .with_model(self.model.clone())
+            .with_session_id(&self.session_id)
+            .with_config(self.config)
+            .with_functions(self.functions.iter().cloned())
+            .with_function_call(self.function_call.clone())
+            .with_messages(self.messages())
+    }
+
+    /// Создает ответвление диалога, содержащее сообщения до `index` (не включая).
+    ///
+    /// Ответвление получает новый идентификатор сессии и ссылку на исходный диалог.
+    pub fn fork(&self, index: usize) -> Result<Conversation, error::Error> {
+        ensure!(
+            index <= self.entries.len(),
+            error::ForkIndexOutOfRangeSnafu {
+                index,
+                length: self.entries.len(),
+            }
+        );
+
+        Ok(Conversation {
+            client: self.client.clone(),
+            session_id: Uuid::new_v4().to_string(),
+            forked_from: Some(ForkOrigin {
+                session_id: self.session_id.clone(),
+                index,
+            }),
+            model: self.model.clone(),
+            system_prompt: self.system_prompt.clone(),
+            summary: self.summary.clone(),
+            entries: self.entries[..index].to_vec(),
+            token_budget: self.token_budget,
+            strategy: self.strategy,
+            config: self.config,
+            functions: self.functions.clone(),
+            function_call: self.function_call.clone(),
+            tokens_per_char: self.tokens_per_char,
+        })
+    }
+
+    /// Возвращает сериализуемое состояние диалога.
+    pub fn snapshot(&self) -> ConversationSnapshot {
+        ConversationSnapshot {
+            version: SNAPSHOT_VERSION,
+            session_id: self.session_id.clone(),
+            forked_from: self.forked_from.clone(),
+            model: self.model.clone(),
+            system_prompt: self.system_prompt.clone(),
+            summary: self.summary.clone(),
+            entries: self.entries.clone(),
+            token_budget: self.token_budget,
+            strategy: self.strategy,
+            config: self.config,
+            functions: self.functions.clone(),
+            function_call: self.function_call.clone(),
+            tokens_per_char: self.tokens_per_char,
+        }
+    }
+
+    /// Восстанавливает диалог из сохраненного состояния.
+    pub fn restore(
+        client: GigaChatClient,
+        snapshot: ConversationSnapshot,
+    ) -> Result<Conversation, error::Error> {
+        ensure!(
+            snapshot.version == SNAPSHOT_VERSION,
+            error::UnsupportedSnapshotVersionSnafu {
+                version: snapshot.version,
+            }
+        );
+
+        Ok(Conversation {
+            client,
+            session_id: snapshot.session_id,
+            forked_from: snapshot.forked_from,
+            model: snapshot.model,
+            system_prompt: snapshot.system_prompt,
+            summary: snapshot.summary,
+            entries: snapshot.entries,
+            token_budget: snapshot.token_budget,
+            strategy: snapshot.strategy,
+            config: snapshot.config,
+            functions: snapshot.functions,
+            function_call: snapshot.function_call,
+            tokens_per_char: snapshot.tokens_per_char,
+        })
+    }
+}
+
+impl GigaChatClient {
+    /// Создает сборщик диалога.
+    pub fn conversation(&self) -> ConversationBuilder {
+        ConversationBuilder {
+            client: self.clone(),
+            session_id: None,
+            model: Model::default(),
+            system_prompt: None,
+            token_budget: None,
+            strategy: CompactionStrategy::default(),
+            config: GenerationConfig::default(),
+            functions: Vec::new(),
+            function_call: FunctionCall::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        generation::structures::FunctionCallResponse,
+        mock::{self, MockServer},
+    };
+
+    fn assistant(content: &str) -> Message {
+        Message::Assistant {
+            content: content.to_string(),
+            function_call: None,
+        }
+    }
+
+    fn history(conversation: &Conversation) -> Vec<Message> {
+        conversation
+            .entries()
+            .iter()
+            .map(|entry| entry.message.clone())
+            .collect()
+    }
+
+    /// Диалог из трех реплик, каждое сообщение — 10 символов;
+    /// один символ оценивается в один токен.
+    async fn conversation(
+        server: &MockServer,
+        strategy: CompactionStrategy,
+        budget: usize,
+    ) -> Conversation {
+        let mut conversation = server
+            .client()
+            .await
+            .conversation()
+            .with_token_budget(budget)
+            .with_compaction_strategy(strategy)
+            .build();
+        conversation.tokens_per_char = 1.0;
+
+        for message in [
+            Message::user("a".repeat(10)),
+            assistant(&"b".repeat(10)),
+            Message::user("c".repeat(10)),
+            assistant(&"d".repeat(10)),
+            Message::user("e".repeat(10)),
+        ] {
+            conversation.push(message);
+        }
+        conversation
+    }
+
+    fn failing_server() -> impl Fn(&mock::Request) -> (u16, serde_json::Value) {
+        |_| (500, json!({ "message": "internal error" }))
+    }
+
+    #[tokio::test]
+    async fn turns_start_with_user_messages() {
+        let server = MockServer::start(failing_server()).await;
+        let mut conversation = server.client().await.conversation().build();
+        assert!(conversation.turn_starts().is_empty());
+
+        let call = FunctionCallResponse {
+            name: FunctionName::text2image(),
+            arguments: json!({ "query": "кот" }),
+        };
+        for message in [
+            assistant("приветствие"),
+            Message::user("вопрос"),
+            Message::Assistant {
+                content: String::new(),
+                function_call: Some(call),
+            },
+            Message::function(FunctionName::text2image(), json!({ "ok": true })),
+            assistant("ответ"),
+            Message::user("еще вопрос"),
+        ] {
+            conversation.push(message);
+        }
+        assert_eq!(conversation.turn_starts(), [0, 1, 5]);
+
+        let mut conversation = server.client().await.conversation().build();
+        for message in [Message::user("1"), assistant("2"), Message::user("3")] {
+            conversation.push(message);
+        }
+        assert_eq!(conversation.turn_starts(), [0, 2]);
+    }
+
+    #[tokio::test]
+    async fn history_within_budget_is_kept() {
+        let server = MockServer::start(failing_server()).await;
+        let mut conversation = conversation(&server, CompactionStrategy::Summarize, 50).await;
+        assert_eq!(conversation.estimated_tokens(), 50);
+
+        conversation.compact().await.unwrap();
+        assert_eq!(conversation.entries().len(), 5);
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn trim_drops_oldest_turns() {
+        let server = MockServer::start(failing_server()).await;
+        let mut conversation = conversation(&server, CompactionStrategy::Trim, 30).await;
+
+        conversation.compact().await.unwrap();
+        assert_eq!(
+            history(&conversation),
+            [
+                Message::user("c".repeat(10)),
+                assistant(&"d".repeat(10)),
+                Message::user("e".repeat(10)),
+            ]
+        );
+        assert_eq!(conversation.estimated_tokens(), 30);
+        assert!(conversation.summary().is_none());
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn trim_keeps_last_turn() {
+        let server = MockServer::start(failing_server()).await;
+        let mut conversation = conversation(&server, CompactionStrategy::Trim, 1).await;
+
+        conversation.compact().await.unwrap();
+        assert_eq!(history(&conversation), [Message::user("e".repeat(10))]);
+    }
+
+    #[tokio::test]
+    async fn summarize_replaces_oldest_turns() {
+        let server =
+            MockServer::start(|_| (200, mock::completion("GigaChat-2-Max", "пересказ"))).await;
+        let mut conversation = conversation(&server, CompactionStrategy::Summarize, 30).await;
+
+        conversation.compact().await.unwrap();
+        assert_eq!(conversation.summary(), Some("пересказ"));
+        assert_eq!(conversation.entries().len(), 3);
+        assert_eq!(
+            conversation.messages()[0],
+            Message::system("Краткое содержание предыдущей части диалога:\nпересказ")
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let body = requests[0].json();
+        assert_eq!(
+            body["messages"][1]["content"],
+            format!(
+                "Пользователь: {}\nАссистент: {}\n",
+                "a".repeat(10),
+                "b".repeat(10)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_summary_keeps_history() {
+        let server = MockServer::start(failing_server()).await;
+        let mut conversation = conversation(&server, CompactionStrategy::Summarize, 30).await;
+        let mut expected = history(&conversation);
+
+        let error = conversation.compact().await.unwrap_err();
+        assert!(matches!(error, error::Error::Summarize { .. }), "{error:?}");
+        assert_eq!(history(&conversation), expected);
+        assert!(conversation.summary().is_none());
+
+        let error = conversation.send("f".repeat(10)).await.unwrap_err();
+        assert!(matches!(error, error::Error::Summarize { .. }), "{error:?}");
+        expected.push(Message::user("f".repeat(10)));
+        assert_eq!(history(&conversation), expected);
+    }
+
+    #[tokio::test]
+    async fn failed_send_can_be_retried() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let server = MockServer::start(move |_| match counter.fetch_add(1, Ordering::SeqCst) {
+            0 => (500, json!({ "message": "internal error" })),
+            _ => (200, mock::completion("GigaChat-2-Max", "ответ")),
+        })
+        .await;
+        let mut conversation = server.client().await.conversation().build();
+
+        let error = conversation.send("вопрос").await.unwrap_err();
+        assert!(matches!(error, error::Error::Generate { .. }), "{error:?}");
+        assert_eq!(history(&conversation), [Message::user("вопрос")]);
+
+        let response = conversation.reply().await.unwrap();
+        assert_eq!(response.text(), "ответ");
+        assert_eq!(
+            history(&conversation),
+            [Message::user("вопрос"), assistant("ответ")]
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    const SNAPSHOT_V1: &str = include_str!("../../tests/fixtures/conversation_snapshot_v1.json");
+
+    #[tokio::test]
+    async fn snapshot_v1_fixture_is_restored() {
+        let server = MockServer::start(failing_server()).await;
+        let snapshot = serde_json::from_str(SNAPSHOT_V1).unwrap();
+        let conversation = Conversation::restore(server.client().await, snapshot).unwrap();
+
+        assert_eq!(conversation.session_id(), "session-1");
+        assert_eq!(
+            conversation.forked_from(),
+            Some(&ForkOrigin {
+                session_id: "session-0".to_string(),
+                index: 2,
+            })
+        );
+        assert_eq!(conversation.entries().len(), 3);
+        assert_eq!(
+            conversation.entries()[1].request_id.as_deref(),
+            Some("request-1")
+        );
+        assert!(matches!(
+            &conversation.entries()[2].message,
+            Message::Function { name, content }
+                if name.as_str() == "add" && *content == json!({ "sum": 5 })
+        ));
+        assert_eq!(
+            conversation.messages()[0],
+            Message::system(
+                "Ты — вежливый ассистент.\n\n\
+                 Краткое содержание предыдущей части диалога:\n\
+                 Пользователь поздоровался."
+            )
+        );
+
+        let expected: serde_json::Value = serde_json::from_str(SNAPSHOT_V1).unwrap();
+        assert_eq!(
+            serde_json::to_value(conversation.snapshot()).unwrap(),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn snapshot_round_trip() {
+        let server = MockServer::start(failing_server()).await;
+        let mut conversation = conversation(&server, CompactionStrategy::Summarize, 100).await;
+        conversation.push(Message::function(
+            FunctionName::text2image(),
+            json!({ "url": "https://example.com/\"кот\".png" }),
+        ));
+
+        let snapshot = serde_json::to_string(&conversation.snapshot()).unwrap();
+        let restored = Conversation::restore(
+            server.client().await,
+            serde_json::from_str(&snapshot).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(history(&restored), history(&conversation));
+        assert_eq!(restored.session_id(), conversation.session_id());
+        assert_eq!(
+            serde_json::to_string(&restored.snapshot()).unwrap(),
+            snapshot
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_snapshot_version_is_rejected() {
+        let server = MockServer::start(failing_server()).await;
+        let mut snapshot: serde_json::Value = serde_json::from_str(SNAPSHOT_V1).unwrap();
+        snapshot["version"] = json!(SNAPSHOT_VERSION + 1);
+
+        let result = Conversation::restore(
+            server.client().await,
+            serde_json::from_value(snapshot).unwrap(),
+        );
+        assert!(matches!(
+            result,
+            Err(error::Error::UnsupportedSnapshotVersion { version }) if version == SNAPSHOT_VERSION + 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn fork_copies_history_prefix() {
+        let server = MockServer::start(failing_server()).await;
+        let conversation = conversation(&server, CompactionStrategy::Trim, 100).await;
+
+        let fork = conversation.fork(2).unwrap();
+        assert_eq!(history(&fork), history(&conversation)[..2].to_vec());
+        assert_ne!(fork.session_id(), conversation.session_id());
+        assert_eq!(
+            fork.forked_from(),
+            Some(&ForkOrigin {
+                session_id: conversation.session_id().to_string(),
+                index: 2,
+            })
+        );
+        assert_eq!(fork.snapshot().forked_from.as_ref(), fork.forked_from());
+
+        assert!(conversation.fork(0).unwrap().entries().is_empty());
+        assert_eq!(conversation.fork(5).unwrap().entries().len(), 5);
+        assert!(matches!(
+            conversation.fork(6),
+            Err(error::Error::ForkIndexOutOfRange {
+                index: 6,
+                length: 5
+            })
+        ));
+    }
+}
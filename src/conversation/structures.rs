@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::CompactionStrategy;
+use crate::generation::{
+    Model,
+    structures::{Function, FunctionCall, GenerationConfig, Message, Usage},
+};
+
+/// Текущая версия формата сохранения диалога.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Сообщение диалога вместе с метаданными.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationEntry {
+    /// Сообщение, включая вызовы функций и вложения.
+    pub message: Message,
+    /// Время добавления сообщения в историю.
+    #[serde(with = "time::serde::timestamp::milliseconds")]
+    pub created_at: OffsetDateTime,
+    /// Модель, сгенерировавшая ответ.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<Model>,
+    /// Потребление токенов запросом, вернувшим это сообщение.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    /// Идентификатор запроса (`X-Request-ID`), вернувшего это сообщение.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ConversationEntry {
+    /// Создает запись для сообщения, добавленного вызывающей стороной.
+    pub fn new(message: Message) -> Self {
+        Self {
+            message,
+            created_at: OffsetDateTime::now_utc(),
+            model: None,
+            usage: None,
+            request_id: None,
+        }
+    }
+}
+
+/// Происхождение ответвления диалога.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkOrigin {
+    /// Идентификатор сессии исходного диалога.
+    pub session_id: String,
+    /// Индекс сообщения, начиная с которого история была отброшена.
+    pub index: usize,
+}
+
+/// Сериализуемое состояние диалога.
+///
+/// Формат версионируется полем `version`; при восстановлении поддерживается
+/// только [`SNAPSHOT_VERSION`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationSnapshot {
+    /// Версия формата.
+    pub version: u32,
+    /// Идентификатор сессии.
+    pub session_id: String,
+    /// Происхождение, если диалог является ответвлением другого.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<ForkOrigin>,
+    /// Модель для генерации ответов.
+    pub model: Model,
+    /// Системный промпт.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Краткий пересказ вытесненной части диалога.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// История сообщений.
+    pub entries: Vec<ConversationEntry>,
+    /// Бюджет токенов.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<usize>,
+    /// Стратегия сокращения истории.
+    #[serde(default)]
+    pub strategy: CompactionStrategy,
+    /// Параметры генерации.
+    #[serde(default)]
+    pub config: GenerationConfig,
+    /// Доступные функции.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub functions: Vec<Function>,
+    /// Режим вызова функций.
+    #[serde(default)]
+    pub function_call: FunctionCall,
+    /// Откалиброванное количество токенов на символ.
+    pub tokens_per_char: f64,
+}
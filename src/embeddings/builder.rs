@@ -0,0 +1,239 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use snafu::prelude::*;
+
+use super::{
+    ChunkingConfig, Embeddings, Model, error,
+    structures::{EmbeddingRequest, Input, TextEmbedding, TextEmbeddings},
+};
+use crate::client::GigaChatClient;
+
+/// Сборщик запроса векторизации.
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::client::GigaChatClientBuilder;
+/// use gigachat_rust::embeddings::Model;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let embeddings = client
+///         .embeddings()
+///         .with_model(Model::EmbeddingsGigaR)
+///         .with_input("Первая строка")
+///         .with_input("Вторая строка")
+///         .execute()
+///         .await
+///         .unwrap();
+///
+///     for item in embeddings.items {
+///         println!("{}: {} dimensions", item.text, item.embedding.len());
+///     }
+/// }
+/// ```
+pub struct EmbeddingsBuilder {
+    client: GigaChatClient,
+    model: Model,
+    inputs: Vec<String>,
+    headers: HeaderMap,
+    chunking: Option<ChunkingConfig>,
+}
+
+impl EmbeddingsBuilder {
+    /// Устанавливает модель для векторизации.
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Добавляет строку для векторизации.
+    pub fn with_input<S: Into<String>>(mut self, input: S) -> Self {
+        self.inputs.push(input.into());
+        self
+    }
+
+    /// Добавляет строки для векторизации.
+    pub fn with_inputs<I: IntoIterator<Item = S>, S: Into<String>>(mut self, inputs: I) -> Self {
+        self.inputs.extend(inputs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Добавляет произвольный HTTP-заголовок к запросу.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Включает разбиение входных данных на несколько запросов.
+    ///
+    /// См. [`Embeddings::create_many`].
+    pub fn with_chunking(mut self, chunking: ChunkingConfig) -> Self {
+        self.chunking = Some(chunking);
+        self
+    }
+
+    /// Собирает запрос векторизации.
+    pub fn build(self) -> EmbeddingRequest {
+        EmbeddingRequest {
+            model: self.model,
+            input: Input::Many(self.inputs),
+        }
+    }
+
+    /// Выполняет запрос векторизации.
+    ///
+    /// Результаты упорядочены по индексу и сопоставлены с исходными строками.
+    #[tracing::instrument(skip_all, fields(embeddings.inputs = self.inputs.len()), err)]
+    pub async fn execute(self) -> Result<TextEmbeddings, error::Error> {
+        ensure!(!self.inputs.is_empty(), error::InputIsMissingSnafu);
+
+        let embeddings = Embeddings {
+            client: self.client,
+            headers: self.headers,
+        };
+
+        let mut response = match self.chunking {
+            Some(chunking) => {
+                embeddings
+                    .create_many(self.inputs.clone(), Some(self.model), chunking)
+                    .await?
+            }
+            None => {
+                let request = EmbeddingRequest {
+                    model: self.model,
+                    input: Input::Many(self.inputs.clone()),
+                };
+                embeddings.execute(&request).await?
+            }
+        };
+
+        ensure!(
+            response.data.len() == self.inputs.len(),
+            error::ResponseMismatchSnafu {
+                expected: self.inputs.len(),
+                actual: response.data.len(),
+            }
+        );
+        response.data.sort_by_key(|item| item.index);
+
+        let items = response
+            .data
+            .into_iter()
+            .zip(self.inputs)
+            .enumerate()
+            .map(|(index, (item, text))| TextEmbedding {
+                index,
+                text,
+                embedding: item.embedding,
+                usage: item.usage,
+            })
+            .collect();
+
+        Ok(TextEmbeddings {
+            model: response.model,
+            items,
+        })
+    }
+}
+
+impl GigaChatClient {
+    /// Создает сборщик запроса векторизации.
+    pub fn embeddings(&self) -> EmbeddingsBuilder {
+        EmbeddingsBuilder {
+            client: self.clone(),
+            model: Model::default(),
+            inputs: Vec::new(),
+            headers: HeaderMap::new(),
+            chunking: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::mock::{self, MockServer};
+
+    /// Ответ с вектором `[i]` для каждой входной строки, в обратном порядке.
+    fn reversed(request: &mock::Request) -> (u16, Value) {
+        let count = mock::embedding_inputs(request).len();
+        let vectors: Vec<_> = (0..count).map(|index| vec![index as f32]).collect();
+        let mut response = mock::embeddings(&vectors);
+        response["data"].as_array_mut().unwrap().reverse();
+        (200, response)
+    }
+
+    #[tokio::test]
+    async fn missing_input_is_rejected() {
+        let server = MockServer::start(reversed).await;
+
+        let result = server.client().await.embeddings().execute().await;
+
+        assert!(matches!(result, Err(error::Error::InputIsMissing)));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn inputs_keep_order() {
+        let server = MockServer::start(reversed).await;
+        let client = server.client().await;
+        let embeddings = || {
+            client
+                .embeddings()
+                .with_input("первая")
+                .with_inputs(["вторая", "третья"])
+                .with_input("четвертая")
+        };
+
+        let expected = ["первая", "вторая", "третья", "четвертая"];
+        assert_eq!(
+            embeddings().build().input,
+            Input::Many(expected.iter().map(|text| text.to_string()).collect())
+        );
+
+        let response = embeddings().execute().await.unwrap();
+        let items: Vec<_> = response
+            .items
+            .iter()
+            .map(|item| (item.index, item.text.as_str(), item.embedding[0]))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                (0, "первая", 0.0),
+                (1, "вторая", 1.0),
+                (2, "третья", 2.0),
+                (3, "четвертая", 3.0),
+            ]
+        );
+        assert_eq!(mock::embedding_inputs(&server.requests()[0]), expected);
+    }
+
+    #[tokio::test]
+    async fn response_count_mismatch() {
+        let server = MockServer::start(|_| (200, mock::embeddings(&[vec![0.0]]))).await;
+
+        let result = server
+            .client()
+            .await
+            .embeddings()
+            .with_inputs(["первая", "вторая"])
+            .execute()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(error::Error::ResponseMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,279 @@
+use std::{ops::Range, time::Duration};
+
+use futures::{StreamExt, TryStreamExt, stream};
+use snafu::prelude::*;
+
+use super::{
+    Embeddings, Model, error,
+    structures::{EmbeddingRequest, EmbeddingResponse, EmbeddingResponseItem, Input},
+};
+use crate::client::error::ErrorClass;
+
+/// Параметры разбиения входных данных на запросы.
+#[derive(Debug, Clone)]
+pub struct ChunkingConfig {
+    /// Максимальное количество строк в одном запросе.
+    pub max_items: usize,
+    /// Максимальное суммарное количество символов в одном запросе.
+    ///
+    /// Строка, превышающая лимит, отправляется отдельным запросом.
+    pub max_chars: usize,
+    /// Максимальное количество одновременно выполняемых запросов.
+    pub concurrency: usize,
+    /// Количество повторов запроса при временных ошибках.
+    pub max_retries: u32,
+    /// Задержка перед первым повтором; удваивается с каждой попыткой.
+    pub retry_delay: Duration,
+    /// Классы ошибок, при которых запрос повторяется.
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            max_items: 100,
+            max_chars: 100_000,
+            concurrency: 4,
+            max_retries: 3,
+            retry_delay: Duration::from_millis(500),
+            retry_on: vec![
+                ErrorClass::RateLimited,
+                ErrorClass::ServerError,
+                ErrorClass::Connection,
+            ],
+        }
+    }
+}
+
+impl ChunkingConfig {
+    /// Разбивает входные строки на диапазоны, укладывающиеся в лимиты.
+    fn split(&self, inputs: &[String]) -> Vec<Range<usize>> {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        let mut chars = 0;
+
+        for (index, input) in inputs.iter().enumerate() {
+            let length = input.chars().count();
+            let full = index - start >= self.max_items.max(1);
+            if index > start && (full || chars + length > self.max_chars) {
+                chunks.push(start..index);
+                start = index;
+                chars = 0;
+            }
+            chars += length;
+        }
+
+        if start < inputs.len() {
+            chunks.push(start..inputs.len());
+        }
+        chunks
+    }
+}
+
+/// Объединяет ответы на части запроса в порядке входных строк.
+///
+/// Ответ на каждую часть должен содержать ровно один вектор для каждой
+/// строки своего диапазона, иначе возвращается [`error::Error::ResponseMismatch`].
+/// Индексы элементов сдвигаются на начало диапазона.
+fn merge_chunks(
+    responses: Vec<(Range<usize>, EmbeddingResponse)>,
+    total: usize,
+) -> Result<Vec<EmbeddingResponseItem>, error::Error> {
+    let mut data = Vec::with_capacity(total);
+    for (range, response) in responses {
+        let mut present = vec![false; range.len()];
+        for item in &response.data {
+            if let Some(slot) = usize::try_from(item.index)
+                .ok()
+                .and_then(|index| present.get_mut(index))
+            {
+                *slot = true;
+            }
+        }
+
+        // При совпадающем количестве элементов повторяющиеся индексы и индексы
+        // вне диапазона означают, что часть строк осталась без вектора.
+        let actual = if response.data.len() == range.len() {
+            present.iter().filter(|&&present| present).count()
+        } else {
+            response.data.len()
+        };
+        ensure!(
+            actual == range.len(),
+            error::ResponseMismatchSnafu {
+                expected: range.len(),
+                actual,
+            }
+        );
+
+        data.extend(response.data.into_iter().map(|mut item| {
+            item.index += range.start as u64;
+            item
+        }));
+    }
+
+    data.sort_by_key(|item| item.index);
+    Ok(data)
+}
+
+impl Embeddings {
+    /// Создает векторные представления для большого набора строк.
+    ///
+    /// Входные данные разбиваются на запросы согласно [`ChunkingConfig`],
+    /// которые выполняются с ограниченным параллелизмом. Запросы, завершившиеся
+    /// временной ошибкой, повторяются с экспоненциальной задержкой.
+    /// Элементы результата упорядочены по индексу входной строки,
+    /// а [`EmbeddingResponse::usage`] учитывает все запросы.
+    #[tracing::instrument(skip_all, fields(
+        embeddings.inputs = inputs.len(),
+        embeddings.chunks,
+    ), err)]
+    pub async fn create_many(
+        &self,
+        inputs: Vec<String>,
+        model: Option<Model>,
+        config: ChunkingConfig,
+    ) -> Result<EmbeddingResponse, error::Error> {
+        let model = model.unwrap_or_default();
+        let chunks = config.split(&inputs);
+        tracing::Span::current().record("embeddings.chunks", chunks.len());
+
+        let responses: Vec<(Range<usize>, EmbeddingResponse)> = stream::iter(chunks)
+            .map(|range| {
+                let request = EmbeddingRequest {
+                    model: model.clone(),
+                    input: Input::Many(inputs[range.clone()].to_vec()),
+                };
+                let config = &config;
+                async move {
+                    let response = self.execute_with_retries(&request, config).await?;
+                    Ok::<_, error::Error>((range, response))
+                }
+            })
+            .buffer_unordered(config.concurrency.max(1))
+            .try_collect()
+            .await?;
+
+        let data = merge_chunks(responses, inputs.len())?;
+        Ok(EmbeddingResponse { model, data })
+    }
+
+    async fn execute_with_retries(
+        &self,
+        request: &EmbeddingRequest,
+        config: &ChunkingConfig,
+    ) -> Result<EmbeddingResponse, error::Error> {
+        let mut attempt = 0;
+        loop {
+            match self.execute(request).await {
+                Ok(response) => return Ok(response),
+                Err(error)
+                    if attempt < config.max_retries
+                        && error.class().is_some_and(|c| config.retry_on.contains(&c)) =>
+                {
+                    let delay = config.retry_delay * 2u32.saturating_pow(attempt);
+                    tracing::warn!(attempt, error = %error, ?delay, "retrying embeddings chunk");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::structures::Usage;
+
+    fn inputs(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    fn response(indices: &[u64]) -> EmbeddingResponse {
+        EmbeddingResponse {
+            model: Model::EmbeddingsGigaR,
+            data: indices
+                .iter()
+                .map(|&index| EmbeddingResponseItem {
+                    embedding: vec![index as f32],
+                    index,
+                    usage: Usage::default(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn split_by_item_count() {
+        let config = ChunkingConfig {
+            max_items: 2,
+            ..Default::default()
+        };
+
+        let chunks = config.split(&inputs(&["a", "b", "c", "d", "e"]));
+        assert_eq!(chunks, [0..2, 2..4, 4..5]);
+        assert!(config.split(&[]).is_empty());
+    }
+
+    #[test]
+    fn split_by_char_count() {
+        let config = ChunkingConfig {
+            max_chars: 5,
+            ..Default::default()
+        };
+
+        let chunks = config.split(&inputs(&["аб", "вг", "де"]));
+        assert_eq!(chunks, [0..2, 2..3]);
+
+        let chunks = config.split(&inputs(&["абв", "гд", "е"]));
+        assert_eq!(chunks, [0..2, 2..3]);
+    }
+
+    #[test]
+    fn oversized_input_is_sent_alone() {
+        let config = ChunkingConfig {
+            max_chars: 3,
+            ..Default::default()
+        };
+
+        let chunks = config.split(&inputs(&["a", "abcdef", "b"]));
+        assert_eq!(chunks, [0..1, 1..2, 2..3]);
+
+        let chunks = config.split(&inputs(&["abcdef"]));
+        assert_eq!(chunks, [0..1]);
+    }
+
+    #[test]
+    fn merge_offsets_and_sorts() {
+        let responses = vec![(3..5, response(&[1, 0])), (0..3, response(&[2, 0, 1]))];
+
+        let data = merge_chunks(responses, 5).unwrap();
+        let indices: Vec<_> = data.iter().map(|item| item.index).collect();
+        let embeddings: Vec<_> = data.iter().map(|item| item.embedding[0]).collect();
+        assert_eq!(indices, [0, 1, 2, 3, 4]);
+        assert_eq!(embeddings, [0.0, 1.0, 2.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn merge_rejects_misaligned_chunk() {
+        for (indices, actual) in [
+            (&[0][..], 1),
+            (&[0, 1, 2][..], 3),
+            (&[0, 0][..], 1),
+            (&[0, 2][..], 1),
+        ] {
+            let responses = vec![(0..2, response(&[0, 1])), (2..4, response(indices))];
+
+            let error = merge_chunks(responses, 4).unwrap_err();
+            assert!(
+                matches!(
+                    error,
+                    error::Error::ResponseMismatch { expected: 2, actual: a } if a == actual
+                ),
+                "{indices:?}: {error:?}"
+            );
+        }
+    }
+}
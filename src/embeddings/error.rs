@@ -12,4 +12,48 @@ pub enum Error {
     BuildUrl {
         source: crate::client::BuildUrlError,
     },
+
+    #[snafu(display("input is missing"))]
+    InputIsMissing,
+
+    #[snafu(display("response has {actual} embeddings for {expected} inputs"))]
+    ResponseMismatch { expected: usize, actual: usize },
+
+    #[snafu(display("vector dimension mismatch; expected {expected}, got {actual}"))]
+    DimensionMismatch { expected: usize, actual: usize },
+
+    #[snafu(display("index i/o error"))]
+    IndexIo { source: std::io::Error },
+
+    #[snafu(display("invalid index file: {message}"))]
+    IndexFormat { message: String },
+
+    #[snafu(display("failed to (de)serialize index payload"))]
+    IndexPayload { source: serde_json::Error },
+
+    #[snafu(display("export i/o error"))]
+    ExportIo { source: std::io::Error },
+
+    #[snafu(display("invalid exported data: {message}"))]
+    InvalidExport { message: String },
+
+    #[cfg(feature = "arrow")]
+    #[snafu(display("arrow error"))]
+    Arrow { source: arrow_schema::ArrowError },
+
+    #[cfg(feature = "parquet")]
+    #[snafu(display("parquet error"))]
+    Parquet {
+        source: parquet::errors::ParquetError,
+    },
+}
+
+impl Error {
+    /// Возвращает класс ошибки запроса, если он определен.
+    pub fn class(&self) -> Option<crate::client::error::ErrorClass> {
+        match self {
+            Error::RequestFailed { source } => source.class(),
+            _ => None,
+        }
+    }
 }
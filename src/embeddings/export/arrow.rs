@@ -0,0 +1,400 @@
+use std::{
+    io::{Read, Seek, Write},
+    sync::Arc,
+};
+
+use arrow_array::{
+    Array, ArrayRef, FixedSizeListArray, Float32Array, RecordBatch, StringArray, UInt32Array,
+    UInt64Array,
+    cast::AsArray,
+    types::{Float32Type, UInt32Type, UInt64Type},
+};
+use arrow_ipc::{reader::FileReader, writer::FileWriter};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use snafu::prelude::*;
+
+use super::dimension;
+use crate::embeddings::{
+    error,
+    structures::{TextEmbedding, Usage},
+};
+
+/// Имена колонок.
+const INDEX: &str = "index";
+const TEXT: &str = "text";
+const EMBEDDING: &str = "embedding";
+const PROMPT_TOKENS: &str = "prompt_tokens";
+
+fn invalid(message: impl Into<String>) -> error::Error {
+    error::InvalidExportSnafu {
+        message: message.into(),
+    }
+    .build()
+}
+
+/// Схема таблицы векторов указанной размерности.
+///
+/// Колонки: `index` (`UInt64`), `text` (`Utf8`), `embedding`
+/// (`FixedSizeList<Float32>` длины `dimension`) и `prompt_tokens` (`UInt32`).
+pub fn schema(dimension: usize) -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(INDEX, DataType::UInt64, false),
+        Field::new(TEXT, DataType::Utf8, false),
+        Field::new(
+            EMBEDDING,
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, false)),
+                dimension as i32,
+            ),
+            false,
+        ),
+        Field::new(PROMPT_TOKENS, DataType::UInt32, false),
+    ]))
+}
+
+/// Преобразует векторы в Arrow `RecordBatch` со схемой [`schema`].
+pub fn to_record_batch(items: &[TextEmbedding]) -> Result<RecordBatch, error::Error> {
+    let dimension = dimension(items)?;
+    let schema = schema(dimension);
+
+    let values = Float32Array::from_iter_values(
+        items.iter().flat_map(|item| item.embedding.iter().copied()),
+    );
+    let embeddings = FixedSizeListArray::try_new(
+        Arc::new(Field::new("item", DataType::Float32, false)),
+        dimension as i32,
+        Arc::new(values),
+        None,
+    )
+    .context(error::ArrowSnafu)?;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(
+            items.iter().map(|item| item.index as u64),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            items.iter().map(|item| item.text.as_str()),
+        )),
+        Arc::new(embeddings),
+        Arc::new(UInt32Array::from_iter_values(
+            items.iter().map(|item| item.usage.prompt_tokens),
+        )),
+    ];
+
+    RecordBatch::try_new(schema, columns).context(error::ArrowSnafu)
+}
+
+/// Читает векторы из `RecordBatch`.
+///
+/// Обязательны колонки `text` (`Utf8` или `LargeUtf8`) и `embedding`
+/// (`FixedSizeList<Float32>` или `List<Float32>`); при отсутствии `index`
+/// и `prompt_tokens` используются порядковые номера и нулевое потребление токенов.
+/// Пустые (`null`) значения в колонках считаются ошибкой формата.
+pub fn from_record_batch(batch: &RecordBatch) -> Result<Vec<TextEmbedding>, error::Error> {
+    record_batch_items(batch, 0)
+}
+
+/// Читает векторы из `RecordBatch`, начинающегося со строки `offset` файла.
+///
+/// При отсутствии колонки `index` индексом служит номер строки в файле.
+fn record_batch_items(
+    batch: &RecordBatch,
+    offset: usize,
+) -> Result<Vec<TextEmbedding>, error::Error> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .ok_or_else(|| invalid(format!("column `{name}` is missing")))
+    };
+    let null = |name: &str| invalid(format!("column `{name}` contains null values"));
+
+    let texts: Vec<String> = {
+        let texts = column(TEXT)?;
+        if texts.null_count() > 0 {
+            return Err(null(TEXT));
+        }
+        match texts.data_type() {
+            DataType::Utf8 => texts
+                .as_string::<i32>()
+                .iter()
+                .map(|text| text.unwrap_or_default().to_string())
+                .collect(),
+            DataType::LargeUtf8 => texts
+                .as_string::<i64>()
+                .iter()
+                .map(|text| text.unwrap_or_default().to_string())
+                .collect(),
+            other => return Err(invalid(format!("column `{TEXT}` has type {other}"))),
+        }
+    };
+
+    let embeddings: Vec<Vec<f32>> = {
+        let embeddings = column(EMBEDDING)?;
+        let rows: Vec<Option<ArrayRef>> = match embeddings.data_type() {
+            DataType::FixedSizeList(..) => embeddings.as_fixed_size_list().iter().collect(),
+            DataType::List(_) => embeddings.as_list::<i32>().iter().collect(),
+            other => return Err(invalid(format!("column `{EMBEDDING}` has type {other}"))),
+        };
+        rows.into_iter()
+            .map(|row| match row {
+                Some(row) if row.null_count() > 0 => Err(null(EMBEDDING)),
+                Some(row) if row.data_type() == &DataType::Float32 => {
+                    Ok(row.as_primitive::<Float32Type>().values().to_vec())
+                }
+                Some(row) => Err(invalid(format!(
+                    "column `{EMBEDDING}` has items of type {}",
+                    row.data_type()
+                ))),
+                None => Err(null(EMBEDDING)),
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    let indices = match batch.column_by_name(INDEX) {
+        Some(indices) if indices.null_count() > 0 => return Err(null(INDEX)),
+        Some(indices) if indices.data_type() == &DataType::UInt64 => {
+            Some(indices.as_primitive::<UInt64Type>().values().to_vec())
+        }
+        Some(indices) => {
+            return Err(invalid(format!(
+                "column `{INDEX}` has type {}",
+                indices.data_type()
+            )));
+        }
+        None => None,
+    };
+    let tokens = match batch.column_by_name(PROMPT_TOKENS) {
+        Some(tokens) if tokens.null_count() > 0 => return Err(null(PROMPT_TOKENS)),
+        Some(tokens) if tokens.data_type() == &DataType::UInt32 => {
+            Some(tokens.as_primitive::<UInt32Type>().values().to_vec())
+        }
+        Some(tokens) => {
+            return Err(invalid(format!(
+                "column `{PROMPT_TOKENS}` has type {}",
+                tokens.data_type()
+            )));
+        }
+        None => None,
+    };
+
+    Ok(embeddings
+        .into_iter()
+        .zip(texts)
+        .enumerate()
+        .map(|(row, (embedding, text))| TextEmbedding {
+            index: indices.as_ref().map_or(offset + row, |i| i[row] as usize),
+            text,
+            embedding,
+            usage: Usage {
+                prompt_tokens: tokens.as_ref().map_or(0, |t| t[row]),
+            },
+        })
+        .collect())
+}
+
+/// Записывает векторы в файл Arrow IPC (Feather v2).
+///
+/// В Python: `pyarrow.feather.read_table(path)` или `pandas.read_feather(path)`.
+pub fn write_ipc<W: Write>(items: &[TextEmbedding], writer: W) -> Result<(), error::Error> {
+    let batch = to_record_batch(items)?;
+    let mut writer = FileWriter::try_new(writer, &batch.schema()).context(error::ArrowSnafu)?;
+    writer.write(&batch).context(error::ArrowSnafu)?;
+    writer.finish().context(error::ArrowSnafu)
+}
+
+/// Читает векторы из файла Arrow IPC.
+pub fn read_ipc<R: Read + Seek>(reader: R) -> Result<Vec<TextEmbedding>, error::Error> {
+    let reader = FileReader::try_new(reader, None).context(error::ArrowSnafu)?;
+
+    let mut items = Vec::new();
+    for batch in reader {
+        let batch = batch.context(error::ArrowSnafu)?;
+        items.extend(record_batch_items(&batch, items.len())?);
+    }
+    Ok(items)
+}
+
+/// Записывает векторы в файл Parquet.
+///
+/// В Python: `pandas.read_parquet(path)` или `pyarrow.parquet.read_table(path)`.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: Write + Send>(
+    items: &[TextEmbedding],
+    writer: W,
+) -> Result<(), error::Error> {
+    let batch = to_record_batch(items)?;
+    let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)
+        .context(error::ParquetSnafu)?;
+    writer.write(&batch).context(error::ParquetSnafu)?;
+    writer.close().context(error::ParquetSnafu)?;
+    Ok(())
+}
+
+/// Читает векторы из файла Parquet.
+///
+/// Принимает любой источник, реализующий `ChunkReader`, например `std::fs::File`.
+#[cfg(feature = "parquet")]
+pub fn read_parquet<R: parquet::file::reader::ChunkReader + 'static>(
+    reader: R,
+) -> Result<Vec<TextEmbedding>, error::Error> {
+    let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(reader)
+        .context(error::ParquetSnafu)?
+        .build()
+        .context(error::ParquetSnafu)?;
+
+    let mut items = Vec::new();
+    for batch in reader {
+        let batch = batch.context(error::ArrowSnafu)?;
+        items.extend(record_batch_items(&batch, items.len())?);
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow_array::builder::{FixedSizeListBuilder, Float32Builder};
+
+    use super::*;
+
+    fn items() -> Vec<TextEmbedding> {
+        vec![
+            TextEmbedding {
+                index: 0,
+                text: "первый".into(),
+                embedding: vec![1.0, 2.0],
+                usage: Usage { prompt_tokens: 3 },
+            },
+            TextEmbedding {
+                index: 1,
+                text: "второй".into(),
+                embedding: vec![-0.5, 0.25],
+                usage: Usage { prompt_tokens: 4 },
+            },
+        ]
+    }
+
+    #[test]
+    fn ipc_round_trip() {
+        let items = items();
+        let mut bytes = Vec::new();
+        write_ipc(&items, &mut bytes).unwrap();
+
+        assert_eq!(read_ipc(Cursor::new(bytes)).unwrap(), items);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_round_trip() {
+        let items = items();
+        let path = std::env::temp_dir().join(format!("gigachat-{}.parquet", uuid::Uuid::new_v4()));
+        write_parquet(&items, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let read = read_parquet(std::fs::File::open(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), items);
+    }
+
+    fn batch(texts: StringArray, embeddings: FixedSizeListArray) -> RecordBatch {
+        RecordBatch::try_from_iter([
+            (TEXT, Arc::new(texts) as ArrayRef),
+            (EMBEDDING, Arc::new(embeddings) as ArrayRef),
+        ])
+        .unwrap()
+    }
+
+    fn embeddings(rows: &[Option<[f32; 2]>]) -> FixedSizeListArray {
+        let mut builder = FixedSizeListBuilder::new(Float32Builder::new(), 2);
+        for row in rows {
+            match row {
+                Some(values) => {
+                    builder.values().append_slice(values);
+                    builder.append(true);
+                }
+                None => {
+                    builder.values().append_nulls(2);
+                    builder.append(false);
+                }
+            }
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn optional_columns_default() {
+        let batch = batch(
+            StringArray::from(vec!["а", "б"]),
+            embeddings(&[Some([1.0, 0.0]), Some([0.0, 1.0])]),
+        );
+
+        let items = from_record_batch(&batch).unwrap();
+        assert_eq!(items[1].index, 1);
+        assert_eq!(items[1].usage.prompt_tokens, 0);
+        assert_eq!(items[1].embedding, [0.0, 1.0]);
+    }
+
+    #[test]
+    fn missing_index_continues_across_batches() {
+        let first = batch(
+            StringArray::from(vec!["а", "б"]),
+            embeddings(&[Some([1.0, 0.0]), Some([0.0, 1.0])]),
+        );
+        let second = batch(
+            StringArray::from(vec!["в"]),
+            embeddings(&[Some([1.0, 1.0])]),
+        );
+        let mut bytes = Vec::new();
+        let mut writer = FileWriter::try_new(&mut bytes, &first.schema()).unwrap();
+        writer.write(&first).unwrap();
+        writer.write(&second).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        let items = read_ipc(Cursor::new(bytes)).unwrap();
+        let indices: Vec<_> = items
+            .iter()
+            .map(|item| (item.index, item.text.as_str()))
+            .collect();
+        assert_eq!(indices, [(0, "а"), (1, "б"), (2, "в")]);
+    }
+
+    #[test]
+    fn empty_embeddings_are_not_written() {
+        let mut items = items();
+        for item in &mut items {
+            item.embedding.clear();
+        }
+
+        assert!(matches!(
+            write_ipc(&items, Vec::new()),
+            Err(error::Error::InvalidExport { .. })
+        ));
+    }
+
+    #[test]
+    fn null_text_is_rejected() {
+        let batch = batch(
+            StringArray::from(vec![Some("а"), None]),
+            embeddings(&[Some([1.0, 0.0]), Some([0.0, 1.0])]),
+        );
+
+        assert!(matches!(
+            from_record_batch(&batch),
+            Err(error::Error::InvalidExport { .. })
+        ));
+    }
+
+    #[test]
+    fn null_embedding_is_rejected() {
+        let batch = batch(
+            StringArray::from(vec!["а", "б"]),
+            embeddings(&[Some([1.0, 0.0]), None]),
+        );
+
+        assert!(matches!(
+            from_record_batch(&batch),
+            Err(error::Error::InvalidExport { .. })
+        ));
+    }
+}
@@ -0,0 +1,78 @@
+//! Выгрузка векторных представлений для анализа в Python.
+//!
+//! - [`npy`] (feature `npy`): матрица `float32` в `.npy` и полный набор
+//!   (индексы, тексты, векторы, токены) в `.npz`;
+//! - [`arrow`] (feature `arrow`): Arrow IPC и, с feature `parquet`, Parquet
+//!   с текстовой колонкой и колонкой векторов фиксированной длины.
+
+use snafu::prelude::*;
+
+use super::{error, structures::TextEmbedding};
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "npy")]
+pub mod npy;
+
+/// Возвращает общую размерность векторов.
+///
+/// Пустые векторы не выгружаются: из матрицы нулевой ширины нельзя
+/// восстановить количество строк.
+fn dimension(items: &[TextEmbedding]) -> Result<usize, error::Error> {
+    let dimension = items.first().map_or(0, |item| item.embedding.len());
+    ensure!(
+        dimension > 0 || items.is_empty(),
+        error::InvalidExportSnafu {
+            message: "embeddings are empty"
+        }
+    );
+    for item in items {
+        ensure!(
+            item.embedding.len() == dimension,
+            error::DimensionMismatchSnafu {
+                expected: dimension,
+                actual: item.embedding.len(),
+            }
+        );
+    }
+    Ok(dimension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::structures::Usage;
+
+    fn item(embedding: Vec<f32>) -> TextEmbedding {
+        TextEmbedding {
+            index: 0,
+            text: "текст".to_string(),
+            embedding,
+            usage: Usage::default(),
+        }
+    }
+
+    #[test]
+    fn common_dimension() {
+        assert_eq!(dimension(&[]).unwrap(), 0);
+        assert_eq!(
+            dimension(&[item(vec![1.0, 2.0]), item(vec![3.0, 4.0])]).unwrap(),
+            2
+        );
+        assert!(matches!(
+            dimension(&[item(vec![1.0, 2.0]), item(vec![3.0])]),
+            Err(error::Error::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn empty_embeddings_are_rejected() {
+        assert!(matches!(
+            dimension(&[item(Vec::new()), item(Vec::new())]),
+            Err(error::Error::InvalidExport { .. })
+        ));
+    }
+}
@@ -0,0 +1,310 @@
+use std::io::{Read, Seek, Write};
+
+use npyz::{
+    DType, NpyFile, WriteOptions, WriterBuilder,
+    npz::{NpzArchive, NpzWriter},
+    zip::write::FileOptions,
+};
+use snafu::prelude::*;
+
+use super::dimension;
+use crate::embeddings::{
+    error,
+    structures::{TextEmbedding, Usage},
+};
+
+/// Имена массивов в архиве `.npz`.
+const EMBEDDINGS: &str = "embeddings";
+const TEXTS: &str = "texts";
+const INDICES: &str = "indices";
+const PROMPT_TOKENS: &str = "prompt_tokens";
+
+fn invalid(message: impl Into<String>) -> error::Error {
+    error::InvalidExportSnafu {
+        message: message.into(),
+    }
+    .build()
+}
+
+/// Записывает векторы в формате `.npy` как матрицу `float32` размера
+/// `[количество, размерность]`.
+///
+/// Тексты в `.npy` не сохраняются; для полного набора используйте [`write_npz`].
+/// В Python файл читается через `numpy.load(path)`.
+pub fn write_npy<W: Write>(items: &[TextEmbedding], writer: W) -> Result<(), error::Error> {
+    let dimension = dimension(items)?;
+
+    let mut writer = WriteOptions::new()
+        .default_dtype()
+        .shape(&[items.len() as u64, dimension as u64])
+        .writer(writer)
+        .begin_nd()
+        .context(error::ExportIoSnafu)?;
+    writer
+        .extend(items.iter().flat_map(|item| item.embedding.iter().copied()))
+        .context(error::ExportIoSnafu)?;
+    writer.finish().context(error::ExportIoSnafu)
+}
+
+/// Читает матрицу `float32` из файла `.npy`, записанного [`write_npy`]
+/// или `numpy.save`.
+///
+/// Матрицы нулевой ширины с ненулевым количеством строк не принимаются.
+pub fn read_npy<R: Read>(reader: R) -> Result<Vec<Vec<f32>>, error::Error> {
+    let file = NpyFile::new(reader).context(error::ExportIoSnafu)?;
+    read_matrix(file)
+}
+
+fn read_matrix<R: Read>(file: NpyFile<R>) -> Result<Vec<Vec<f32>>, error::Error> {
+    let &[rows, columns] = file.shape() else {
+        return Err(invalid(format!(
+            "expected 2-dimensional array, got shape {:?}",
+            file.shape()
+        )));
+    };
+
+    // Количество строк матрицы нулевой ширины берется только из заголовка,
+    // поэтому такие матрицы не принимаются.
+    ensure!(
+        columns > 0 || rows == 0,
+        error::InvalidExportSnafu {
+            message: format!("matrix of {rows} rows has zero columns"),
+        }
+    );
+    if rows == 0 {
+        return Ok(Vec::new());
+    }
+
+    let values: Vec<f32> = file.into_vec().context(error::ExportIoSnafu)?;
+    Ok(values
+        .chunks_exact(columns as usize)
+        .map(<[f32]>::to_vec)
+        .collect())
+}
+
+/// Записывает векторы вместе с исходными текстами в архив `.npz`.
+///
+/// Архив содержит массивы `embeddings` (`float32`, `[количество, размерность]`),
+/// `texts` (строки Unicode), `indices` (`uint64`) и `prompt_tokens` (`uint32`).
+/// В Python: `data = numpy.load(path); data["embeddings"], data["texts"]`.
+pub fn write_npz<W: Write + Seek>(items: &[TextEmbedding], writer: W) -> Result<(), error::Error> {
+    let dimension = dimension(items)?;
+    let mut npz = NpzWriter::new(writer);
+
+    let mut embeddings = npz
+        .array::<f32>(EMBEDDINGS, FileOptions::default())
+        .context(error::ExportIoSnafu)?
+        .default_dtype()
+        .shape(&[items.len() as u64, dimension as u64])
+        .begin_nd()
+        .context(error::ExportIoSnafu)?;
+    embeddings
+        .extend(items.iter().flat_map(|item| item.embedding.iter().copied()))
+        .context(error::ExportIoSnafu)?;
+    embeddings.finish().context(error::ExportIoSnafu)?;
+
+    // NumPy хранит строки фиксированной длины в кодовых точках.
+    let width = items
+        .iter()
+        .map(|item| item.text.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let text_dtype = DType::Plain(
+        format!("<U{width}")
+            .parse()
+            .expect("unicode type string is always valid"),
+    );
+    let mut texts = npz
+        .array::<str>(TEXTS, FileOptions::default())
+        .context(error::ExportIoSnafu)?
+        .dtype(text_dtype)
+        .shape(&[items.len() as u64])
+        .begin_nd()
+        .context(error::ExportIoSnafu)?;
+    for item in items {
+        texts
+            .push(item.text.as_str())
+            .context(error::ExportIoSnafu)?;
+    }
+    texts.finish().context(error::ExportIoSnafu)?;
+
+    let mut indices = npz
+        .array::<u64>(INDICES, FileOptions::default())
+        .context(error::ExportIoSnafu)?
+        .default_dtype()
+        .shape(&[items.len() as u64])
+        .begin_nd()
+        .context(error::ExportIoSnafu)?;
+    indices
+        .extend(items.iter().map(|item| item.index as u64))
+        .context(error::ExportIoSnafu)?;
+    indices.finish().context(error::ExportIoSnafu)?;
+
+    let mut tokens = npz
+        .array::<u32>(PROMPT_TOKENS, FileOptions::default())
+        .context(error::ExportIoSnafu)?
+        .default_dtype()
+        .shape(&[items.len() as u64])
+        .begin_nd()
+        .context(error::ExportIoSnafu)?;
+    tokens
+        .extend(items.iter().map(|item| item.usage.prompt_tokens))
+        .context(error::ExportIoSnafu)?;
+    tokens.finish().context(error::ExportIoSnafu)?;
+
+    npz.zip_writer()
+        .finish()
+        .map_err(std::io::Error::from)
+        .context(error::ExportIoSnafu)?;
+    Ok(())
+}
+
+/// Читает векторы и тексты из архива `.npz`, записанного [`write_npz`].
+///
+/// Массивы `indices` и `prompt_tokens` необязательны: при их отсутствии
+/// используются порядковые номера и нулевое потребление токенов.
+pub fn read_npz<R: Read + Seek>(reader: R) -> Result<Vec<TextEmbedding>, error::Error> {
+    let mut npz = NpzArchive::new(reader).context(error::ExportIoSnafu)?;
+
+    let embeddings = npz
+        .by_name(EMBEDDINGS)
+        .context(error::ExportIoSnafu)?
+        .ok_or_else(|| invalid(format!("array `{EMBEDDINGS}` is missing")))?;
+    let embeddings = read_matrix(embeddings)?;
+
+    let texts: Vec<String> = npz
+        .by_name(TEXTS)
+        .context(error::ExportIoSnafu)?
+        .ok_or_else(|| invalid(format!("array `{TEXTS}` is missing")))?
+        .into_vec()
+        .context(error::ExportIoSnafu)?;
+
+    let indices: Option<Vec<u64>> = npz
+        .by_name(INDICES)
+        .context(error::ExportIoSnafu)?
+        .map(|file| file.into_vec())
+        .transpose()
+        .context(error::ExportIoSnafu)?;
+    let tokens: Option<Vec<u32>> = npz
+        .by_name(PROMPT_TOKENS)
+        .context(error::ExportIoSnafu)?
+        .map(|file| file.into_vec())
+        .transpose()
+        .context(error::ExportIoSnafu)?;
+
+    let rows = embeddings.len();
+    ensure!(
+        texts.len() == rows
+            && indices.as_ref().is_none_or(|i| i.len() == rows)
+            && tokens.as_ref().is_none_or(|t| t.len() == rows),
+        error::InvalidExportSnafu {
+            message: "arrays have different lengths"
+        }
+    );
+
+    Ok(embeddings
+        .into_iter()
+        .zip(texts)
+        .enumerate()
+        .map(|(row, (embedding, text))| TextEmbedding {
+            index: indices.as_ref().map_or(row, |i| i[row] as usize),
+            text,
+            embedding,
+            usage: Usage {
+                prompt_tokens: tokens.as_ref().map_or(0, |t| t[row]),
+            },
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn items() -> Vec<TextEmbedding> {
+        vec![
+            TextEmbedding {
+                index: 0,
+                text: "первый".into(),
+                embedding: vec![1.0, 2.0],
+                usage: Usage { prompt_tokens: 3 },
+            },
+            TextEmbedding {
+                index: 1,
+                text: "второй текст".into(),
+                embedding: vec![-0.5, 0.25],
+                usage: Usage { prompt_tokens: 4 },
+            },
+        ]
+    }
+
+    #[test]
+    fn npy_round_trip() {
+        let items = items();
+        let mut bytes = Vec::new();
+        write_npy(&items, &mut bytes).unwrap();
+
+        let matrix = read_npy(bytes.as_slice()).unwrap();
+        let expected: Vec<_> = items.into_iter().map(|item| item.embedding).collect();
+        assert_eq!(matrix, expected);
+    }
+
+    #[test]
+    fn npz_round_trip() {
+        let items = items();
+        let mut bytes = Cursor::new(Vec::new());
+        write_npz(&items, &mut bytes).unwrap();
+        bytes.set_position(0);
+
+        assert_eq!(read_npz(bytes).unwrap(), items);
+    }
+
+    #[test]
+    fn dimension_mismatch_is_rejected() {
+        let mut items = items();
+        items[1].embedding.push(0.0);
+
+        assert!(write_npy(&items, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn empty_embeddings_are_not_written() {
+        let mut items = items();
+        for item in &mut items {
+            item.embedding.clear();
+        }
+
+        assert!(matches!(
+            write_npy(&items, Vec::new()),
+            Err(error::Error::InvalidExport { .. })
+        ));
+        assert!(matches!(
+            write_npz(&items, Cursor::new(Vec::new())),
+            Err(error::Error::InvalidExport { .. })
+        ));
+    }
+
+    #[test]
+    fn zero_width_matrix_is_rejected() {
+        let matrix = |rows: u64| {
+            let mut bytes = Vec::new();
+            let writer = WriteOptions::<f32>::new()
+                .default_dtype()
+                .shape(&[rows, 0])
+                .writer(&mut bytes)
+                .begin_nd()
+                .unwrap();
+            writer.finish().unwrap();
+            bytes
+        };
+
+        assert!(matches!(
+            read_npy(matrix(u64::MAX).as_slice()),
+            Err(error::Error::InvalidExport { .. })
+        ));
+        assert!(read_npy(matrix(0).as_slice()).unwrap().is_empty());
+    }
+}
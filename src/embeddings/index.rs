@@ -0,0 +1,562 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use snafu::prelude::*;
+
+use super::{error, structures::TextEmbeddings};
+
+/// Сигнатура файла индекса.
+const MAGIC: &[u8; 4] = b"GCVI";
+/// Версия формата файла индекса.
+const FORMAT_VERSION: u32 = 1;
+
+/// Нормализует вектор до единичной длины.
+///
+/// Нулевой вектор остается без изменений.
+pub fn normalize(vector: &mut [f32]) {
+    let norm = dot(vector, vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// Скалярное произведение векторов.
+///
+/// Векторы разной длины сравниваются по общему префиксу.
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Косинусное сходство векторов.
+///
+/// Для нулевого вектора возвращает `0.0`.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let norm = (dot(a, a) * dot(b, b)).sqrt();
+    if norm > 0.0 { dot(a, b) / norm } else { 0.0 }
+}
+
+/// Мера сходства векторов.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Metric {
+    /// Косинусное сходство; векторы нормализуются при добавлении.
+    #[default]
+    Cosine,
+    /// Скалярное произведение.
+    Dot,
+}
+
+impl Metric {
+    fn to_byte(self) -> u8 {
+        match self {
+            Metric::Cosine => 0,
+            Metric::Dot => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Metric::Cosine),
+            1 => Some(Metric::Dot),
+            _ => None,
+        }
+    }
+}
+
+/// Результат поиска.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchHit<'a, M> {
+    /// Идентификатор записи (порядковый номер добавления).
+    pub id: usize,
+    /// Сходство с запросом.
+    pub score: f32,
+    /// Данные, связанные с записью.
+    pub payload: &'a M,
+}
+
+/// Индекс векторов в памяти с полным перебором.
+///
+/// Подходит для небольших наборов (до сотен тысяч векторов), когда внешняя
+/// векторная база данных избыточна. Индекс можно сохранить в компактный
+/// бинарный файл: векторы хранятся как `f32`, данные записей — как JSON.
+///
+/// ## Пример
+///
+/// ```rust
+/// use gigachat_rust::embeddings::index::{Metric, VectorIndex};
+///
+/// let mut index = VectorIndex::new(3, Metric::Cosine);
+/// index.insert(vec![1.0, 0.0, 0.0], "первый".to_string()).unwrap();
+/// index.insert(vec![0.0, 1.0, 0.0], "второй".to_string()).unwrap();
+///
+/// let hits = index.search(&[0.9, 0.1, 0.0], 1).unwrap();
+/// assert_eq!(hits[0].payload, "первый");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorIndex<M = serde_json::Value> {
+    dimension: usize,
+    metric: Metric,
+    vectors: Vec<f32>,
+    payloads: Vec<M>,
+}
+
+impl<M> VectorIndex<M> {
+    /// Создает пустой индекс для векторов указанной размерности.
+    pub fn new(dimension: usize, metric: Metric) -> Self {
+        Self {
+            dimension,
+            metric,
+            vectors: Vec::new(),
+            payloads: Vec::new(),
+        }
+    }
+
+    /// Размерность векторов.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Мера сходства.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Количество записей.
+    pub fn len(&self) -> usize {
+        self.payloads.len()
+    }
+
+    /// Проверяет, пуст ли индекс.
+    pub fn is_empty(&self) -> bool {
+        self.payloads.is_empty()
+    }
+
+    /// Возвращает вектор и данные записи по идентификатору.
+    pub fn get(&self, id: usize) -> Option<(&[f32], &M)> {
+        let payload = self.payloads.get(id)?;
+        let start = id * self.dimension;
+        Some((&self.vectors[start..start + self.dimension], payload))
+    }
+
+    fn ensure_dimension(&self, actual: usize) -> Result<(), error::Error> {
+        ensure!(
+            actual == self.dimension,
+            error::DimensionMismatchSnafu {
+                expected: self.dimension,
+                actual,
+            }
+        );
+        Ok(())
+    }
+
+    /// Добавляет вектор с данными и возвращает идентификатор записи.
+    pub fn insert(&mut self, mut embedding: Vec<f32>, payload: M) -> Result<usize, error::Error> {
+        self.ensure_dimension(embedding.len())?;
+        if self.metric == Metric::Cosine {
+            normalize(&mut embedding);
+        }
+
+        self.vectors.extend_from_slice(&embedding);
+        self.payloads.push(payload);
+        Ok(self.payloads.len() - 1)
+    }
+
+    /// Находит `k` записей, наиболее близких к запросу, в порядке убывания сходства.
+    pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchHit<'_, M>>, error::Error> {
+        self.ensure_dimension(query.len())?;
+
+        let mut query = query.to_vec();
+        if self.metric == Metric::Cosine {
+            normalize(&mut query);
+        }
+
+        let mut scores: Vec<(usize, f32)> = self
+            .vectors
+            .chunks_exact(self.dimension.max(1))
+            .map(|vector| dot(vector, &query))
+            .enumerate()
+            .collect();
+
+        let by_score = |a: &(usize, f32), b: &(usize, f32)| b.1.total_cmp(&a.1);
+        let k = k.min(scores.len());
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        if k < scores.len() {
+            scores.select_nth_unstable_by(k - 1, by_score);
+            scores.truncate(k);
+        }
+        scores.sort_by(|a, b| by_score(a, b).then(a.0.cmp(&b.0)));
+
+        Ok(scores
+            .into_iter()
+            .map(|(id, score)| SearchHit {
+                id,
+                score,
+                payload: &self.payloads[id],
+            })
+            .collect())
+    }
+}
+
+impl VectorIndex<String> {
+    /// Создает индекс из результатов векторизации; данными записей служат исходные тексты.
+    pub fn from_embeddings(
+        embeddings: TextEmbeddings,
+        metric: Metric,
+    ) -> Result<Self, error::Error> {
+        let dimension = embeddings
+            .items
+            .first()
+            .map_or(0, |item| item.embedding.len());
+
+        let mut index = Self::new(dimension, metric);
+        for item in embeddings.items {
+            index.insert(item.embedding, item.text)?;
+        }
+        Ok(index)
+    }
+}
+
+fn format_error(message: &str) -> error::Error {
+    error::IndexFormatSnafu { message }.build()
+}
+
+fn read_array<const N: usize, R: Read>(reader: &mut R) -> Result<[u8; N], error::Error> {
+    let mut buffer = [0; N];
+    reader
+        .read_exact(&mut buffer)
+        .context(error::IndexIoSnafu)?;
+    Ok(buffer)
+}
+
+impl<M: Serialize> VectorIndex<M> {
+    /// Записывает индекс в бинарном формате.
+    ///
+    /// Формат: сигнатура `GCVI`, версия (`u32`), мера (`u8`), размерность
+    /// (`u32`), количество записей (`u64`), векторы (`f32`), затем данные
+    /// записей в JSON с префиксом длины (`u32`). Все числа little-endian.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), error::Error> {
+        let dimension = u32::try_from(self.dimension)
+            .map_err(|_| format_error("dimension does not fit into u32"))?;
+        let mut writer = BufWriter::new(writer);
+        let mut write = |bytes: &[u8]| writer.write_all(bytes).context(error::IndexIoSnafu);
+
+        write(MAGIC)?;
+        write(&FORMAT_VERSION.to_le_bytes())?;
+        write(&[self.metric.to_byte()])?;
+        write(&dimension.to_le_bytes())?;
+        write(&(self.len() as u64).to_le_bytes())?;
+        for value in &self.vectors {
+            write(&value.to_le_bytes())?;
+        }
+        for payload in &self.payloads {
+            let bytes = serde_json::to_vec(payload).context(error::IndexPayloadSnafu)?;
+            let length = u32::try_from(bytes.len())
+                .map_err(|_| format_error("payload does not fit into u32"))?;
+            write(&length.to_le_bytes())?;
+            write(&bytes)?;
+        }
+
+        writer.flush().context(error::IndexIoSnafu)
+    }
+
+    /// Сохраняет индекс в файл.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), error::Error> {
+        let file = File::create(path).context(error::IndexIoSnafu)?;
+        self.write_to(file)
+    }
+}
+
+impl<M: DeserializeOwned> VectorIndex<M> {
+    /// Читает индекс, записанный [`VectorIndex::write_to`].
+    ///
+    /// Размеры из заголовка не используются для предварительного выделения
+    /// памяти, поэтому поврежденный файл приводит к ошибке, а не к аварии.
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, error::Error> {
+        let mut reader = BufReader::new(reader);
+
+        ensure!(
+            &read_array::<4, _>(&mut reader)? == MAGIC,
+            error::IndexFormatSnafu {
+                message: "bad signature"
+            }
+        );
+        let version = u32::from_le_bytes(read_array(&mut reader)?);
+        ensure!(
+            version == FORMAT_VERSION,
+            error::IndexFormatSnafu {
+                message: format!("unsupported version {version}")
+            }
+        );
+        let [metric] = read_array::<1, _>(&mut reader)?;
+        let metric = Metric::from_byte(metric).ok_or_else(|| format_error("unknown metric"))?;
+        let dimension = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+        let count = u64::from_le_bytes(read_array(&mut reader)?) as usize;
+
+        let values = count
+            .checked_mul(dimension)
+            .ok_or_else(|| format_error("index is too large"))?;
+        let mut vectors = Vec::new();
+        for _ in 0..values {
+            vectors.push(f32::from_le_bytes(read_array(&mut reader)?));
+        }
+
+        let mut payloads = Vec::new();
+        let mut buffer = Vec::new();
+        for _ in 0..count {
+            let length = u32::from_le_bytes(read_array(&mut reader)?);
+            buffer.clear();
+            (&mut reader)
+                .take(u64::from(length))
+                .read_to_end(&mut buffer)
+                .context(error::IndexIoSnafu)?;
+            ensure!(
+                buffer.len() == length as usize,
+                error::IndexFormatSnafu {
+                    message: "truncated payload"
+                }
+            );
+            payloads.push(serde_json::from_slice(&buffer).context(error::IndexPayloadSnafu)?);
+        }
+
+        Ok(Self {
+            dimension,
+            metric,
+            vectors,
+            payloads,
+        })
+    }
+
+    /// Загружает индекс из файла.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, error::Error> {
+        let file = File::open(path).context(error::IndexIoSnafu)?;
+        Self::read_from(file)
+    }
+}
+
+impl<M> Default for VectorIndex<M> {
+    fn default() -> Self {
+        Self::new(0, Metric::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::{
+        Model,
+        structures::{TextEmbedding, Usage},
+    };
+
+    fn sample() -> VectorIndex<String> {
+        let mut index = VectorIndex::new(3, Metric::Dot);
+        index.insert(vec![1.0, 2.0, 3.0], "первый".into()).unwrap();
+        index.insert(vec![-1.0, 0.5, 0.0], "второй".into()).unwrap();
+        index
+    }
+
+    fn hits(index: &VectorIndex<String>, query: &[f32], k: usize) -> Vec<(usize, f32)> {
+        index
+            .search(query, k)
+            .unwrap()
+            .into_iter()
+            .map(|hit| (hit.id, hit.score))
+            .collect()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn vector_functions() {
+        assert_eq!(dot(&[1.0, 2.0, 3.0], &[4.0, -5.0, 6.0]), 12.0);
+        assert_eq!(dot(&[1.0, 2.0], &[3.0]), 3.0);
+
+        assert_close(
+            cosine(&[1.0, 0.0], &[1.0, 1.0]),
+            std::f32::consts::FRAC_1_SQRT_2,
+        );
+        assert_close(cosine(&[2.0, 0.0], &[-3.0, 0.0]), -1.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+
+        let mut vector = [3.0, 4.0];
+        normalize(&mut vector);
+        assert_eq!(vector, [0.6, 0.8]);
+        let mut zero = [0.0, 0.0];
+        normalize(&mut zero);
+        assert_eq!(zero, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn search_orders_by_score() {
+        let mut index = sample();
+        index.insert(vec![0.0, 0.0, 1.0], "третий".into()).unwrap();
+        index
+            .insert(vec![1.0, 0.0, 0.0], "четвертый".into())
+            .unwrap();
+
+        assert_eq!(
+            hits(&index, &[1.0, 0.0, 0.0], 4),
+            [(0, 1.0), (3, 1.0), (2, 0.0), (1, -1.0)]
+        );
+        assert_eq!(hits(&index, &[0.0, 1.0, 1.0], 2), [(0, 5.0), (2, 1.0)]);
+
+        let top = index.search(&[0.0, 0.0, 2.0], 1).unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!((top[0].id, top[0].score), (0, 6.0));
+        assert_eq!(top[0].payload, "первый");
+    }
+
+    #[test]
+    fn search_k_bounds() {
+        let index = sample();
+
+        assert!(hits(&index, &[1.0, 0.0, 0.0], 0).is_empty());
+        assert_eq!(hits(&index, &[1.0, 0.0, 0.0], 10), [(0, 1.0), (1, -1.0)]);
+        assert!(hits(&VectorIndex::new(3, Metric::Dot), &[1.0, 0.0, 0.0], 5).is_empty());
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let mut index = sample();
+
+        assert!(matches!(
+            index.search(&[1.0, 0.0], 1),
+            Err(error::Error::DimensionMismatch {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            index.insert(vec![1.0; 4], "лишний".into()),
+            Err(error::Error::DimensionMismatch {
+                expected: 3,
+                actual: 4
+            })
+        ));
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn cosine_normalizes_vectors_and_query() {
+        let mut index = VectorIndex::new(2, Metric::Cosine);
+        index
+            .insert(vec![3.0, 4.0], "наклонный".to_string())
+            .unwrap();
+        index
+            .insert(vec![0.0, 10.0], "вертикальный".to_string())
+            .unwrap();
+
+        assert_eq!(index.get(0).unwrap().0, [0.6, 0.8]);
+        assert_eq!(index.get(1).unwrap().0, [0.0, 1.0]);
+
+        let found = hits(&index, &[30.0, 40.0], 2);
+        assert_eq!(found.iter().map(|hit| hit.0).collect::<Vec<_>>(), [0, 1]);
+        assert_close(found[0].1, 1.0);
+        assert_close(found[1].1, 0.8);
+    }
+
+    #[test]
+    fn from_embeddings() {
+        let item = |index: usize, text: &str, embedding: Vec<f32>| TextEmbedding {
+            index,
+            text: text.to_string(),
+            embedding,
+            usage: Usage::default(),
+        };
+        let embeddings = |items| TextEmbeddings {
+            model: Model::EmbeddingsGigaR,
+            items,
+        };
+
+        let index = VectorIndex::from_embeddings(
+            embeddings(vec![
+                item(0, "первый", vec![1.0, 0.0]),
+                item(1, "второй", vec![0.0, 2.0]),
+            ]),
+            Metric::Dot,
+        )
+        .unwrap();
+        assert_eq!((index.len(), index.dimension()), (2, 2));
+        assert_eq!(index.get(1), Some((&[0.0, 2.0][..], &"второй".to_string())));
+
+        let mismatch = VectorIndex::from_embeddings(
+            embeddings(vec![
+                item(0, "первый", vec![1.0, 0.0]),
+                item(1, "второй", vec![1.0]),
+            ]),
+            Metric::Dot,
+        );
+        assert!(matches!(
+            mismatch,
+            Err(error::Error::DimensionMismatch { .. })
+        ));
+    }
+
+    fn header(dimension: u32, count: u64) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.push(Metric::Dot.to_byte());
+        bytes.extend_from_slice(&dimension.to_le_bytes());
+        bytes.extend_from_slice(&count.to_le_bytes());
+        bytes
+    }
+
+    fn read(bytes: &[u8]) -> Result<VectorIndex<String>, error::Error> {
+        VectorIndex::read_from(bytes)
+    }
+
+    #[test]
+    fn round_trip() {
+        let index = sample();
+        let mut bytes = Vec::new();
+        index.write_to(&mut bytes).unwrap();
+
+        assert_eq!(read(&bytes).unwrap(), index);
+    }
+
+    #[test]
+    fn truncated_file() {
+        let mut bytes = Vec::new();
+        sample().write_to(&mut bytes).unwrap();
+
+        for length in [0, 3, 12, bytes.len() - 1] {
+            assert!(read(&bytes[..length]).is_err(), "length {length}");
+        }
+    }
+
+    #[test]
+    fn bad_signature() {
+        let mut bytes = header(3, 0);
+        bytes[0] = b'X';
+
+        assert!(matches!(
+            read(&bytes),
+            Err(error::Error::IndexFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn huge_count_in_header() {
+        let bytes = header(u32::MAX, u64::MAX);
+
+        assert!(read(&bytes).is_err());
+    }
+
+    #[test]
+    fn huge_payload_length() {
+        let mut bytes = header(0, 1);
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(b"\"x\"");
+
+        assert!(matches!(
+            read(&bytes),
+            Err(error::Error::IndexFormat { .. })
+        ));
+    }
+}
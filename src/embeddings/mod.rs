@@ -1,9 +1,17 @@
 use crate::client::GigaChatClient;
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
 
+pub mod builder;
+mod chunking;
 pub mod error;
+#[cfg(any(feature = "npy", feature = "arrow"))]
+pub mod export;
+pub mod index;
+pub mod store;
 pub mod structures;
+pub use chunking::ChunkingConfig;
 use structures::{EmbeddingRequest, EmbeddingResponse, Input};
 
 /// Модель для создания векторных представлений.
@@ -33,12 +41,19 @@ impl Model {
 /// Клиент для создания векторных представлений.
 pub struct Embeddings {
     client: GigaChatClient,
+    headers: HeaderMap,
 }
 
 impl Embeddings {
     /// Создает новый клиент для создания векторных представлений.
+    ///
+    /// Для большинства случаев удобнее сборщик
+    /// [`GigaChatClient::embeddings`](builder::EmbeddingsBuilder).
     pub fn new(client: GigaChatClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            headers: HeaderMap::new(),
+        }
     }
 
     /// Создает векторные представления для входного текста(ов).
@@ -80,21 +95,44 @@ impl Embeddings {
         input: I,
         model: Option<Model>,
     ) -> Result<EmbeddingResponse, error::Error> {
-        let model = model.unwrap_or_default();
-
         let request = EmbeddingRequest {
-            model,
+            model: model.unwrap_or_default(),
             input: input.into(),
         };
 
+        self.execute(&request).await
+    }
+
+    /// Выполняет запрос векторизации, используя кэш клиента при его наличии.
+    pub(crate) async fn execute(
+        &self,
+        request: &EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, error::Error> {
         let url = self
             .client
             .build_url("embeddings", None)
             .context(error::BuildUrlSnafu)?;
 
-        self.client
-            .perform_request(|c| c.post(url).json(&request), async |r| r.json().await)
+        let cache = self.client.cache();
+        let cache_key = cache.and_then(|cache| cache.key("embeddings", request));
+        if let (Some(cache), Some(key)) = (cache, &cache_key)
+            && let Some(response) = cache.get(key).await
+        {
+            return Ok(response);
+        }
+
+        let response = self
+            .client
+            .perform_request(
+                |c| c.post(url).headers(self.headers.clone()).json(request),
+                async |r| r.json().await,
+            )
             .await
-            .context(error::RequestFailedSnafu)
+            .context(error::RequestFailedSnafu)?;
+
+        if let (Some(cache), Some(key)) = (cache, &cache_key) {
+            cache.put(key, &response).await;
+        }
+        Ok(response)
     }
 }
@@ -0,0 +1,515 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use sha2::{Digest, Sha256};
+use snafu::prelude::*;
+use tracing::Span;
+use uuid::Uuid;
+
+use super::{
+    ChunkingConfig, Model, error,
+    structures::{TextEmbedding, TextEmbeddings, Usage},
+};
+use crate::{cache, client::GigaChatClient};
+
+/// Ключ векторного представления: модель и SHA-256 текста.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmbeddingKey {
+    model: String,
+    hash: [u8; 32],
+}
+
+impl EmbeddingKey {
+    /// Строит ключ для текста, векторизованного указанной моделью.
+    pub fn new(model: &Model, text: &str) -> Self {
+        Self {
+            model: model.as_str().to_string(),
+            hash: Sha256::digest(text.as_bytes()).into(),
+        }
+    }
+
+    /// Название модели.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// SHA-256 текста.
+    pub fn hash(&self) -> &[u8; 32] {
+        &self.hash
+    }
+
+    /// SHA-256 текста в шестнадцатеричном виде.
+    pub fn hash_hex(&self) -> String {
+        self.hash.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+/// Хранилище векторных представлений с адресацией по содержимому.
+#[async_trait::async_trait]
+pub trait EmbeddingStore: Send + Sync {
+    /// Возвращает сохраненные векторы в порядке ключей.
+    async fn get_many(
+        &self,
+        keys: &[EmbeddingKey],
+    ) -> Result<Vec<Option<Vec<f32>>>, cache::error::Error>;
+
+    /// Сохраняет векторы.
+    async fn put_many(
+        &self,
+        entries: &[(EmbeddingKey, Vec<f32>)],
+    ) -> Result<(), cache::error::Error>;
+}
+
+/// Хранилище векторов на диске.
+///
+/// Каждый вектор хранится в отдельном файле
+/// `<директория>/<модель>/<2 символа хэша>/<хэш>.f32` в виде
+/// последовательности `f32` little-endian. Разбиение по первым символам
+/// хэша ограничивает количество файлов в одной директории. Символы имени
+/// модели, недопустимые в имени директории, кодируются как `%XX`, поэтому
+/// разные модели всегда хранятся в разных директориях.
+#[derive(Debug, Clone)]
+pub struct FileEmbeddingStore {
+    directory: PathBuf,
+}
+
+impl FileEmbeddingStore {
+    /// Создает хранилище в указанной директории.
+    ///
+    /// Директории создаются при первой записи.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Директория хранилища.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn entry_path(&self, key: &EmbeddingKey) -> PathBuf {
+        let model = encode_model(key.model());
+        let hash = key.hash_hex();
+
+        self.directory
+            .join(model)
+            .join(&hash[..2])
+            .join(format!("{hash}.f32"))
+    }
+}
+
+/// Кодирует имя модели в имя директории.
+///
+/// ASCII-буквы, цифры, `-`, `_` и `.` (кроме первого символа) сохраняются,
+/// остальные байты UTF-8 записываются как `%XX`. Кодирование обратимо,
+/// поэтому имена разных моделей не совпадают.
+fn encode_model(model: &str) -> String {
+    let mut encoded = String::with_capacity(model.len());
+    for (i, byte) in model.bytes().enumerate() {
+        let keep =
+            byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' || (byte == b'.' && i > 0);
+        if keep {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+#[async_trait::async_trait]
+impl EmbeddingStore for FileEmbeddingStore {
+    async fn get_many(
+        &self,
+        keys: &[EmbeddingKey],
+    ) -> Result<Vec<Option<Vec<f32>>>, cache::error::Error> {
+        let mut vectors = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let path = self.entry_path(key);
+            let bytes = match tokio::fs::read(&path).await {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    vectors.push(None);
+                    continue;
+                }
+                Err(e) => return Err(e).context(cache::error::ReadEntrySnafu { path }),
+            };
+
+            ensure!(
+                bytes.len() % 4 == 0,
+                cache::error::CorruptedEntrySnafu { path }
+            );
+            vectors.push(Some(
+                bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect(),
+            ));
+        }
+
+        Ok(vectors)
+    }
+
+    async fn put_many(
+        &self,
+        entries: &[(EmbeddingKey, Vec<f32>)],
+    ) -> Result<(), cache::error::Error> {
+        for (key, vector) in entries {
+            let path = self.entry_path(key);
+            let parent = path.parent().expect("entry path has a parent directory");
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context(cache::error::WriteEntrySnafu {
+                    path: parent.to_path_buf(),
+                })?;
+
+            let bytes: Vec<u8> = vector.iter().flat_map(|x| x.to_le_bytes()).collect();
+
+            // Запись через временный файл, чтобы читатели не увидели частичную запись.
+            // Имя файла уникально, чтобы параллельные записи одного ключа
+            // не мешали друг другу.
+            let temporary = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+            tokio::fs::write(&temporary, bytes)
+                .await
+                .context(cache::error::WriteEntrySnafu {
+                    path: temporary.clone(),
+                })?;
+            tokio::fs::rename(&temporary, &path)
+                .await
+                .context(cache::error::WriteEntrySnafu { path })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Векторизация с сохранением результатов в [`EmbeddingStore`].
+///
+/// Перед обращением к API векторы ищутся в хранилище по ключу
+/// (модель, хэш текста), и в API отправляются только отсутствующие строки.
+/// Повторяющиеся строки векторизуются один раз. Количество попаданий и
+/// промахов записывается в поля `embeddings.hits` и `embeddings.misses`
+/// span'а `embed`.
+///
+/// Ошибки хранилища не прерывают векторизацию: они записываются в лог,
+/// а строки векторизуются через API.
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::client::GigaChatClientBuilder;
+/// use gigachat_rust::embeddings::store::{CachedEmbeddings, FileEmbeddingStore};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let embeddings = CachedEmbeddings::new(client, FileEmbeddingStore::new("embeddings"));
+///     let result = embeddings
+///         .embed(vec!["Первый абзац".to_string(), "Второй абзац".to_string()])
+///         .await
+///         .unwrap();
+///
+///     println!("{} tokens spent", result.usage().prompt_tokens);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CachedEmbeddings {
+    client: GigaChatClient,
+    store: Arc<dyn EmbeddingStore>,
+    model: Model,
+    chunking: ChunkingConfig,
+}
+
+impl CachedEmbeddings {
+    /// Создает обертку над хранилищем.
+    pub fn new<S: EmbeddingStore + 'static>(client: GigaChatClient, store: S) -> Self {
+        Self {
+            client,
+            store: Arc::new(store),
+            model: Model::default(),
+            chunking: ChunkingConfig::default(),
+        }
+    }
+
+    /// Устанавливает модель для векторизации.
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Устанавливает параметры разбиения отсутствующих строк на запросы.
+    pub fn with_chunking(mut self, chunking: ChunkingConfig) -> Self {
+        self.chunking = chunking;
+        self
+    }
+
+    /// Создает векторные представления строк.
+    ///
+    /// Для строк, найденных в хранилище, [`TextEmbedding::usage`] равен нулю.
+    #[tracing::instrument(
+        name = "embed",
+        skip_all,
+        fields(embeddings.inputs = inputs.len(), embeddings.hits, embeddings.misses),
+        err
+    )]
+    pub async fn embed(&self, inputs: Vec<String>) -> Result<TextEmbeddings, error::Error> {
+        ensure!(!inputs.is_empty(), error::InputIsMissingSnafu);
+
+        let keys: Vec<EmbeddingKey> = inputs
+            .iter()
+            .map(|text| EmbeddingKey::new(&self.model, text))
+            .collect();
+
+        let mut found = match self.store.get_many(&keys).await {
+            Ok(found) if found.len() == keys.len() => found,
+            Ok(found) => {
+                tracing::warn!(
+                    expected = keys.len(),
+                    actual = found.len(),
+                    "embedding store returned wrong number of entries"
+                );
+                vec![None; keys.len()]
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "embedding store lookup failed");
+                vec![None; keys.len()]
+            }
+        };
+
+        // Отсутствующие строки без повторов, в порядке первого появления.
+        let mut misses: Vec<usize> = Vec::new();
+        let mut miss_positions: HashMap<&EmbeddingKey, usize> = HashMap::new();
+        for (index, vector) in found.iter().enumerate() {
+            if vector.is_none() {
+                miss_positions.entry(&keys[index]).or_insert_with(|| {
+                    misses.push(index);
+                    misses.len() - 1
+                });
+            }
+        }
+
+        let hits = found.iter().filter(|vector| vector.is_some()).count();
+        Span::current().record("embeddings.hits", hits);
+        Span::current().record("embeddings.misses", inputs.len() - hits);
+        tracing::info!(
+            hits,
+            misses = inputs.len() - hits,
+            requested = misses.len(),
+            "embedding store lookup finished"
+        );
+
+        let mut usages = vec![Usage::default(); inputs.len()];
+        if !misses.is_empty() {
+            let embedded = self
+                .client
+                .embeddings()
+                .with_model(self.model.clone())
+                .with_inputs(misses.iter().map(|&index| inputs[index].clone()))
+                .with_chunking(self.chunking.clone())
+                .execute()
+                .await?;
+
+            let entries: Vec<(EmbeddingKey, Vec<f32>)> = misses
+                .iter()
+                .zip(&embedded.items)
+                .map(|(&index, item)| (keys[index].clone(), item.embedding.clone()))
+                .collect();
+            if let Err(e) = self.store.put_many(&entries).await {
+                tracing::warn!(error = %e, "embedding store write failed");
+            }
+
+            for (index, slot) in found.iter_mut().enumerate() {
+                if slot.is_none() {
+                    let position = miss_positions[&keys[index]];
+                    let item = &embedded.items[position];
+                    *slot = Some(item.embedding.clone());
+                    if misses[position] == index {
+                        usages[index] = item.usage;
+                    }
+                }
+            }
+        }
+
+        let items = inputs
+            .into_iter()
+            .zip(found)
+            .zip(usages)
+            .enumerate()
+            .map(|(index, ((text, vector), usage))| TextEmbedding {
+                index,
+                text,
+                embedding: vector.expect("every input is either found or embedded"),
+                usage,
+            })
+            .collect();
+
+        Ok(TextEmbeddings {
+            model: self.model.clone(),
+            items,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{self, MockServer};
+
+    fn store() -> FileEmbeddingStore {
+        FileEmbeddingStore::new(std::env::temp_dir().join(format!("gigachat-{}", Uuid::new_v4())))
+    }
+
+    #[test]
+    fn model_names_do_not_collide() {
+        let models = [
+            "GigaChat/1",
+            "GigaChat:1",
+            "GigaChat_1",
+            "GigaChat%2F1",
+            "Модель",
+            "Мод",
+        ];
+        let encoded: std::collections::HashSet<_> =
+            models.iter().map(|m| encode_model(m)).collect();
+
+        assert_eq!(encoded.len(), models.len());
+    }
+
+    #[test]
+    fn model_name_is_a_single_path_component() {
+        assert_eq!(encode_model("Embeddings"), "Embeddings");
+        assert_eq!(encode_model("EmbeddingsGigaR-2.0"), "EmbeddingsGigaR-2.0");
+        assert_eq!(encode_model(".."), "%2E.");
+        assert_eq!(encode_model("a/b\\c"), "a%2Fb%5Cc");
+    }
+
+    #[tokio::test]
+    async fn file_store_round_trip() {
+        let store = store();
+        let first = EmbeddingKey::new(&Model::EmbeddingsGigaR, "первый");
+        let second = EmbeddingKey::new(&Model::Custom("Custom/1".into()), "первый");
+        let missing = EmbeddingKey::new(&Model::EmbeddingsGigaR, "второй");
+
+        let entries = vec![
+            (first.clone(), vec![1.0, -2.5, f32::MIN_POSITIVE]),
+            (second.clone(), Vec::new()),
+        ];
+        store.put_many(&entries).await.unwrap();
+        store.put_many(&entries[..1]).await.unwrap();
+
+        let found = store.get_many(&[first, missing, second]).await.unwrap();
+        assert_eq!(
+            found,
+            [
+                Some(vec![1.0, -2.5, f32::MIN_POSITIVE]),
+                None,
+                Some(Vec::new())
+            ]
+        );
+
+        let mut files = Vec::new();
+        let mut directories = vec![store.directory().to_path_buf()];
+        while let Some(directory) = directories.pop() {
+            for entry in std::fs::read_dir(directory).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    directories.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+        assert_eq!(files.len(), 2, "temporary files left behind: {files:?}");
+
+        std::fs::remove_dir_all(store.directory()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn corrupted_entry_is_rejected() {
+        let store = store();
+        let key = EmbeddingKey::new(&Model::EmbeddingsGigaR, "текст");
+        store.put_many(&[(key.clone(), vec![1.0])]).await.unwrap();
+
+        std::fs::write(store.entry_path(&key), [0, 0, 0]).unwrap();
+        assert!(matches!(
+            store.get_many(&[key]).await,
+            Err(cache::error::Error::CorruptedEntry { .. })
+        ));
+
+        std::fs::remove_dir_all(store.directory()).unwrap();
+    }
+
+    /// Вектор строки — количество ее символов.
+    fn vector(text: &str) -> Vec<f32> {
+        vec![text.chars().count() as f32]
+    }
+
+    #[tokio::test]
+    async fn cached_embeddings_request_only_misses() {
+        let server = MockServer::start(|request| {
+            let vectors: Vec<_> = mock::embedding_inputs(request)
+                .iter()
+                .map(|text| vector(text))
+                .collect();
+            (200, mock::embeddings(&vectors))
+        })
+        .await;
+        let store = store();
+        let directory = store.directory().to_path_buf();
+        let cached = EmbeddingKey::new(&Model::EmbeddingsGigaR, "кэш");
+        store.put_many(&[(cached, vector("кэш"))]).await.unwrap();
+        let embeddings = CachedEmbeddings::new(server.client().await, store);
+
+        let inputs: Vec<String> = ["кэш", "один", "один", "три слова"]
+            .iter()
+            .map(|text| text.to_string())
+            .collect();
+        let result = embeddings.embed(inputs.clone()).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(mock::embedding_inputs(&requests[0]), ["один", "три слова"]);
+        let items: Vec<_> = result
+            .items
+            .iter()
+            .map(|item| {
+                (
+                    item.text.as_str(),
+                    item.embedding[0],
+                    item.usage.prompt_tokens,
+                )
+            })
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("кэш", 3.0, 0),
+                ("один", 4.0, 1),
+                ("один", 4.0, 0),
+                ("три слова", 9.0, 1)
+            ]
+        );
+
+        let repeated = embeddings.embed(inputs).await.unwrap();
+        assert_eq!(server.requests().len(), 1, "misses were not written back");
+        assert_eq!(repeated.usage().prompt_tokens, 0);
+        let vectors: Vec<_> = repeated
+            .items
+            .iter()
+            .map(|item| item.embedding[0])
+            .collect();
+        assert_eq!(vectors, [3.0, 4.0, 4.0, 9.0]);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -16,7 +16,7 @@ pub struct EmbeddingRequest {
     pub input: Input,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Usage {
     /// Количество токенов в строке, для которой сгенерирован эмбеддинг.
     pub prompt_tokens: u32,
@@ -34,3 +34,43 @@ pub struct EmbeddingResponse {
     pub model: Model,
     pub data: Vec<EmbeddingResponseItem>,
 }
+
+impl EmbeddingResponse {
+    /// Суммарное потребление токенов по всем элементам ответа.
+    pub fn usage(&self) -> Usage {
+        Usage {
+            prompt_tokens: self.data.iter().map(|item| item.usage.prompt_tokens).sum(),
+        }
+    }
+}
+
+/// Векторное представление строки вместе с исходным текстом.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextEmbedding {
+    /// Индекс строки во входных данных.
+    pub index: usize,
+    /// Исходный текст.
+    pub text: String,
+    /// Векторное представление.
+    pub embedding: Vec<f32>,
+    /// Потребление токенов.
+    pub usage: Usage,
+}
+
+/// Результат векторизации, упорядоченный по индексу входной строки.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextEmbeddings {
+    /// Модель, использованная для векторизации.
+    pub model: Model,
+    /// Векторные представления в порядке входных строк.
+    pub items: Vec<TextEmbedding>,
+}
+
+impl TextEmbeddings {
+    /// Суммарное потребление токенов.
+    pub fn usage(&self) -> Usage {
+        Usage {
+            prompt_tokens: self.items.iter().map(|item| item.usage.prompt_tokens).sum(),
+        }
+    }
+}
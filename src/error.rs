@@ -24,11 +24,28 @@ pub enum Error {
     #[snafu(display("check error"))]
     Check { source: crate::check::error::Error },
 
+    #[snafu(display("conversation error"))]
+    Conversation {
+        source: crate::conversation::error::Error,
+    },
+
     #[snafu(display("embeddings error"))]
     Embeddings {
         source: crate::embeddings::error::Error,
     },
 
+    #[snafu(display("rag error"))]
+    Rag { source: crate::rag::error::Error },
+
+    #[snafu(display("text splitting error"))]
+    Splitter {
+        source: crate::splitter::error::Error,
+    },
+
+    #[cfg(feature = "mcp")]
+    #[snafu(display("mcp error"))]
+    Mcp { source: crate::mcp::error::Error },
+
     #[snafu(whatever, display("{message}"))]
     Custom {
         message: String,
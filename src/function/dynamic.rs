@@ -0,0 +1,181 @@
+use serde_json::{Value, json};
+use snafu::OptionExt;
+
+use super::{
+    error,
+    structures::{FunctionCheckDiagnostics, FunctionExample, UserFunction},
+};
+
+/// Сборщик функций из JSON-схем, заданных во время выполнения.
+///
+/// В отличие от [`FunctionBuilder`](super::FunctionBuilder), не требует
+/// Rust-типов для аргументов и результата: схемы и примеры передаются как
+/// [`serde_json::Value`]. Это позволяет описывать функции, загруженные из
+/// конфигурации, манифестов плагинов или других сервисов.
+///
+/// ## Пример
+///
+/// ```rust
+/// use gigachat_rust::function::{DynamicFunctionBuilder, FunctionExample};
+/// use serde_json::json;
+///
+/// let function = DynamicFunctionBuilder::new("add")
+///     .with_description("Складывает два числа.")
+///     .with_parameters(json!({
+///         "type": "object",
+///         "properties": {
+///             "a": { "type": "integer", "description": "Первое число." },
+///             "b": { "type": "integer", "description": "Второе число." }
+///         },
+///         "required": ["a", "b"]
+///     }))
+///     .with_example(FunctionExample {
+///         request: "Сколько будет 2 + 3?".to_string(),
+///         params: json!({ "a": 2, "b": 3 }),
+///     })
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(function.name(), "add");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DynamicFunctionBuilder {
+    name: String,
+    description: Option<String>,
+    parameters: Option<Value>,
+    return_parameters: Option<Value>,
+    examples: Vec<FunctionExample<Value>>,
+}
+
+impl DynamicFunctionBuilder {
+    /// Создает новый экземпляр сборщика функций.
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            parameters: None,
+            return_parameters: None,
+            examples: Vec::new(),
+        }
+    }
+
+    /// Устанавливает описание функции.
+    pub fn with_description<D: Into<String>>(mut self, description: D) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Устанавливает JSON-схему аргументов функции.
+    pub fn with_parameters(mut self, parameters: Value) -> Self {
+        self.parameters = Some(parameters);
+        self
+    }
+
+    /// Устанавливает JSON-схему результата функции.
+    ///
+    /// Если схема не задана, используется пустой объект.
+    pub fn with_return_parameters(mut self, return_parameters: Value) -> Self {
+        self.return_parameters = Some(return_parameters);
+        self
+    }
+
+    /// Добавляет пример использования функции.
+    pub fn with_example(mut self, example: FunctionExample<Value>) -> Self {
+        self.examples.push(example);
+        self
+    }
+
+    /// Собирает функцию без проверки схем.
+    pub fn build_unchecked(self) -> Result<UserFunction, error::Error> {
+        Ok(UserFunction {
+            name: self.name,
+            description: self.description,
+            parameters: self.parameters.context(error::ParametersAreMissingSnafu)?,
+            few_shot_examples: self
+                .examples
+                .into_iter()
+                .map(|e| serde_json::to_value(e).expect("JSON values always serialize"))
+                .collect(),
+            return_parameters: self
+                .return_parameters
+                .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+        })
+    }
+
+    /// Собирает функцию и проверяет ее при помощи [`UserFunction::validate`].
+    ///
+    /// Предупреждения проверки записываются в журнал; чтобы получить их,
+    /// используйте [`DynamicFunctionBuilder::build_with_warnings`].
+    pub fn build(self) -> Result<UserFunction, error::Error> {
+        let (function, warnings) = self.build_with_warnings()?;
+        for warning in &warnings {
+            tracing::warn!(
+                function = function.name(),
+                location = warning.schema_location(),
+                "{}",
+                warning.description()
+            );
+        }
+        Ok(function)
+    }
+
+    /// Собирает функцию и возвращает ее вместе с предупреждениями
+    /// [`UserFunction::validate`].
+    pub fn build_with_warnings(
+        self,
+    ) -> Result<(UserFunction, Vec<FunctionCheckDiagnostics>), error::Error> {
+        let function = self.build_unchecked()?;
+        let warnings = function.validate()?;
+        Ok((function, warnings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> DynamicFunctionBuilder {
+        DynamicFunctionBuilder::new("weather")
+            .with_description("Возвращает погоду в городе.")
+            .with_parameters(json!({
+                "type": "object",
+                "properties": {
+                    "city": { "type": "string", "description": "Город" },
+                },
+                "required": ["city"],
+            }))
+    }
+
+    #[test]
+    fn warnings_are_returned() {
+        let (function, warnings) = builder().build_with_warnings().unwrap();
+
+        assert_eq!(function.name(), "weather");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].schema_location(), "/few_shot_examples");
+    }
+
+    #[test]
+    fn warnings_do_not_fail_build() {
+        assert!(builder().build().is_ok());
+    }
+
+    #[test]
+    fn example_removes_warning() {
+        let (_, warnings) = builder()
+            .with_example(FunctionExample {
+                request: "Какая погода в Москве?".to_string(),
+                params: json!({ "city": "Москва" }),
+            })
+            .build_with_warnings()
+            .unwrap();
+
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn missing_parameters() {
+        let result = DynamicFunctionBuilder::new("weather").build_with_warnings();
+        assert!(matches!(result, Err(error::Error::ParametersAreMissing)));
+    }
+}
@@ -10,6 +10,9 @@ pub enum Error {
         source: crate::client::error::RequestError,
     },
 
+    #[snafu(display("parameters schema is missing"))]
+    ParametersAreMissing,
+
     #[snafu(display("bad function; errors: {errors:?}"))]
     BadFunction {
         errors: Vec<FunctionCheckDiagnostics>,
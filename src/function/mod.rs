@@ -1,5 +1,7 @@
+mod dynamic;
 pub mod error;
 pub mod structures;
+mod validation;
 
 use std::marker::PhantomData;
 
@@ -14,6 +16,7 @@ use crate::{
         structures::{FunctionCheckDiagnostics, FunctionCheckResult},
     },
 };
+pub use dynamic::DynamicFunctionBuilder;
 pub use structures::{FunctionCheckResponse, FunctionExample, FunctionName, UserFunction};
 
 /// Генератор JSON-схем.
@@ -6,6 +6,25 @@ pub struct FunctionCheckDiagnostics {
     schema_location: String,
 }
 
+impl FunctionCheckDiagnostics {
+    pub(crate) fn new(description: impl Into<String>, schema_location: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            schema_location: schema_location.into(),
+        }
+    }
+
+    /// Описание проблемы.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Расположение проблемы в схеме функции.
+    pub fn schema_location(&self) -> &str {
+        &self.schema_location
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FunctionExample<I> {
     pub request: String,
@@ -29,6 +48,26 @@ impl UserFunction {
     pub fn function_name(&self) -> FunctionName {
         FunctionName::new(self.name.clone())
     }
+
+    /// Описание функции.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// JSON-схема аргументов функции.
+    pub fn parameters(&self) -> &serde_json::Value {
+        &self.parameters
+    }
+
+    /// JSON-схема результата функции.
+    pub fn return_parameters(&self) -> &serde_json::Value {
+        &self.return_parameters
+    }
+
+    /// Примеры использования функции.
+    pub fn few_shot_examples(&self) -> &[serde_json::Value] {
+        &self.few_shot_examples
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -41,6 +80,11 @@ impl FunctionName {
         Self { name: name.into() }
     }
 
+    /// Возвращает имя функции в виде строки.
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
     pub fn text2image() -> Self {
         Self::new("text2image")
     }
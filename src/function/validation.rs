@@ -0,0 +1,512 @@
+use serde_json::{Map, Value};
+
+use super::{
+    error,
+    structures::{FunctionCheckDiagnostics, UserFunction},
+};
+
+/// Ключевые слова JSON Schema, которые не поддерживаются правилами JSON AI.
+const UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "$ref",
+    "$defs",
+    "definitions",
+    "oneOf",
+    "anyOf",
+    "allOf",
+    "not",
+    "if",
+    "then",
+    "else",
+    "patternProperties",
+];
+
+impl UserFunction {
+    /// Проверяет функцию на корректность без обращения к API.
+    ///
+    /// Повторяет основные правила JSON AI, которые применяет
+    /// [`GigaChatClient::check_function`](crate::client::GigaChatClient::check_function):
+    /// наличие описаний у функции и всех полей, отсутствие неподдерживаемых
+    /// ключевых слов (`$ref`, `oneOf` и т.д.) и соответствие примеров схеме
+    /// аргументов. Расположение ошибки указывается в виде JSON Pointer
+    /// относительно описания функции, например `/parameters/properties/a`.
+    ///
+    /// Как и при проверке через API, ошибки возвращаются как
+    /// [`error::Error::BadFunction`], а предупреждения — как успешный результат.
+    pub fn validate(&self) -> Result<Vec<FunctionCheckDiagnostics>, error::Error> {
+        let mut linter = Linter::default();
+        linter.lint_function(self);
+
+        if linter.errors.is_empty() {
+            Ok(linter.warnings)
+        } else {
+            error::BadFunctionSnafu {
+                errors: linter.errors,
+            }
+            .fail()
+        }
+    }
+}
+
+#[derive(Default)]
+struct Linter {
+    errors: Vec<FunctionCheckDiagnostics>,
+    warnings: Vec<FunctionCheckDiagnostics>,
+}
+
+/// Добавляет сегмент к JSON Pointer, экранируя `~` и `/`.
+fn push_pointer(location: &str, segment: &str) -> String {
+    let segment = segment.replace('~', "~0").replace('/', "~1");
+    format!("{location}/{segment}")
+}
+
+/// Возвращает название JSON-типа значения.
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Проверяет, соответствует ли значение JSON-типу из схемы.
+fn matches_type(value: &Value, ty: &str) -> bool {
+    match (ty, value) {
+        ("integer", Value::Number(n)) => {
+            n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        ("number", Value::Number(_)) => true,
+        (ty, value) => ty == value_type(value),
+    }
+}
+
+impl Linter {
+    fn error(&mut self, description: impl Into<String>, location: impl Into<String>) {
+        self.errors
+            .push(FunctionCheckDiagnostics::new(description, location));
+    }
+
+    fn warning(&mut self, description: impl Into<String>, location: impl Into<String>) {
+        self.warnings
+            .push(FunctionCheckDiagnostics::new(description, location));
+    }
+
+    fn lint_function(&mut self, function: &UserFunction) {
+        if function.name.trim().is_empty() {
+            self.error("function name is empty", "/name");
+        }
+
+        if function
+            .description
+            .as_deref()
+            .is_none_or(|d| d.trim().is_empty())
+        {
+            self.error("function description is missing", "/description");
+        }
+
+        match function.parameters.get("type").and_then(Value::as_str) {
+            Some("object") => {}
+            _ => self.error("parameters must be an object schema", "/parameters"),
+        }
+        self.lint_schema(&function.parameters, "/parameters", false);
+        self.lint_schema(&function.return_parameters, "/return_parameters", false);
+
+        if function.few_shot_examples.is_empty() {
+            self.warning("few-shot examples are missing", "/few_shot_examples");
+        }
+
+        for (index, example) in function.few_shot_examples.iter().enumerate() {
+            let location = format!("/few_shot_examples/{index}");
+
+            if example
+                .get("request")
+                .and_then(Value::as_str)
+                .is_none_or(|r| r.trim().is_empty())
+            {
+                self.error(
+                    "example request is missing",
+                    push_pointer(&location, "request"),
+                );
+            }
+
+            match example.get("params") {
+                Some(params) => self.lint_instance(
+                    &function.parameters,
+                    params,
+                    &push_pointer(&location, "params"),
+                ),
+                None => self.error("example params are missing", location),
+            }
+        }
+    }
+
+    /// Проверяет схему на наличие описаний и неподдерживаемых ключевых слов.
+    ///
+    /// `is_property` означает, что схема описывает поле объекта и поэтому
+    /// должна содержать описание.
+    fn lint_schema(&mut self, schema: &Value, location: &str, is_property: bool) {
+        let Some(schema) = schema.as_object() else {
+            self.error("schema must be an object", location);
+            return;
+        };
+
+        for keyword in UNSUPPORTED_KEYWORDS {
+            if schema.contains_key(*keyword) {
+                self.error(
+                    format!("unsupported keyword `{keyword}`"),
+                    push_pointer(location, keyword),
+                );
+            }
+        }
+
+        if is_property
+            && schema
+                .get("description")
+                .and_then(Value::as_str)
+                .is_none_or(|d| d.trim().is_empty())
+        {
+            self.error("description is missing", location);
+        }
+
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            let location = push_pointer(location, "properties");
+            for (name, property) in properties {
+                self.lint_schema(property, &push_pointer(&location, name), true);
+            }
+        }
+
+        if let Some(items) = schema.get("items").filter(|i| i.is_object()) {
+            self.lint_schema(items, &push_pointer(location, "items"), false);
+        }
+
+        if let Some(additional) = schema.get("additionalProperties").filter(|a| a.is_object()) {
+            self.lint_schema(
+                additional,
+                &push_pointer(location, "additionalProperties"),
+                false,
+            );
+        }
+    }
+
+    /// Проверяет соответствие значения схеме.
+    ///
+    /// Поддерживается подмножество JSON Schema, которое генерирует [`super::SberSchema`].
+    fn lint_instance(&mut self, schema: &Value, value: &Value, location: &str) {
+        let Some(schema) = schema.as_object() else {
+            return;
+        };
+
+        let nullable = schema.get("nullable").and_then(Value::as_bool) == Some(true);
+        if value.is_null() && nullable {
+            return;
+        }
+
+        let types: Vec<&str> = match schema.get("type") {
+            Some(Value::String(ty)) => vec![ty.as_str()],
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|ty| matches_type(value, ty)) {
+            self.error(
+                format!(
+                    "expected `{}`, found `{}`",
+                    types.join("` or `"),
+                    value_type(value)
+                ),
+                location,
+            );
+            return;
+        }
+
+        if let Some(variants) = schema.get("enum").and_then(Value::as_array)
+            && !variants.contains(value)
+        {
+            self.error(
+                format!("value {value} is not one of {variants:?}"),
+                location,
+            );
+        }
+
+        if let Some(expected) = schema.get("const")
+            && expected != value
+        {
+            self.error(
+                format!("value {value} is not equal to {expected}"),
+                location,
+            );
+        }
+
+        if let Some(number) = value.as_f64() {
+            if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64)
+                && number < minimum
+            {
+                self.error(format!("value {value} is less than {minimum}"), location);
+            }
+            if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64)
+                && number > maximum
+            {
+                self.error(format!("value {value} is greater than {maximum}"), location);
+            }
+        }
+
+        match value {
+            Value::Object(object) => self.lint_object(schema, object, location),
+            Value::Array(elements) => {
+                if let Some(items) = schema.get("items") {
+                    for (index, element) in elements.iter().enumerate() {
+                        self.lint_instance(items, element, &format!("{location}/{index}"));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lint_object(
+        &mut self,
+        schema: &Map<String, Value>,
+        object: &Map<String, Value>,
+        location: &str,
+    ) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        for required in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !object.contains_key(required) {
+                self.error(format!("missing required property `{required}`"), location);
+            }
+        }
+
+        for (name, value) in object {
+            let property_location = push_pointer(location, name);
+            match properties.and_then(|p| p.get(name)) {
+                Some(property) => self.lint_instance(property, value, &property_location),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        self.error(format!("unknown property `{name}`"), property_location)
+                    }
+                    Some(additional @ Value::Object(_)) => {
+                        self.lint_instance(additional, value, &property_location)
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn function(parameters: Value) -> UserFunction {
+        UserFunction {
+            name: "weather".to_string(),
+            description: Some("Возвращает прогноз погоды".to_string()),
+            parameters,
+            few_shot_examples: vec![json!({
+                "request": "Погода в Москве",
+                "params": { "city": "Москва" },
+            })],
+            return_parameters: json!({ "type": "object", "properties": {} }),
+        }
+    }
+
+    fn city_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "city": { "type": "string", "description": "Город" },
+            },
+            "required": ["city"],
+        })
+    }
+
+    fn errors(function: &UserFunction) -> Vec<(String, String)> {
+        match function.validate() {
+            Ok(_) => Vec::new(),
+            Err(error::Error::BadFunction { errors }) => errors
+                .into_iter()
+                .map(|e| (e.description().to_string(), e.schema_location().to_string()))
+                .collect(),
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    fn locations(function: &UserFunction) -> Vec<String> {
+        errors(function).into_iter().map(|(_, l)| l).collect()
+    }
+
+    #[test]
+    fn valid_schema_passes() {
+        let warnings = function(city_schema()).validate().unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn missing_examples_is_a_warning() {
+        let mut function = function(city_schema());
+        function.few_shot_examples.clear();
+
+        let warnings = function.validate().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].schema_location(), "/few_shot_examples");
+    }
+
+    #[test]
+    fn empty_name_and_missing_function_description() {
+        let mut function = function(city_schema());
+        function.name = " ".to_string();
+        function.description = None;
+
+        assert_eq!(locations(&function), ["/name", "/description"]);
+    }
+
+    #[test]
+    fn missing_property_description() {
+        let function = function(json!({
+            "type": "object",
+            "properties": { "city": { "type": "string" } },
+        }));
+
+        assert_eq!(locations(&function), ["/parameters/properties/city"]);
+    }
+
+    #[test]
+    fn parameters_must_be_object_schema() {
+        let mut function = function(json!({ "type": "string" }));
+        function.few_shot_examples.clear();
+
+        assert_eq!(locations(&function), ["/parameters"]);
+    }
+
+    #[test]
+    fn unsupported_keywords() {
+        let mut function = function(json!({
+            "type": "object",
+            "$defs": {},
+            "properties": {
+                "value": {
+                    "description": "Значение",
+                    "oneOf": [{ "type": "string" }, { "type": "integer" }],
+                },
+            },
+        }));
+        function.few_shot_examples.clear();
+
+        assert_eq!(
+            errors(&function),
+            [
+                (
+                    "unsupported keyword `$defs`".to_string(),
+                    "/parameters/$defs".to_string()
+                ),
+                (
+                    "unsupported keyword `oneOf`".to_string(),
+                    "/parameters/properties/value/oneOf".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_objects_and_arrays() {
+        let mut function = function(json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "type": "object",
+                    "description": "Адрес",
+                    "properties": {
+                        "a/b": { "type": "string" },
+                    },
+                },
+                "tags": {
+                    "type": "array",
+                    "description": "Теги",
+                    "items": {
+                        "type": "object",
+                        "properties": { "name": { "type": "string" } },
+                    },
+                },
+            },
+        }));
+        function.few_shot_examples.clear();
+
+        assert_eq!(
+            locations(&function),
+            [
+                "/parameters/properties/address/properties/a~1b",
+                "/parameters/properties/tags/items/properties/name",
+            ]
+        );
+    }
+
+    #[test]
+    fn example_must_match_schema() {
+        let mut function = function(json!({
+            "type": "object",
+            "properties": {
+                "city": { "type": "string", "description": "Город" },
+                "days": {
+                    "type": "integer",
+                    "description": "Дни",
+                    "minimum": 1,
+                    "maximum": 7,
+                },
+                "units": {
+                    "type": "string",
+                    "description": "Единицы",
+                    "enum": ["c", "f"],
+                },
+            },
+            "required": ["city"],
+            "additionalProperties": false,
+        }));
+        function.few_shot_examples = vec![
+            json!({ "request": "", "params": { "days": 10, "units": "k", "extra": 1 } }),
+            json!({ "request": "Погода", "params": { "city": 1 } }),
+            json!({ "request": "Погода" }),
+        ];
+
+        assert_eq!(
+            locations(&function),
+            [
+                "/few_shot_examples/0/request",
+                "/few_shot_examples/0/params",
+                "/few_shot_examples/0/params/days",
+                "/few_shot_examples/0/params/extra",
+                "/few_shot_examples/0/params/units",
+                "/few_shot_examples/1/params/city",
+                "/few_shot_examples/2",
+            ]
+        );
+    }
+
+    #[test]
+    fn nullable_and_integer_values() {
+        let mut function = function(json!({
+            "type": "object",
+            "properties": {
+                "count": { "type": "integer", "description": "Количество", "nullable": true },
+            },
+        }));
+        function.few_shot_examples = vec![
+            json!({ "request": "a", "params": { "count": null } }),
+            json!({ "request": "b", "params": { "count": 2.0 } }),
+            json!({ "request": "c", "params": { "count": 2.5 } }),
+        ];
+
+        assert_eq!(locations(&function), ["/few_shot_examples/2/params/count"]);
+    }
+}
@@ -1,15 +1,20 @@
 use eventsource_stream::Eventsource;
 use futures::{Stream, StreamExt, TryStreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use snafu::prelude::*;
 use std::future;
-use tracing::Span;
+use tracing::{Instrument, Span};
 
 use super::{
     error,
-    structures::{GenerationRequest, GenerationResponse, GenerationResponseStream, Message},
+    structures::{
+        GenerationRequest, GenerationResponse, GenerationResponseStream, Message, TokensCount,
+        TokensCountRequest,
+    },
 };
 use crate::{
-    client::GigaChatClient,
+    cache::CacheMode,
+    client::{GigaChatClient, error::ErrorClass},
     function::{FunctionName, UserFunction},
     generation::structures::{Function, FunctionCall},
 };
@@ -22,8 +27,16 @@ pub struct GenerationBuilder {
     config: super::structures::GenerationConfig,
     functions: Vec<Function>,
     function_call: FunctionCall,
+    session_id: Option<String>,
+    headers: HeaderMap,
+    fallback_models: Vec<super::Model>,
+    fallback_on: Vec<ErrorClass>,
+    cache_mode: CacheMode,
 }
 
+/// Заголовок, по которому GigaChat кэширует контекст сессии.
+const SESSION_ID_HEADER: &str = "X-Session-ID";
+
 impl GenerationBuilder {
     pub fn with_model(mut self, model: super::Model) -> Self {
         self.model = model;
@@ -35,6 +48,11 @@ impl GenerationBuilder {
         self
     }
 
+    pub fn with_config(mut self, config: super::structures::GenerationConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     pub fn with_temperature(mut self, temperature: f32) -> Self {
         self.config.temperature = Some(temperature);
         self
@@ -60,6 +78,20 @@ impl GenerationBuilder {
         self
     }
 
+    pub fn with_user_functions<F: IntoIterator<Item = UserFunction>>(
+        mut self,
+        functions: F,
+    ) -> Self {
+        self.functions
+            .extend(functions.into_iter().map(Function::User));
+        self
+    }
+
+    pub fn with_functions<F: IntoIterator<Item = Function>>(mut self, functions: F) -> Self {
+        self.functions.extend(functions);
+        self
+    }
+
     pub fn with_builtin_function(mut self, function: FunctionName) -> Self {
         self.functions.push(Function::BuiltIn(function));
         self
@@ -70,6 +102,59 @@ impl GenerationBuilder {
         self
     }
 
+    /// Устанавливает идентификатор сессии.
+    ///
+    /// Запросы с одинаковым идентификатором позволяют GigaChat кэшировать
+    /// историю сообщений и снижать стоимость повторной обработки контекста.
+    pub fn with_session_id<S: Into<String>>(mut self, session_id: S) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Добавляет произвольный HTTP-заголовок к запросу.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Устанавливает цепочку резервных моделей.
+    ///
+    /// Модели перебираются по порядку после основной, если запрос завершился
+    /// ошибкой одного из классов, заданных [`GenerationBuilder::with_fallback_on`].
+    pub fn with_fallback_models<M: IntoIterator<Item = super::Model>>(mut self, models: M) -> Self {
+        self.fallback_models = models.into_iter().collect();
+        self
+    }
+
+    /// Устанавливает классы ошибок, при которых выполняется переход на резервную модель.
+    ///
+    /// По умолчанию: [`ErrorClass::RateLimited`] и [`ErrorClass::ServerError`].
+    pub fn with_fallback_on<C: IntoIterator<Item = ErrorClass>>(mut self, classes: C) -> Self {
+        self.fallback_on = classes.into_iter().collect();
+        self
+    }
+
+    /// Устанавливает режим использования кэша ответов.
+    ///
+    /// Имеет значение только если кэш подключен к клиенту.
+    pub fn with_cache_mode(mut self, cache_mode: CacheMode) -> Self {
+        self.cache_mode = cache_mode;
+        self
+    }
+
+    /// Применяет заголовки запроса к HTTP-запросу.
+    fn apply_headers(
+        request: reqwest_middleware::RequestBuilder,
+        session_id: Option<String>,
+        headers: HeaderMap,
+    ) -> reqwest_middleware::RequestBuilder {
+        let request = request.headers(headers);
+        match session_id {
+            Some(session_id) => request.header(SESSION_ID_HEADER, session_id),
+            None => request,
+        }
+    }
+
     pub fn build(self) -> GenerationRequest {
         GenerationRequest {
             model: self.model,
@@ -80,10 +165,31 @@ impl GenerationBuilder {
         }
     }
 
+    /// Выполняет запрос генерации.
+    ///
+    /// Если заданы резервные модели ([`GenerationBuilder::with_fallback_models`]),
+    /// то при ошибке одного из классов [`GenerationBuilder::with_fallback_on`]
+    /// тот же запрос повторяется на следующей модели цепочки. Модель, вернувшая
+    /// ответ, указывается в [`GenerationResponse::served_by`].
     #[tracing::instrument(skip_all, fields(url))]
-    pub async fn execute(self) -> Result<GenerationResponse, error::Error> {
+    pub async fn execute(mut self) -> Result<GenerationResponse, error::Error> {
         let client = self.client.clone();
-        let request = self.build();
+        let session_id = self.session_id.take();
+        let headers = std::mem::take(&mut self.headers);
+        let fallback_models = std::mem::take(&mut self.fallback_models);
+        let fallback_on = std::mem::take(&mut self.fallback_on);
+        let cache_mode = self.cache_mode;
+        let mut request = self.build();
+
+        let cache = client
+            .cache()
+            .filter(|cache| cache.allows(cache_mode, request.config.temperature));
+        let cache_key = cache.and_then(|cache| cache.key("generation", &request));
+        if let (Some(cache), Some(key)) = (cache, &cache_key)
+            && let Some(response) = cache.get(key).await
+        {
+            return Ok(response);
+        }
 
         let url = client
             .build_url("chat/completions", None)
@@ -91,19 +197,53 @@ impl GenerationBuilder {
         Span::current().record("url", url.as_str());
         tracing::debug!("URL constructed successfully");
 
-        client
-            // .perform_request(|c| c.post(url).json(&request), async |r| r.json().await)
-            .perform_request(
-                |c| c.post(url).json(&request),
-                async |r| {
-                    let response = r.json::<serde_json::Value>().await?;
-                    println!("{response}");
+        let models: Vec<_> = std::iter::once(request.model.clone())
+            .chain(fallback_models)
+            .collect();
+        let last_attempt = models.len() - 1;
 
-                    Ok(serde_json::from_value(response).unwrap())
-                },
-            )
-            .await
-            .context(error::BadRequestSnafu)
+        for (attempt, model) in models.into_iter().enumerate() {
+            request.model = model.clone();
+            let span = tracing::info_span!("generation_attempt", attempt, model = ?model);
+            let result = client
+                .perform_request(
+                    |c| {
+                        Self::apply_headers(
+                            c.post(url.clone()),
+                            session_id.clone(),
+                            headers.clone(),
+                        )
+                        .json(&request)
+                    },
+                    async |r| r.json::<GenerationResponse>().await,
+                )
+                .instrument(span)
+                .await
+                .context(error::BadRequestSnafu);
+
+            match result {
+                Ok(mut response) => {
+                    response.served_by = Some(model);
+                    // Ключ кэша построен по запрошенной модели, поэтому ответы
+                    // резервных моделей в кэш не попадают.
+                    if attempt == 0
+                        && let (Some(cache), Some(key)) = (cache, &cache_key)
+                    {
+                        cache.put(key, &response).await;
+                    }
+                    return Ok(response);
+                }
+                Err(error)
+                    if attempt < last_attempt
+                        && error.class().is_some_and(|c| fallback_on.contains(&c)) =>
+                {
+                    tracing::warn!(attempt, model = ?model, error = %error, "falling back to next model");
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        unreachable!("at least one model is always attempted")
     }
 
     #[tracing::instrument(skip_all, fields(url))]
@@ -113,6 +253,8 @@ impl GenerationBuilder {
     {
         let client = self.client.clone();
         self.config.stream = true;
+        let session_id = self.session_id.take();
+        let headers = std::mem::take(&mut self.headers);
         let request = self.build();
 
         let url = client
@@ -122,7 +264,10 @@ impl GenerationBuilder {
         tracing::debug!("URL constructed successfully");
 
         let stream = client
-            .perform_request(|c| c.post(url).json(&request), async |r| Ok(r))
+            .perform_request(
+                |c| Self::apply_headers(c.post(url), session_id, headers).json(&request),
+                async |r| Ok(r),
+            )
             .await
             .context(error::BadRequestSnafu)?;
 
@@ -146,6 +291,25 @@ impl GenerationBuilder {
 }
 
 impl GigaChatClient {
+    /// Подсчитывает количество токенов в строках для указанной модели.
+    ///
+    /// Результаты возвращаются в порядке входных строк.
+    #[tracing::instrument(skip_all, fields(tokens.inputs = input.len()), err)]
+    pub async fn count_tokens(
+        &self,
+        model: super::Model,
+        input: Vec<String>,
+    ) -> Result<Vec<TokensCount>, error::Error> {
+        let request = TokensCountRequest { model, input };
+        let url = self
+            .build_url("tokens/count", None)
+            .context(error::BuildUrlSnafu)?;
+
+        self.perform_request(|c| c.post(url).json(&request), async |r| r.json().await)
+            .await
+            .context(error::BadRequestSnafu)
+    }
+
     pub fn generate(&self) -> GenerationBuilder {
         GenerationBuilder {
             client: self.clone(),
@@ -154,6 +318,184 @@ impl GigaChatClient {
             config: Default::default(),
             function_call: FunctionCall::default(),
             functions: Vec::new(),
+            session_id: None,
+            headers: HeaderMap::new(),
+            fallback_models: Vec::new(),
+            fallback_on: vec![ErrorClass::RateLimited, ErrorClass::ServerError],
+            cache_mode: CacheMode::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::NonZeroUsize,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+    };
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        cache::{MemoryStore, ResponseCache},
+        client::error::RequestError,
+        generation::Model,
+        mock::{self, MockServer},
+    };
+
+    /// Отвечает ошибкой с заданным статусом для перечисленных моделей
+    /// и успешным ответом запрошенной модели для остальных.
+    async fn server(statuses: &'static [(&'static str, u16)]) -> MockServer {
+        MockServer::start(move |request| {
+            let model = request.json()["model"].as_str().unwrap().to_string();
+            match statuses.iter().find(|(name, _)| *name == model) {
+                Some(&(_, status)) => (status, json!({ "message": "error" })),
+                None => (200, mock::completion(&model, "ответ")),
+            }
+        })
+        .await
+    }
+
+    fn models(server: &MockServer) -> Vec<String> {
+        server
+            .requests()
+            .iter()
+            .map(|request| request.json()["model"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    async fn generate(server: &MockServer) -> GenerationBuilder {
+        server
+            .client()
+            .await
+            .generate()
+            .with_messages(vec![Message::user("привет")])
+    }
+
+    #[tokio::test]
+    async fn primary_model_serves_request() {
+        let server = server(&[]).await;
+        let response = generate(&server)
+            .await
+            .with_fallback_models([Model::GigaChat2Pro])
+            .execute()
+            .await
+            .unwrap();
+
+        assert_eq!(response.served_by, Some(Model::GigaChat2Max));
+        assert_eq!(models(&server), ["GigaChat-2-Max"]);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_and_server_error_fall_back() {
+        let server = server(&[("GigaChat-2-Max", 429), ("GigaChat-2-Pro", 503)]).await;
+        let response = generate(&server)
+            .await
+            .with_fallback_models([Model::GigaChat2Pro, Model::GigaChat2Lite])
+            .execute()
+            .await
+            .unwrap();
+
+        assert_eq!(response.served_by, Some(Model::GigaChat2Lite));
+        assert_eq!(response.model, Model::GigaChat2Lite);
+        assert_eq!(
+            models(&server),
+            ["GigaChat-2-Max", "GigaChat-2-Pro", "GigaChat-2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn other_errors_do_not_fall_back() {
+        let server = server(&[("GigaChat-2-Max", 400)]).await;
+        let error = generate(&server)
+            .await
+            .with_fallback_models([Model::GigaChat2Pro])
+            .execute()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            error::Error::BadRequest {
+                source: RequestError::BadResponse {
+                    status_code: 400,
+                    ..
+                }
+            }
+        ));
+        assert_eq!(models(&server), ["GigaChat-2-Max"]);
+    }
+
+    #[tokio::test]
+    async fn fallback_classes_are_configurable() {
+        let server = server(&[("GigaChat-2-Max", 503)]).await;
+        let error = generate(&server)
+            .await
+            .with_fallback_models([Model::GigaChat2Pro])
+            .with_fallback_on([ErrorClass::RateLimited])
+            .execute()
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.class(), Some(ErrorClass::ServerError));
+        assert_eq!(models(&server), ["GigaChat-2-Max"]);
+    }
+
+    #[tokio::test]
+    async fn last_error_is_returned_when_chain_is_exhausted() {
+        let server = server(&[("GigaChat-2-Max", 429), ("GigaChat-2-Pro", 429)]).await;
+        let error = generate(&server)
+            .await
+            .with_fallback_models([Model::GigaChat2Pro])
+            .execute()
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.class(), Some(ErrorClass::RateLimited));
+        assert_eq!(models(&server), ["GigaChat-2-Max", "GigaChat-2-Pro"]);
+    }
+
+    #[tokio::test]
+    async fn only_requested_model_responses_are_cached() {
+        let primary_down = Arc::new(AtomicBool::new(true));
+        let down = primary_down.clone();
+        let server = MockServer::start(move |request| {
+            let model = request.json()["model"].as_str().unwrap().to_string();
+            if model == "GigaChat-2-Max" && down.load(Ordering::SeqCst) {
+                (429, json!({ "message": "too many requests" }))
+            } else {
+                (200, mock::completion(&model, "ответ"))
+            }
+        })
+        .await;
+
+        let cache = ResponseCache::new(MemoryStore::new(NonZeroUsize::new(16).unwrap()));
+        let client = server.client_builder().cache(cache).build().await.unwrap();
+        let generate = || {
+            client
+                .generate()
+                .with_messages(vec![Message::user("привет")])
+                .with_temperature(0.0)
+                .with_fallback_models([Model::GigaChat2Pro])
+        };
+
+        for _ in 0..2 {
+            let response = generate().execute().await.unwrap();
+            assert_eq!(response.served_by, Some(Model::GigaChat2Pro));
+        }
+        assert_eq!(server.requests().len(), 4);
+
+        primary_down.store(false, Ordering::SeqCst);
+        for _ in 0..2 {
+            let response = generate().execute().await.unwrap();
+            assert_eq!(response.served_by, Some(Model::GigaChat2Max));
         }
+        let models = models(&server);
+        assert_eq!(models.len(), 5);
+        assert_eq!(models[4], "GigaChat-2-Max");
     }
 }
@@ -34,3 +34,16 @@ pub enum Error {
         source: crate::client::BuildUrlError,
     },
 }
+
+impl Error {
+    /// Возвращает класс ошибки запроса, если он определен.
+    pub fn class(&self) -> Option<crate::client::error::ErrorClass> {
+        match self {
+            Error::BadRequest { source } => source.class(),
+            Error::StreamConnectionFailed { .. } => {
+                Some(crate::client::error::ErrorClass::Connection)
+            }
+            _ => None,
+        }
+    }
+}
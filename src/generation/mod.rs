@@ -5,7 +5,7 @@ pub mod error;
 pub mod structures;
 
 /// Модель для генерации текста.
-#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Model {
     /// Базовая модель, подходит для простых повседневных задач, требующих максимальной скорости и минимального потребления ресурсов.
     #[serde(rename = "GigaChat-2")]
@@ -21,3 +21,14 @@ pub enum Model {
     #[serde(untagged)]
     Custom(String),
 }
+
+impl From<&str> for Model {
+    fn from(model: &str) -> Self {
+        match model {
+            "GigaChat-2" => Model::GigaChat2Lite,
+            "GigaChat-2-Pro" => Model::GigaChat2Pro,
+            "GigaChat-2-Max" => Model::GigaChat2Max,
+            other => Model::Custom(other.to_string()),
+        }
+    }
+}
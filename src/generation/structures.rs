@@ -18,8 +18,13 @@ pub enum Message {
     System {
         content: String,
     },
+    /// Создается через [`Message::user`] или [`Message::user_with_attachments`].
+    #[non_exhaustive]
     User {
         content: String,
+        /// Идентификаторы файлов, загруженных в хранилище GigaChat.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        attachments: Vec<String>,
     },
     Assistant {
         content: String,
@@ -43,6 +48,17 @@ impl Message {
     pub fn user(content: impl Into<String>) -> Self {
         Self::User {
             content: content.into(),
+            attachments: Vec::new(),
+        }
+    }
+
+    pub fn user_with_attachments<A: IntoIterator<Item = String>>(
+        content: impl Into<String>,
+        attachments: A,
+    ) -> Self {
+        Self::User {
+            content: content.into(),
+            attachments: attachments.into_iter().collect(),
         }
     }
 
@@ -96,7 +112,7 @@ pub struct GenerationRequest {
     pub config: GenerationConfig,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum FinishReason {
     Stop,
@@ -143,6 +159,12 @@ pub struct GenerationResponse {
     pub created: OffsetDateTime,
     pub model: Model,
     pub usage: Usage,
+    /// Модель из цепочки резервных моделей, на которую был отправлен
+    /// успешный запрос. Заполняется при выполнении через
+    /// [`GenerationBuilder::execute`](super::builder::GenerationBuilder::execute)
+    /// и сохраняется вместе с ответом в кэше.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<Model>,
 }
 
 impl GenerationResponse {
@@ -151,7 +173,7 @@ impl GenerationResponse {
             .first()
             .and_then(|choice| match &choice.message {
                 Message::System { content }
-                | Message::User { content }
+                | Message::User { content, .. }
                 | Message::Assistant { content, .. } => Some(content.clone()),
                 Message::Function { .. } => None,
             })
@@ -179,3 +201,18 @@ pub struct GenerationResponseStream {
     pub created: OffsetDateTime,
     pub choices: Vec<ChoiceStreamPart>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokensCountRequest {
+    pub model: Model,
+    pub input: Vec<String>,
+}
+
+/// Количество токенов в строке.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokensCount {
+    /// Количество токенов.
+    pub tokens: usize,
+    /// Количество символов.
+    pub characters: usize,
+}
@@ -1,11 +1,20 @@
 pub mod client;
 
 pub mod batch;
+pub mod cache;
 pub mod check;
+pub mod conversation;
 pub mod embeddings;
 pub mod function;
 pub mod generation;
+#[cfg(feature = "mcp")]
+pub mod mcp;
+pub mod rag;
+pub mod splitter;
 
 pub mod serialization;
 
 pub mod error;
+
+#[cfg(test)]
+mod mock;
@@ -0,0 +1,35 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display("failed to spawn mcp server process"))]
+    SpawnServer { source: std::io::Error },
+
+    #[snafu(display("failed to initialize mcp session"))]
+    Initialize {
+        #[snafu(source(from(rmcp::service::ClientInitializeError, Box::new)))]
+        source: Box<rmcp::service::ClientInitializeError>,
+    },
+
+    #[snafu(display("failed to list mcp tools"))]
+    ListTools { source: rmcp::ServiceError },
+
+    #[snafu(display("failed to convert mcp tool '{name}' into function"))]
+    ConvertTool {
+        name: String,
+        source: crate::function::error::Error,
+    },
+
+    #[snafu(display("unknown mcp tool '{name}'"))]
+    UnknownTool { name: String },
+
+    #[snafu(display("arguments of mcp tool '{name}' must be an object"))]
+    ArgumentsNotObject { name: String },
+
+    #[snafu(display("failed to call mcp tool '{name}'"))]
+    CallTool {
+        name: String,
+        source: rmcp::ServiceError,
+    },
+}
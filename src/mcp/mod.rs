@@ -0,0 +1,239 @@
+use std::borrow::Cow;
+
+use rmcp::{
+    RoleClient, ServiceExt,
+    model::{CallToolRequestParam, CallToolResult, Tool},
+    service::RunningService,
+    transport::{StreamableHttpClientTransport, TokioChildProcess},
+};
+use serde_json::{Value, json};
+use snafu::prelude::*;
+use tokio::process::Command;
+
+use crate::{
+    function::{DynamicFunctionBuilder, FunctionName, UserFunction},
+    generation::structures::{FunctionCallResponse, Message},
+};
+
+pub mod error;
+mod schema;
+
+/// Клиент MCP (Model Context Protocol), предоставляющий инструменты
+/// MCP-сервера в виде функций GigaChat.
+///
+/// При подключении запрашивает список инструментов сервера и преобразует
+/// их входные схемы в [`UserFunction`], упрощая конструкции, которые
+/// GigaChat не поддерживает (`$ref`, `oneOf`, `anyOf` и т.д.).
+/// Вызовы функций, возвращенные моделью, передаются серверу как `tools/call`,
+/// а результат возвращается в виде [`Message::Function`].
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::{
+///     client::GigaChatClientBuilder,
+///     generation::structures::{FunctionCall, Message},
+///     mcp::McpClient,
+/// };
+/// use tokio::process::Command;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let mut command = Command::new("npx");
+///     command.args(["-y", "@modelcontextprotocol/server-everything"]);
+///     let mcp = McpClient::connect_stdio(command).await.unwrap();
+///
+///     let mut messages = vec![Message::user("Сложи 2 и 3.")];
+///     let response = client
+///         .generate()
+///         .with_messages(messages.clone())
+///         .with_user_functions(mcp.functions().iter().cloned())
+///         .with_function_call(FunctionCall::Auto)
+///         .execute()
+///         .await
+///         .unwrap();
+///
+///     let message = response.choices[0].message.clone();
+///     if let Message::Assistant { function_call: Some(call), .. } = &message {
+///         let result = mcp.call(call).await.unwrap();
+///         messages.extend([message.clone(), result]);
+///     }
+/// }
+/// ```
+pub struct McpClient {
+    service: RunningService<RoleClient, ()>,
+    functions: Vec<UserFunction>,
+}
+
+impl McpClient {
+    /// Запускает MCP-сервер как дочерний процесс и подключается к нему через stdio.
+    #[tracing::instrument(skip_all, err)]
+    pub async fn connect_stdio(command: Command) -> Result<Self, error::Error> {
+        let transport = TokioChildProcess::new(command).context(error::SpawnServerSnafu)?;
+        let service = ().serve(transport).await.context(error::InitializeSnafu)?;
+        Self::from_service(service).await
+    }
+
+    /// Подключается к MCP-серверу по протоколу Streamable HTTP.
+    #[tracing::instrument(skip_all, fields(url = url.as_ref()), err)]
+    pub async fn connect_http<U: AsRef<str>>(url: U) -> Result<Self, error::Error> {
+        let transport = StreamableHttpClientTransport::from_uri(url.as_ref());
+        let service = ().serve(transport).await.context(error::InitializeSnafu)?;
+        Self::from_service(service).await
+    }
+
+    async fn from_service(service: RunningService<RoleClient, ()>) -> Result<Self, error::Error> {
+        let mut result = Self {
+            service,
+            functions: Vec::new(),
+        };
+        result.refresh_functions().await?;
+        Ok(result)
+    }
+
+    /// Повторно запрашивает список инструментов сервера.
+    ///
+    /// Инструменты, схемы которых не удалось привести к функции GigaChat,
+    /// пропускаются с предупреждением в журнале, чтобы остальные оставались
+    /// доступны.
+    #[tracing::instrument(skip_all, fields(tools.count, tools.skipped), err)]
+    pub async fn refresh_functions(&mut self) -> Result<(), error::Error> {
+        let tools = self
+            .service
+            .list_all_tools()
+            .await
+            .context(error::ListToolsSnafu)?;
+        tracing::Span::current().record("tools.count", tools.len());
+
+        self.functions = tools
+            .iter()
+            .filter_map(|tool| match Self::tool_to_function(tool) {
+                Ok(function) => Some(function),
+                Err(error) => {
+                    tracing::warn!(
+                        tool.name = tool.name.as_ref(),
+                        error = ?error,
+                        "skipping mcp tool with invalid schema"
+                    );
+                    None
+                }
+            })
+            .collect();
+        tracing::Span::current().record("tools.skipped", tools.len() - self.functions.len());
+        Ok(())
+    }
+
+    /// Преобразует инструмент MCP в функцию GigaChat.
+    fn tool_to_function(tool: &Tool) -> Result<UserFunction, error::Error> {
+        let description = tool
+            .description
+            .as_deref()
+            .or(tool.title.as_deref())
+            .unwrap_or(&tool.name);
+
+        let mut builder = DynamicFunctionBuilder::new(tool.name.as_ref())
+            .with_description(description)
+            .with_parameters(schema::relax_root(&tool.input_schema));
+        if let Some(output_schema) = &tool.output_schema {
+            builder = builder.with_return_parameters(schema::relax_root(output_schema));
+        }
+
+        builder.build().context(error::ConvertToolSnafu {
+            name: tool.name.as_ref(),
+        })
+    }
+
+    /// Функции, соответствующие инструментам сервера.
+    pub fn functions(&self) -> &[UserFunction] {
+        &self.functions
+    }
+
+    /// Проверяет, предоставляет ли сервер функцию с указанным именем.
+    pub fn has_function(&self, name: &FunctionName) -> bool {
+        self.functions.iter().any(|f| f.name() == name.as_str())
+    }
+
+    /// Выполняет вызов функции, запрошенный моделью, на MCP-сервере.
+    ///
+    /// Результат возвращается в виде [`Message::Function`], готового
+    /// к добавлению в историю сообщений.
+    #[tracing::instrument(skip_all, fields(function.name = call.name.as_str()), err)]
+    pub async fn call(&self, call: &FunctionCallResponse) -> Result<Message, error::Error> {
+        let name = call.name.as_str();
+        ensure!(
+            self.has_function(&call.name),
+            error::UnknownToolSnafu { name }
+        );
+
+        let arguments = match &call.arguments {
+            Value::Object(arguments) => Some(arguments.clone()),
+            Value::Null => None,
+            _ => return error::ArgumentsNotObjectSnafu { name }.fail(),
+        };
+
+        let result = self
+            .service
+            .call_tool(CallToolRequestParam {
+                name: Cow::Owned(name.to_string()),
+                arguments,
+            })
+            .await
+            .context(error::CallToolSnafu { name })?;
+
+        Ok(Message::Function {
+            name: call.name.clone(),
+            content: Self::result_to_value(result),
+        })
+    }
+
+    /// Преобразует результат инструмента в JSON-объект.
+    ///
+    /// Структурированный результат используется как есть, текстовый
+    /// разбирается как JSON-объект или оборачивается в поле `result`
+    /// (`error`, если инструмент сообщил об ошибке).
+    fn result_to_value(result: CallToolResult) -> Value {
+        let is_error = result.is_error.unwrap_or(false);
+        if let Some(structured) = result.structured_content
+            && !is_error
+        {
+            return structured;
+        }
+
+        let text = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text())
+            .map(|content| content.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if is_error {
+            return json!({ "error": text });
+        }
+
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value @ Value::Object(_)) => value,
+            _ => json!({ "result": text }),
+        }
+    }
+
+    /// Завершает сессию и останавливает транспорт.
+    pub async fn close(self) {
+        if let Err(error) = self.service.cancel().await {
+            tracing::warn!(error = %error, "failed to close mcp session");
+        }
+    }
+}
+
+impl std::fmt::Debug for McpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpClient")
+            .field("functions", &self.functions.len())
+            .finish()
+    }
+}
@@ -0,0 +1,373 @@
+use serde_json::{Map, Value, json};
+
+/// Максимальная глубина раскрытия `$ref`, защищающая от рекурсивных схем.
+const MAX_REF_DEPTH: usize = 8;
+
+/// Ключевые слова, которые удаляются из схемы без замены.
+const DROPPED_KEYWORDS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "$defs",
+    "definitions",
+    "not",
+    "if",
+    "then",
+    "else",
+    "patternProperties",
+];
+
+/// Приводит JSON-схему инструмента MCP к виду, который принимает GigaChat.
+///
+/// Ссылки `$ref` раскрываются, `allOf` объединяется, `anyOf`/`oneOf` с `null`
+/// превращаются в `nullable`, а из остальных вариантов остается первый.
+/// Полям без описания назначается описание из `title` или имени поля.
+pub(crate) fn relax_root(schema: &Map<String, Value>) -> Value {
+    let definitions = schema
+        .get("$defs")
+        .or_else(|| schema.get("definitions"))
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+
+    let relaxer = Relaxer { definitions };
+    let mut root = relaxer.relax(&Value::Object(schema.clone()), 0);
+
+    let root_object = root
+        .as_object_mut()
+        .expect("relaxed schema is always an object");
+    root_object.insert("type".to_string(), json!("object"));
+    root_object.entry("properties").or_insert_with(|| json!({}));
+    root
+}
+
+struct Relaxer {
+    definitions: Map<String, Value>,
+}
+
+impl Relaxer {
+    fn resolve(&self, reference: &str) -> Option<&Value> {
+        let name = reference
+            .strip_prefix("#/$defs/")
+            .or_else(|| reference.strip_prefix("#/definitions/"))?;
+        self.definitions.get(name)
+    }
+
+    fn relax(&self, schema: &Value, depth: usize) -> Value {
+        let Some(schema) = schema.as_object() else {
+            return json!({});
+        };
+        let mut result = schema.clone();
+
+        if let Some(reference) = result.remove("$ref") {
+            match reference.as_str().and_then(|r| self.resolve(r)) {
+                Some(target) if depth < MAX_REF_DEPTH => {
+                    if let Value::Object(target) = self.relax(target, depth + 1) {
+                        for (key, value) in target {
+                            result.entry(key).or_insert(value);
+                        }
+                    }
+                }
+                _ => {
+                    result.entry("type").or_insert_with(|| json!("object"));
+                }
+            }
+        }
+
+        if let Some(Value::Array(parts)) = result.remove("allOf") {
+            for part in parts {
+                if let Value::Object(part) = self.relax(&part, depth) {
+                    merge_schema(&mut result, part);
+                }
+            }
+        }
+
+        for keyword in ["anyOf", "oneOf"] {
+            let Some(Value::Array(variants)) = result.remove(keyword) else {
+                continue;
+            };
+            let (nulls, variants): (Vec<_>, Vec<_>) = variants
+                .iter()
+                .partition(|v| v.get("type").and_then(Value::as_str) == Some("null"));
+            if !nulls.is_empty() {
+                result.insert("nullable".to_string(), json!(true));
+            }
+            if let Some(Value::Object(variant)) = variants.first().map(|v| self.relax(v, depth)) {
+                for (key, value) in variant {
+                    result.entry(key).or_insert(value);
+                }
+            }
+        }
+
+        if let Some(Value::Array(types)) = result.get("type").cloned() {
+            result.remove("type");
+            if types.iter().any(|t| t == "null") {
+                result.insert("nullable".to_string(), json!(true));
+            }
+            if let Some(ty) = types.into_iter().find(|t| t != "null") {
+                result.insert("type".to_string(), ty);
+            }
+        }
+
+        for keyword in DROPPED_KEYWORDS {
+            result.remove(*keyword);
+        }
+
+        if let Some(Value::Object(properties)) = result.get_mut("properties") {
+            for (name, property) in properties.iter_mut() {
+                let mut relaxed = self.relax(property, depth);
+                let relaxed_object = relaxed
+                    .as_object_mut()
+                    .expect("relaxed schema is always an object");
+                if relaxed_object
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .is_none_or(|d| d.trim().is_empty())
+                {
+                    let description = relaxed_object
+                        .get("title")
+                        .and_then(Value::as_str)
+                        .unwrap_or(name)
+                        .to_string();
+                    relaxed_object.insert("description".to_string(), json!(description));
+                }
+                *property = relaxed;
+            }
+        }
+
+        if let Some(items) = result.get_mut("items") {
+            *items = self.relax(items, depth);
+        }
+
+        if let Some(additional @ Value::Object(_)) = result.get_mut("additionalProperties") {
+            *additional = self.relax(additional, depth);
+        }
+
+        Value::Object(result)
+    }
+}
+
+/// Объединяет части `allOf`: свойства и обязательные поля суммируются,
+/// остальные ключи переносятся, если еще не заданы.
+fn merge_schema(target: &mut Map<String, Value>, part: Map<String, Value>) {
+    for (key, value) in part {
+        match (key.as_str(), target.get_mut(&key), value) {
+            ("properties", Some(Value::Object(existing)), Value::Object(properties)) => {
+                existing.extend(properties);
+            }
+            ("required", Some(Value::Array(existing)), Value::Array(required)) => {
+                for name in required {
+                    if !existing.contains(&name) {
+                        existing.push(name);
+                    }
+                }
+            }
+            (_, Some(_), _) => {}
+            (_, None, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relax(schema: Value) -> Value {
+        relax_root(schema.as_object().unwrap())
+    }
+
+    fn contains_key(value: &Value, key: &str) -> bool {
+        match value {
+            Value::Object(object) => {
+                object.contains_key(key) || object.values().any(|v| contains_key(v, key))
+            }
+            Value::Array(items) => items.iter().any(|v| contains_key(v, key)),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn empty_schema_becomes_object() {
+        assert_eq!(
+            relax_root(&Map::new()),
+            json!({ "type": "object", "properties": {} })
+        );
+    }
+
+    #[test]
+    fn references_are_inlined() {
+        let schema = relax(json!({
+            "type": "object",
+            "properties": {
+                "point": { "$ref": "#/$defs/Point" },
+                "missing": { "$ref": "#/$defs/Missing" },
+            },
+            "$defs": {
+                "Point": {
+                    "type": "object",
+                    "description": "Точка",
+                    "properties": { "x": { "type": "number", "description": "X" } },
+                },
+            },
+        }));
+
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "properties": {
+                    "point": {
+                        "type": "object",
+                        "description": "Точка",
+                        "properties": { "x": { "type": "number", "description": "X" } },
+                    },
+                    "missing": { "type": "object", "description": "missing" },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn recursive_references_terminate() {
+        let schema = relax(json!({
+            "properties": { "root": { "$ref": "#/definitions/Node" } },
+            "definitions": {
+                "Node": {
+                    "type": "object",
+                    "properties": { "child": { "$ref": "#/definitions/Node" } },
+                },
+            },
+        }));
+
+        assert!(!contains_key(&schema, "$ref"));
+        assert!(!contains_key(&schema, "definitions"));
+        assert_eq!(
+            schema["properties"]["root"]["properties"]["child"]["type"],
+            "object"
+        );
+    }
+
+    #[test]
+    fn all_of_is_merged() {
+        let schema = relax(json!({
+            "allOf": [
+                {
+                    "properties": { "a": { "type": "string", "description": "A" } },
+                    "required": ["a"],
+                },
+                {
+                    "properties": { "b": { "type": "integer", "description": "B" } },
+                    "required": ["a", "b"],
+                },
+            ],
+        }));
+
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "properties": {
+                    "a": { "type": "string", "description": "A" },
+                    "b": { "type": "integer", "description": "B" },
+                },
+                "required": ["a", "b"],
+            })
+        );
+    }
+
+    #[test]
+    fn null_variants_become_nullable() {
+        let schema = relax(json!({
+            "properties": {
+                "name": {
+                    "anyOf": [{ "type": "string" }, { "type": "null" }],
+                    "description": "Имя",
+                },
+                "tags": {
+                    "type": ["array", "null"],
+                    "items": { "type": "string" },
+                    "description": "Теги",
+                },
+                "choice": {
+                    "oneOf": [{ "type": "integer" }, { "type": "string" }],
+                    "description": "Выбор",
+                },
+            },
+        }));
+
+        assert_eq!(
+            schema["properties"],
+            json!({
+                "name": { "type": "string", "nullable": true, "description": "Имя" },
+                "tags": {
+                    "type": "array",
+                    "nullable": true,
+                    "items": { "type": "string" },
+                    "description": "Теги",
+                },
+                "choice": { "type": "integer", "description": "Выбор" },
+            })
+        );
+    }
+
+    #[test]
+    fn unsupported_keywords_are_dropped() {
+        let schema = relax(json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": "urn:tool",
+            "$comment": "комментарий",
+            "properties": {
+                "value": {
+                    "type": "string",
+                    "description": "Значение",
+                    "not": { "type": "null" },
+                    "if": { "minLength": 1 },
+                    "then": { "maxLength": 10 },
+                    "else": { "maxLength": 0 },
+                    "patternProperties": { "^x": { "type": "string" } },
+                },
+            },
+        }));
+
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "properties": {
+                    "value": { "type": "string", "description": "Значение" },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn missing_descriptions_are_filled() {
+        let schema = relax(json!({
+            "properties": {
+                "plain": { "type": "string" },
+                "titled": { "type": "string", "title": "Заголовок" },
+                "blank": { "type": "string", "description": "  " },
+                "nested": {
+                    "type": "object",
+                    "properties": { "inner": { "type": "string" } },
+                },
+            },
+        }));
+
+        assert_eq!(
+            schema["properties"],
+            json!({
+                "plain": { "type": "string", "description": "plain" },
+                "titled": { "type": "string", "title": "Заголовок", "description": "Заголовок" },
+                "blank": { "type": "string", "description": "blank" },
+                "nested": {
+                    "type": "object",
+                    "description": "nested",
+                    "properties": { "inner": { "type": "string", "description": "inner" } },
+                },
+            })
+        );
+    }
+}
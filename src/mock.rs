@@ -0,0 +1,228 @@
+//! Локальный HTTP-сервер для тестов клиента.
+//!
+//! Сервер сам отвечает на запрос токена (`/oauth`), остальные запросы
+//! передаются обработчику и сохраняются для проверок.
+
+use std::sync::{Arc, Mutex};
+
+use reqwest::Url;
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::client::{GigaChatClient, GigaChatClientBuilder};
+
+/// Запрос, принятый сервером.
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    /// Метод запроса, например `POST`.
+    pub(crate) method: String,
+    /// Путь запроса, например `/chat/completions`.
+    pub(crate) path: String,
+    /// Тело запроса.
+    pub(crate) body: Vec<u8>,
+}
+
+impl Request {
+    /// Тело запроса в виде JSON.
+    pub(crate) fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+type Handler = dyn Fn(&Request) -> (u16, String) + Send + Sync;
+
+/// Сервер, отвечающий на запросы обработчиком `(статус, тело)`.
+pub(crate) struct MockServer {
+    url: Url,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub(crate) async fn start<H>(handler: H) -> Self
+    where
+        H: Fn(&Request) -> (u16, Value) + Send + Sync + 'static,
+    {
+        Self::start_raw(move |request| {
+            let (status, body) = handler(request);
+            (status, body.to_string())
+        })
+        .await
+    }
+
+    /// Запускает сервер, отвечающий телом в исходном виде, например JSONL.
+    pub(crate) async fn start_raw<H>(handler: H) -> Self
+    where
+        H: Fn(&Request) -> (u16, String) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let log = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                let log = log.clone();
+                tokio::spawn(async move {
+                    let Some(request) = read_request(&mut stream).await else {
+                        return;
+                    };
+
+                    let (status, body) = if request.path == "/oauth" {
+                        let token = json!({
+                            "access_token": "token",
+                            "expires_at": 4_102_444_800_000_i64,
+                        });
+                        (200, token.to_string())
+                    } else {
+                        log.lock().unwrap().push(request.clone());
+                        (*handler)(&request)
+                    };
+
+                    let response = format!(
+                        "HTTP/1.1 {status} Mock\r\n\
+                         Content-Type: application/json\r\n\
+                         Content-Length: {}\r\n\
+                         Connection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Сборщик клиента, направленный на этот сервер.
+    pub(crate) fn client_builder(&self) -> GigaChatClientBuilder {
+        GigaChatClientBuilder::new("token".to_string())
+            .auth_url(self.url.join("oauth").unwrap())
+            .base_url(self.url.clone())
+    }
+
+    /// Клиент, направленный на этот сервер.
+    pub(crate) async fn client(&self) -> GigaChatClient {
+        self.client_builder().build().await.unwrap()
+    }
+
+    /// Запросы к API, принятые сервером, без запросов токена.
+    pub(crate) fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut start_line = head.split_whitespace();
+    let method = start_line.next()?.to_string();
+    let path = start_line.next()?.to_string();
+    let length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buffer.len() < header_end + length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    Some(Request {
+        method,
+        path,
+        body: buffer[header_end..header_end + length].to_vec(),
+    })
+}
+
+/// Ответ `/chat/completions` с одним сообщением ассистента.
+pub(crate) fn completion(model: &str, content: &str) -> Value {
+    json!({
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content, "function_call": null },
+            "finish_reason": "stop",
+        }],
+        "created": 1_700_000_000,
+        "model": model,
+        "usage": {
+            "prompt_tokens": 1,
+            "completion_tokens": 1,
+            "precached_prompt_tokens": 0,
+            "total_tokens": 2,
+        },
+    })
+}
+
+/// Ответ `/embeddings` с заданными векторами в порядке входных строк.
+pub(crate) fn embeddings(vectors: &[Vec<f32>]) -> Value {
+    let data: Vec<_> = vectors
+        .iter()
+        .enumerate()
+        .map(|(index, embedding)| {
+            json!({
+                "embedding": embedding,
+                "index": index,
+                "usage": { "prompt_tokens": 1 },
+            })
+        })
+        .collect();
+    json!({ "model": "EmbeddingsGigaR", "data": data })
+}
+
+/// Строки из поля `input` запроса `/embeddings`.
+pub(crate) fn embedding_inputs(request: &Request) -> Vec<String> {
+    serde_json::from_value(request.json()["input"].clone()).unwrap()
+}
+
+/// Описание пакета для ответов на создание и проверку пакета.
+pub(crate) fn batch(id: &str, method: &str, status: &str, output_file_id: Option<&str>) -> Value {
+    json!({
+        "id": id,
+        "method": method,
+        "counts": { "total": 1 },
+        "request_counts": { "total": 1, "completed": 0, "failed": 0 },
+        "status": status,
+        "output_file_id": output_file_id,
+        "created_at": 1_700_000_000_000_i64,
+        "updated_at": 1_700_000_000_000_i64,
+    })
+}
+
+/// Строки JSONL из значений.
+pub(crate) fn jsonl(lines: &[Value]) -> String {
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
+
+/// Строки JSONL из тела запроса.
+pub(crate) fn jsonl_lines(request: &Request) -> Vec<Value> {
+    request
+        .body
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect()
+}
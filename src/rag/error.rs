@@ -0,0 +1,26 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display("failed to embed text"))]
+    Embed {
+        source: crate::embeddings::error::Error,
+    },
+
+    #[snafu(display("failed to retrieve chunks"))]
+    Retrieve {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[snafu(display("generation failed"))]
+    Generate {
+        source: crate::generation::error::Error,
+    },
+
+    #[snafu(display("embeddings response contains no vectors"))]
+    EmptyEmbedding,
+
+    #[snafu(display("response contains no choices"))]
+    EmptyResponse,
+}
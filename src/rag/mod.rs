@@ -0,0 +1,540 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+
+use crate::{
+    client::GigaChatClient,
+    embeddings::{
+        self,
+        index::{Metric, VectorIndex},
+    },
+    generation::{
+        self,
+        structures::{GenerationConfig, GenerationResponse, Message},
+    },
+};
+
+pub mod error;
+
+/// Количество фрагментов, извлекаемых по умолчанию.
+const DEFAULT_TOP_K: usize = 4;
+
+/// Системный промпт по умолчанию.
+const DEFAULT_SYSTEM_PROMPT: &str = "Отвечай на вопрос пользователя, опираясь только на \
+    приведенные источники. После каждого утверждения указывай номер источника в квадратных \
+    скобках, например [1]. Если в источниках нет ответа, так и скажи.";
+
+/// Фрагмент документа, по которому выполняется поиск.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chunk {
+    /// Идентификатор фрагмента, например путь к документу и номер фрагмента.
+    pub id: String,
+    /// Текст фрагмента.
+    pub text: String,
+    /// Произвольные метаданные.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub metadata: serde_json::Value,
+}
+
+impl Chunk {
+    /// Создает фрагмент без метаданных.
+    pub fn new(id: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            text: text.into(),
+            metadata: serde_json::Value::Null,
+        }
+    }
+
+    /// Устанавливает метаданные фрагмента.
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+/// Фрагмент, найденный по запросу.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetrievedChunk {
+    /// Фрагмент документа.
+    pub chunk: Chunk,
+    /// Сходство с запросом.
+    pub score: f32,
+}
+
+/// Источник фрагментов для генерации с извлечением.
+///
+/// Реализация может обращаться к внешней векторной базе данных;
+/// для небольших наборов подходит [`InMemoryRetriever`].
+#[async_trait::async_trait]
+pub trait Retriever: Send + Sync {
+    /// Возвращает не более `k` фрагментов, наиболее близких к вектору запроса,
+    /// в порядке убывания сходства.
+    async fn retrieve(
+        &self,
+        query: &[f32],
+        k: usize,
+    ) -> Result<Vec<RetrievedChunk>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Источник фрагментов в памяти на основе [`VectorIndex`].
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryRetriever {
+    index: VectorIndex<Chunk>,
+}
+
+impl InMemoryRetriever {
+    /// Создает источник из готового индекса.
+    pub fn new(index: VectorIndex<Chunk>) -> Self {
+        Self { index }
+    }
+
+    /// Векторизует фрагменты и создает источник с косинусным сходством.
+    pub async fn from_chunks(
+        client: &GigaChatClient,
+        model: embeddings::Model,
+        chunks: Vec<Chunk>,
+    ) -> Result<Self, error::Error> {
+        let embeddings = client
+            .embeddings()
+            .with_model(model)
+            .with_inputs(chunks.iter().map(|chunk| chunk.text.clone()))
+            .with_chunking(embeddings::ChunkingConfig::default())
+            .execute()
+            .await
+            .context(error::EmbedSnafu)?;
+
+        let dimension = embeddings
+            .items
+            .first()
+            .map_or(0, |item| item.embedding.len());
+        let mut index = VectorIndex::new(dimension, Metric::Cosine);
+        for (item, chunk) in embeddings.items.into_iter().zip(chunks) {
+            index
+                .insert(item.embedding, chunk)
+                .context(error::EmbedSnafu)?;
+        }
+
+        Ok(Self { index })
+    }
+
+    /// Индекс фрагментов.
+    pub fn index(&self) -> &VectorIndex<Chunk> {
+        &self.index
+    }
+
+    /// Изменяемый индекс фрагментов.
+    pub fn index_mut(&mut self) -> &mut VectorIndex<Chunk> {
+        &mut self.index
+    }
+}
+
+#[async_trait::async_trait]
+impl Retriever for InMemoryRetriever {
+    async fn retrieve(
+        &self,
+        query: &[f32],
+        k: usize,
+    ) -> Result<Vec<RetrievedChunk>, Box<dyn std::error::Error + Send + Sync>> {
+        if self.index.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self
+            .index
+            .search(query, k)?
+            .into_iter()
+            .map(|hit| RetrievedChunk {
+                chunk: hit.payload.clone(),
+                score: hit.score,
+            })
+            .collect())
+    }
+}
+
+/// Ответ, сгенерированный с опорой на найденные фрагменты.
+#[derive(Debug, Clone)]
+pub struct RagAnswer {
+    /// Текст ответа.
+    pub text: String,
+    /// Фрагменты, переданные модели; источник `[n]` соответствует `sources[n - 1]`.
+    pub sources: Vec<RetrievedChunk>,
+    /// Исходный ответ модели.
+    pub response: GenerationResponse,
+}
+
+impl RagAnswer {
+    /// Фрагменты, на которые модель сослалась в ответе.
+    pub fn cited_sources(&self) -> Vec<&RetrievedChunk> {
+        let mut cited = vec![false; self.sources.len()];
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find('[') {
+            rest = &rest[start + 1..];
+            let Some(end) = rest.find(']') else {
+                break;
+            };
+            for number in rest[..end].split(',') {
+                if let Ok(number) = number.trim().parse::<usize>()
+                    && (1..=cited.len()).contains(&number)
+                {
+                    cited[number - 1] = true;
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+
+        self.sources
+            .iter()
+            .zip(cited)
+            .filter_map(|(source, cited)| cited.then_some(source))
+            .collect()
+    }
+}
+
+/// Сборщик конвейера генерации с извлечением (RAG).
+///
+/// ## Пример
+///
+/// ```rust,no_run
+/// use gigachat_rust::client::GigaChatClientBuilder;
+/// use gigachat_rust::embeddings::Model;
+/// use gigachat_rust::rag::{Chunk, InMemoryRetriever};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let client = GigaChatClientBuilder::new("YOUR_TOKEN".to_string())
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let chunks = vec![
+///         Chunk::new("doc-1", "Байкал — самое глубокое озеро на планете."),
+///         Chunk::new("doc-2", "Эльбрус — высочайшая вершина России."),
+///     ];
+///     let retriever = InMemoryRetriever::from_chunks(&client, Model::EmbeddingsGigaR, chunks)
+///         .await
+///         .unwrap();
+///
+///     let rag = client.rag(retriever).with_top_k(1).build();
+///     let answer = rag.ask("Какое озеро самое глубокое?").await.unwrap();
+///
+///     println!("{}", answer.text);
+///     for source in answer.cited_sources() {
+///         println!("- {}", source.chunk.id);
+///     }
+/// }
+/// ```
+pub struct RagBuilder {
+    client: GigaChatClient,
+    retriever: Arc<dyn Retriever>,
+    embeddings_model: embeddings::Model,
+    model: generation::Model,
+    top_k: usize,
+    system_prompt: String,
+    config: GenerationConfig,
+}
+
+impl RagBuilder {
+    /// Устанавливает модель для векторизации запроса.
+    ///
+    /// Должна совпадать с моделью, которой векторизованы фрагменты.
+    pub fn with_embeddings_model(mut self, model: embeddings::Model) -> Self {
+        self.embeddings_model = model;
+        self
+    }
+
+    /// Устанавливает модель для генерации ответа.
+    pub fn with_model(mut self, model: generation::Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Устанавливает количество извлекаемых фрагментов.
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    /// Заменяет системный промпт.
+    ///
+    /// Промпт должен объяснять модели, как ссылаться на источники `[n]`.
+    pub fn with_system_prompt<S: Into<String>>(mut self, system_prompt: S) -> Self {
+        self.system_prompt = system_prompt.into();
+        self
+    }
+
+    /// Устанавливает параметры генерации.
+    pub fn with_config(mut self, config: GenerationConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Собирает конвейер.
+    pub fn build(self) -> RagPipeline {
+        RagPipeline {
+            client: self.client,
+            retriever: self.retriever,
+            embeddings_model: self.embeddings_model,
+            model: self.model,
+            top_k: self.top_k,
+            system_prompt: self.system_prompt,
+            config: self.config,
+        }
+    }
+}
+
+/// Конвейер генерации с извлечением: векторизация вопроса, поиск фрагментов
+/// и генерация ответа со ссылками на источники.
+#[derive(Clone)]
+pub struct RagPipeline {
+    client: GigaChatClient,
+    retriever: Arc<dyn Retriever>,
+    embeddings_model: embeddings::Model,
+    model: generation::Model,
+    top_k: usize,
+    system_prompt: String,
+    config: GenerationConfig,
+}
+
+impl RagPipeline {
+    /// Находит фрагменты, относящиеся к вопросу.
+    #[tracing::instrument(skip_all, fields(rag.top_k = self.top_k), err)]
+    pub async fn retrieve(&self, question: &str) -> Result<Vec<RetrievedChunk>, error::Error> {
+        let embeddings = self
+            .client
+            .embeddings()
+            .with_model(self.embeddings_model.clone())
+            .with_input(question)
+            .execute()
+            .await
+            .context(error::EmbedSnafu)?;
+        let query = embeddings
+            .items
+            .into_iter()
+            .next()
+            .context(error::EmptyEmbeddingSnafu)?;
+
+        let sources = self
+            .retriever
+            .retrieve(&query.embedding, self.top_k)
+            .await
+            .context(error::RetrieveSnafu)?;
+        tracing::debug!(sources = sources.len(), "chunks retrieved");
+        Ok(sources)
+    }
+
+    /// Сообщения для модели: системный промпт и вопрос с пронумерованными источниками.
+    pub fn messages(&self, question: &str, sources: &[RetrievedChunk]) -> Vec<Message> {
+        let mut prompt = String::from("Источники:\n");
+        for (number, source) in sources.iter().enumerate() {
+            prompt.push_str(&format!(
+                "\n[{}] {}\n",
+                number + 1,
+                source.chunk.text.trim()
+            ));
+        }
+        prompt.push_str(&format!("\nВопрос: {question}"));
+
+        vec![
+            Message::system(self.system_prompt.clone()),
+            Message::user(prompt),
+        ]
+    }
+
+    /// Отвечает на вопрос, используя найденные фрагменты.
+    #[tracing::instrument(skip_all, err)]
+    pub async fn ask(&self, question: &str) -> Result<RagAnswer, error::Error> {
+        let sources = self.retrieve(question).await?;
+
+        let response = self
+            .client
+            .generate()
+            .with_model(self.model.clone())
+            .with_config(self.config)
+            .with_messages(self.messages(question, &sources))
+            .execute()
+            .await
+            .context(error::GenerateSnafu)?;
+        ensure!(!response.choices.is_empty(), error::EmptyResponseSnafu);
+
+        Ok(RagAnswer {
+            text: response.text(),
+            sources,
+            response,
+        })
+    }
+}
+
+impl GigaChatClient {
+    /// Создает сборщик конвейера генерации с извлечением.
+    pub fn rag<R: Retriever + 'static>(&self, retriever: R) -> RagBuilder {
+        RagBuilder {
+            client: self.clone(),
+            retriever: Arc::new(retriever),
+            embeddings_model: embeddings::Model::EmbeddingsGigaR,
+            model: generation::Model::default(),
+            top_k: DEFAULT_TOP_K,
+            system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            config: GenerationConfig::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::mock::{self, MockServer};
+
+    fn retriever() -> InMemoryRetriever {
+        let mut index = VectorIndex::new(2, Metric::Cosine);
+        let chunks = [
+            (
+                vec![1.0, 0.0],
+                Chunk::new("lake", "Байкал — самое глубокое озеро."),
+            ),
+            (
+                vec![0.0, 1.0],
+                Chunk::new("mountain", "Эльбрус — вершина России."),
+            ),
+            (
+                vec![0.7, 0.7],
+                Chunk::new("both", "Байкал и Эльбрус находятся в России."),
+            ),
+        ];
+        for (embedding, chunk) in chunks {
+            index.insert(embedding, chunk).unwrap();
+        }
+        InMemoryRetriever::new(index)
+    }
+
+    fn source(id: &str) -> RetrievedChunk {
+        RetrievedChunk {
+            chunk: Chunk::new(id, format!(" текст {id}\n")),
+            score: 1.0,
+        }
+    }
+
+    fn rag_answer(text: &str, sources: usize) -> RagAnswer {
+        RagAnswer {
+            text: text.to_string(),
+            sources: (1..=sources).map(|n| source(&format!("doc-{n}"))).collect(),
+            response: serde_json::from_value(mock::completion("GigaChat-2-Max", text)).unwrap(),
+        }
+    }
+
+    fn ids<'a>(sources: impl IntoIterator<Item = &'a RetrievedChunk>) -> Vec<&'a str> {
+        sources.into_iter().map(|s| s.chunk.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn retriever_ranks_by_similarity() {
+        let retriever = retriever();
+
+        let hits = retriever.retrieve(&[1.0, 0.1], 2).await.unwrap();
+        assert_eq!(ids(&hits), ["lake", "both"]);
+        assert!(hits[0].score > hits[1].score);
+
+        let hits = retriever.retrieve(&[0.0, 1.0], 10).await.unwrap();
+        assert_eq!(ids(&hits), ["mountain", "both", "lake"]);
+    }
+
+    #[tokio::test]
+    async fn empty_retriever_returns_nothing() {
+        let hits = InMemoryRetriever::default()
+            .retrieve(&[1.0, 0.0], 3)
+            .await
+            .unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn cited_sources_follow_brackets() {
+        let answer = rag_answer("Байкал [2]. Он в Сибири [1, 3]; см. [0], [7] и [x].", 3);
+        assert_eq!(ids(answer.cited_sources()), ["doc-1", "doc-2", "doc-3"]);
+
+        let answer = rag_answer("Ответ [2] без закрывающей [1", 3);
+        assert_eq!(ids(answer.cited_sources()), ["doc-2"]);
+
+        let answer = rag_answer("Без ссылок.", 2);
+        assert!(answer.cited_sources().is_empty());
+    }
+
+    #[tokio::test]
+    async fn messages_number_sources() {
+        let server = MockServer::start(|_| (404, json!({}))).await;
+        let rag = server
+            .client()
+            .await
+            .rag(retriever())
+            .with_system_prompt("Система")
+            .build();
+
+        let messages = rag.messages("Где Байкал?", &[source("a"), source("b")]);
+        assert_eq!(
+            messages,
+            [
+                Message::system("Система"),
+                Message::user("Источники:\n\n[1] текст a\n\n[2] текст b\n\nВопрос: Где Байкал?"),
+            ]
+        );
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn ask_through_mock_server() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/embeddings" => {
+                let vectors: Vec<_> = mock::embedding_inputs(request)
+                    .iter()
+                    .map(|text| {
+                        if text.contains("Эльбрус") {
+                            vec![0.0, 1.0]
+                        } else {
+                            vec![1.0, 0.0]
+                        }
+                    })
+                    .collect();
+                (200, mock::embeddings(&vectors))
+            }
+            "/chat/completions" => (200, mock::completion("GigaChat-2-Max", "Байкал [1].")),
+            path => (404, json!({ "path": path })),
+        })
+        .await;
+        let client = server.client().await;
+
+        let chunks = vec![
+            Chunk::new("doc-1", "Байкал — самое глубокое озеро."),
+            Chunk::new("doc-2", "Эльбрус — высочайшая вершина России."),
+        ];
+        let retriever =
+            InMemoryRetriever::from_chunks(&client, embeddings::Model::EmbeddingsGigaR, chunks)
+                .await
+                .unwrap();
+        let answer = client
+            .rag(retriever)
+            .with_top_k(1)
+            .build()
+            .ask("Какое озеро самое глубокое?")
+            .await
+            .unwrap();
+
+        assert_eq!(answer.text, "Байкал [1].");
+        assert_eq!(ids(&answer.sources), ["doc-1"]);
+        assert_eq!(ids(answer.cited_sources()), ["doc-1"]);
+
+        let requests = server.requests();
+        let paths: Vec<_> = requests.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["/embeddings", "/embeddings", "/chat/completions"]);
+
+        let body = requests[2].json();
+        let prompt = body["messages"][1]["content"].as_str().unwrap();
+        assert!(
+            prompt.contains("[1] Байкал — самое глубокое озеро."),
+            "{prompt}"
+        );
+        assert!(!prompt.contains("Эльбрус"), "{prompt}");
+    }
+}
@@ -51,9 +51,10 @@ pub mod string_json {
         D: Deserializer<'de>,
     {
         // First, deserialize the incoming data into a string.
-        let s: &str = Deserialize::deserialize(deserializer)?;
+        // The string is owned, because escaped JSON can't be borrowed from the input.
+        let s: String = Deserialize::deserialize(deserializer)?;
         // Then, parse the string into a serde_json::Value.
-        serde_json::from_str(s).map_err(D::Error::custom)
+        serde_json::from_str(&s).map_err(D::Error::custom)
     }
 
     /// Serializes a `serde_json::Value` into a string-encoded JSON.
@@ -0,0 +1,109 @@
+use snafu::prelude::*;
+
+use super::error;
+use crate::{client::GigaChatClient, generation::Model};
+
+/// Оценка количества токенов на символ по умолчанию для русскоязычного текста.
+const DEFAULT_TOKENS_PER_CHAR: f64 = 0.3;
+
+/// Способ подсчета токенов в тексте.
+#[async_trait::async_trait]
+pub trait TokenCounter: Send + Sync {
+    /// Возвращает количество токенов для каждой строки в порядке входных строк.
+    async fn count(&self, texts: &[&str]) -> Result<Vec<usize>, error::Error>;
+}
+
+/// Офлайн-оценка количества токенов по числу символов.
+///
+/// Коэффициент по умолчанию подобран для русского текста; для точной оценки
+/// его можно откалибровать по образцу через [`TokenEstimator::calibrate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenEstimator {
+    tokens_per_char: f64,
+}
+
+impl Default for TokenEstimator {
+    fn default() -> Self {
+        Self::new(DEFAULT_TOKENS_PER_CHAR)
+    }
+}
+
+impl TokenEstimator {
+    /// Создает оценщик с заданным количеством токенов на символ.
+    pub fn new(tokens_per_char: f64) -> Self {
+        Self { tokens_per_char }
+    }
+
+    /// Калибрует коэффициент по образцам текста через `/tokens/count`.
+    ///
+    /// Образцы должны быть похожи на размечаемые документы по языку и стилю.
+    pub async fn calibrate(
+        client: &GigaChatClient,
+        model: Model,
+        samples: &[&str],
+    ) -> Result<Self, error::Error> {
+        let counts = client
+            .count_tokens(model, samples.iter().map(|s| s.to_string()).collect())
+            .await
+            .context(error::CountTokensSnafu)?;
+
+        let tokens: usize = counts.iter().map(|c| c.tokens).sum();
+        let chars: usize = samples.iter().map(|s| s.chars().count()).sum();
+        if tokens == 0 || chars == 0 {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::new(tokens as f64 / chars as f64))
+    }
+
+    /// Количество токенов на символ.
+    pub fn tokens_per_char(&self) -> f64 {
+        self.tokens_per_char
+    }
+
+    /// Оценивает количество токенов в строке.
+    pub fn estimate(&self, text: &str) -> usize {
+        (text.chars().count() as f64 * self.tokens_per_char).ceil() as usize
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCounter for TokenEstimator {
+    async fn count(&self, texts: &[&str]) -> Result<Vec<usize>, error::Error> {
+        Ok(texts.iter().map(|text| self.estimate(text)).collect())
+    }
+}
+
+/// Точный подсчет токенов через метод `/tokens/count`.
+#[derive(Clone)]
+pub struct ApiTokenCounter {
+    client: GigaChatClient,
+    model: Model,
+}
+
+impl ApiTokenCounter {
+    /// Создает счетчик для указанной модели.
+    pub fn new(client: GigaChatClient, model: Model) -> Self {
+        Self { client, model }
+    }
+}
+
+#[async_trait::async_trait]
+impl TokenCounter for ApiTokenCounter {
+    async fn count(&self, texts: &[&str]) -> Result<Vec<usize>, error::Error> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let counts = self
+            .client
+            .count_tokens(
+                self.model.clone(),
+                texts.iter().map(|s| s.to_string()).collect(),
+            )
+            .await
+            .context(error::CountTokensSnafu)?;
+
+        Ok(counts.into_iter().map(|c| c.tokens).collect())
+    }
+}
@@ -0,0 +1,19 @@
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum Error {
+    #[snafu(display("failed to count tokens"))]
+    CountTokens {
+        source: crate::generation::error::Error,
+    },
+
+    #[snafu(display("token counter returned {actual} counts for {expected} texts"))]
+    CountMismatch { expected: usize, actual: usize },
+
+    #[snafu(display("max tokens must be positive"))]
+    MaxTokensIsZero,
+
+    #[snafu(display("overlap {overlap} must be less than max tokens {max_tokens}"))]
+    OverlapTooLarge { overlap: usize, max_tokens: usize },
+}
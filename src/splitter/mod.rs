@@ -0,0 +1,618 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+
+mod counter;
+pub mod error;
+
+pub use counter::{ApiTokenCounter, TokenCounter, TokenEstimator};
+
+/// Знаки, завершающие предложение.
+const SENTENCE_TERMINATORS: &[char] = &['.', '!', '?', '…'];
+/// Знаки, которые могут следовать за концом предложения до пробела.
+const SENTENCE_CLOSERS: &[char] = &['"', '\'', '»', '”', ')', ']'];
+/// Знаки, с которых может начинаться предложение, помимо заглавной буквы и цифры.
+const SENTENCE_OPENERS: &[char] = &['"', '«', '„', '“', '—', '–', '-', '(', '['];
+
+/// Формат размечаемого текста.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// Обычный текст: абзацы, строки и предложения.
+    #[default]
+    Plain,
+    /// Markdown: дополнительно учитываются заголовки разделов,
+    /// а блоки кода не разрываются по абзацам.
+    Markdown,
+}
+
+/// Фрагмент исходного текста.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextChunk {
+    /// Текст фрагмента без пробелов по краям.
+    pub text: String,
+    /// Смещение фрагмента в байтах исходной строки.
+    pub byte_range: Range<usize>,
+    /// Смещение фрагмента в символах исходной строки.
+    pub char_range: Range<usize>,
+    /// Количество токенов по оценке счетчика.
+    pub tokens: usize,
+}
+
+/// Граница, по которой текст может быть разделен, от самой крупной к самой мелкой.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Section,
+    Paragraph,
+    Line,
+    Sentence,
+    Word,
+}
+
+const PLAIN_LEVELS: &[Level] = &[Level::Paragraph, Level::Line, Level::Sentence, Level::Word];
+const MARKDOWN_LEVELS: &[Level] = &[
+    Level::Section,
+    Level::Paragraph,
+    Level::Line,
+    Level::Sentence,
+    Level::Word,
+];
+
+/// Неделимая часть текста с количеством токенов.
+#[derive(Debug, Clone)]
+struct Piece {
+    range: Range<usize>,
+    tokens: usize,
+    /// Индекс следующего уровня разбиения; после последнего уровня
+    /// текст делится по символам.
+    level: usize,
+}
+
+/// Разбивает длинный текст на фрагменты, помещающиеся в ограничение модели.
+///
+/// Текст делится по самым крупным возможным границам: разделам Markdown,
+/// абзацам, строкам, предложениям и словам; слишком длинные слова делятся
+/// по символам. Соседние части объединяются, пока фрагмент укладывается
+/// в `max_tokens`, а соседние фрагменты перекрываются на `overlap` токенов.
+///
+/// Длина измеряется через [`TokenCounter`]: по умолчанию офлайн-оценкой
+/// [`TokenEstimator`], либо точно через [`ApiTokenCounter`]. При подсчете
+/// через API запросы объединяются по уровням разбиения, поэтому их число
+/// не зависит от длины текста.
+///
+/// ## Пример
+///
+/// ```rust
+/// use gigachat_rust::splitter::{Format, TextSplitter};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let text = "# Заголовок\n\nПервое предложение. Второе предложение.\n\nЕще абзац.";
+///
+///     let chunks = TextSplitter::new(8)
+///         .with_format(Format::Markdown)
+///         .split(text)
+///         .await
+///         .unwrap();
+///
+///     for chunk in &chunks {
+///         assert_eq!(&text[chunk.byte_range.clone()], chunk.text);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextSplitter<C = TokenEstimator> {
+    counter: C,
+    max_tokens: usize,
+    overlap: usize,
+    format: Format,
+}
+
+impl TextSplitter {
+    /// Создает разделитель с офлайн-оценкой длины.
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            counter: TokenEstimator::default(),
+            max_tokens,
+            overlap: 0,
+            format: Format::default(),
+        }
+    }
+}
+
+impl<C: TokenCounter> TextSplitter<C> {
+    /// Устанавливает способ подсчета токенов.
+    pub fn with_counter<D: TokenCounter>(self, counter: D) -> TextSplitter<D> {
+        TextSplitter {
+            counter,
+            max_tokens: self.max_tokens,
+            overlap: self.overlap,
+            format: self.format,
+        }
+    }
+
+    /// Устанавливает перекрытие соседних фрагментов в токенах.
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Устанавливает формат текста.
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    async fn count(&self, texts: &[&str]) -> Result<Vec<usize>, error::Error> {
+        let counts = self.counter.count(texts).await?;
+        ensure!(
+            counts.len() == texts.len(),
+            error::CountMismatchSnafu {
+                expected: texts.len(),
+                actual: counts.len(),
+            }
+        );
+        Ok(counts)
+    }
+
+    /// Разбивает текст на фрагменты.
+    ///
+    /// Количество токенов фрагмента пересчитывается счетчиком, поэтому
+    /// при подсчете через API оно может немного превышать `max_tokens`
+    /// из-за неаддитивности токенизации.
+    #[tracing::instrument(skip_all, fields(splitter.bytes = text.len()), err)]
+    pub async fn split(&self, text: &str) -> Result<Vec<TextChunk>, error::Error> {
+        ensure!(self.max_tokens > 0, error::MaxTokensIsZeroSnafu);
+        ensure!(
+            self.overlap < self.max_tokens,
+            error::OverlapTooLargeSnafu {
+                overlap: self.overlap,
+                max_tokens: self.max_tokens,
+            }
+        );
+
+        if text.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pieces = self.pieces(text).await?;
+        let ranges = self.merge(text, &pieces);
+
+        let texts: Vec<&str> = ranges.iter().map(|range| &text[range.clone()]).collect();
+        let tokens = self.count(&texts).await?;
+
+        let char_starts: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+        let char_index = |byte: usize| char_starts.partition_point(|&start| start < byte);
+
+        Ok(ranges
+            .into_iter()
+            .zip(tokens)
+            .map(|(range, tokens)| TextChunk {
+                text: text[range.clone()].to_string(),
+                char_range: char_index(range.start)..char_index(range.end),
+                byte_range: range,
+                tokens,
+            })
+            .collect())
+    }
+
+    /// Делит текст на части, каждая из которых укладывается в `max_tokens`.
+    async fn pieces(&self, text: &str) -> Result<Vec<Piece>, error::Error> {
+        let levels = match self.format {
+            Format::Plain => PLAIN_LEVELS,
+            Format::Markdown => MARKDOWN_LEVELS,
+        };
+        let fences = match self.format {
+            Format::Plain => Vec::new(),
+            Format::Markdown => code_fences(text),
+        };
+
+        let mut pieces = vec![Piece {
+            range: 0..text.len(),
+            tokens: self.count(&[text]).await?[0],
+            level: 0,
+        }];
+
+        loop {
+            let mut next = Vec::with_capacity(pieces.len());
+            let mut uncounted = Vec::new();
+            let mut changed = false;
+
+            for piece in pieces {
+                if piece.tokens <= self.max_tokens {
+                    next.push(piece);
+                    continue;
+                }
+
+                let cuts = match levels.get(piece.level) {
+                    Some(&level) => boundaries(text, piece.range.clone(), level, &fences),
+                    None => char_cuts(text, piece.range.clone(), piece.tokens, self.max_tokens),
+                };
+
+                changed = true;
+                if cuts.is_empty() {
+                    if piece.level < levels.len() {
+                        next.push(Piece {
+                            level: piece.level + 1,
+                            ..piece
+                        });
+                    } else {
+                        // Один символ, превышающий ограничение, делить некуда:
+                        // он образует отдельный фрагмент.
+                        next.push(Piece {
+                            tokens: self.max_tokens,
+                            ..piece
+                        });
+                    }
+                    continue;
+                }
+
+                let level = (piece.level + 1).min(levels.len());
+                let mut start = piece.range.start;
+                for end in cuts.into_iter().chain([piece.range.end]) {
+                    uncounted.push(next.len());
+                    next.push(Piece {
+                        range: start..end,
+                        tokens: 0,
+                        level,
+                    });
+                    start = end;
+                }
+            }
+
+            if !changed {
+                return Ok(next);
+            }
+
+            let texts: Vec<&str> = uncounted
+                .iter()
+                .map(|&i| &text[next[i].range.clone()])
+                .collect();
+            let counts = self.count(&texts).await?;
+            for (i, tokens) in uncounted.into_iter().zip(counts) {
+                next[i].tokens = tokens;
+            }
+            pieces = next;
+        }
+    }
+
+    /// Объединяет соседние части во фрагменты с перекрытием.
+    fn merge(&self, text: &str, pieces: &[Piece]) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+
+        while start < pieces.len() {
+            let mut end = start + 1;
+            let mut tokens = pieces[start].tokens;
+            while end < pieces.len() && tokens + pieces[end].tokens <= self.max_tokens {
+                tokens += pieces[end].tokens;
+                end += 1;
+            }
+
+            let range = trim_range(text, pieces[start].range.start..pieces[end - 1].range.end);
+            if !range.is_empty() {
+                ranges.push(range);
+            }
+
+            if end == pieces.len() {
+                break;
+            }
+
+            // Следующий фрагмент начинается с хвоста текущего в пределах перекрытия,
+            // но всегда продвигается хотя бы на одну часть.
+            let mut next = end;
+            let mut overlap = 0;
+            while next > start + 1 && overlap + pieces[next - 1].tokens <= self.overlap {
+                overlap += pieces[next - 1].tokens;
+                next -= 1;
+            }
+            start = next;
+        }
+
+        ranges
+    }
+}
+
+/// Сужает диапазон, отбрасывая пробельные символы по краям.
+fn trim_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let slice = &text[range.clone()];
+    let start = range.start + (slice.len() - slice.trim_start().len());
+    let end = range.end - (slice.len() - slice.trim_end().len());
+    start..end.max(start)
+}
+
+/// Диапазоны блоков кода Markdown, огражденных ``` или ~~~.
+fn code_fences(text: &str) -> Vec<Range<usize>> {
+    let mut fences = Vec::new();
+    let mut open: Option<(usize, &str)> = None;
+
+    for (start, line) in lines(text, 0..text.len()) {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+
+        match (open, marker) {
+            (None, Some(marker)) => open = Some((start, marker)),
+            (Some((fence_start, fence_marker)), Some(marker)) if marker == fence_marker => {
+                fences.push(fence_start..start + line.len());
+                open = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((fence_start, _)) = open {
+        fences.push(fence_start..text.len());
+    }
+    fences
+}
+
+/// Строки диапазона вместе с их начальным смещением; перевод строки входит в строку.
+fn lines(text: &str, range: Range<usize>) -> impl Iterator<Item = (usize, &str)> {
+    let base = range.start;
+    text[range]
+        .split_inclusive('\n')
+        .scan(base, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+}
+
+/// Позиции внутри диапазона, по которым его можно разделить на указанном уровне.
+fn boundaries(
+    text: &str,
+    range: Range<usize>,
+    level: Level,
+    fences: &[Range<usize>],
+) -> Vec<usize> {
+    let in_fence = |position: usize| {
+        fences
+            .iter()
+            .any(|fence| fence.start < position && position < fence.end)
+    };
+    let inner = |position: usize| range.start < position && position < range.end;
+
+    match level {
+        Level::Section => lines(text, range.clone())
+            .filter(|(start, line)| {
+                let hashes = line.chars().take_while(|&c| c == '#').count();
+                (1..=6).contains(&hashes)
+                    && line[hashes..].starts_with([' ', '\t'])
+                    && inner(*start)
+                    && !in_fence(*start)
+            })
+            .map(|(start, _)| start)
+            .collect(),
+        Level::Paragraph => {
+            let mut cuts = Vec::new();
+            let mut previous_blank = false;
+            for (start, line) in lines(text, range.clone()) {
+                let blank = line.trim().is_empty();
+                if previous_blank && !blank && inner(start) && !in_fence(start) {
+                    cuts.push(start);
+                }
+                previous_blank = blank;
+            }
+            cuts
+        }
+        Level::Line => lines(text, range.clone())
+            .map(|(start, _)| start)
+            .filter(|&start| inner(start))
+            .collect(),
+        Level::Sentence => sentence_boundaries(&text[range.clone()])
+            .into_iter()
+            .map(|position| range.start + position)
+            .filter(|&position| inner(position))
+            .collect(),
+        Level::Word => {
+            let mut cuts = Vec::new();
+            let mut previous_space = false;
+            for (position, c) in text[range.clone()].char_indices() {
+                let space = c.is_whitespace();
+                if previous_space && !space {
+                    cuts.push(range.start + position);
+                }
+                previous_space = space;
+            }
+            cuts.retain(|&position| inner(position));
+            cuts
+        }
+    }
+}
+
+/// Начала предложений, кроме первого.
+///
+/// Предложение заканчивается знаком `.`, `!`, `?` или `…`, за которым следуют
+/// пробел и заглавная буква (латинская или кириллическая), цифра или
+/// открывающая кавычка/тире. Сокращения вида «т. е.» не считаются концом
+/// предложения, так как за ними следует строчная буква.
+fn sentence_boundaries(text: &str) -> Vec<usize> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut cuts = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if !SENTENCE_TERMINATORS.contains(&chars[i].1) {
+            i += 1;
+            continue;
+        }
+
+        let mut j = i + 1;
+        while j < chars.len()
+            && (SENTENCE_TERMINATORS.contains(&chars[j].1)
+                || SENTENCE_CLOSERS.contains(&chars[j].1))
+        {
+            j += 1;
+        }
+
+        let spaces = j;
+        while j < chars.len() && chars[j].1.is_whitespace() {
+            j += 1;
+        }
+
+        if j > spaces
+            && let Some(&(position, c)) = chars.get(j)
+            && (c.is_uppercase() || c.is_ascii_digit() || SENTENCE_OPENERS.contains(&c))
+        {
+            cuts.push(position);
+        }
+        i = j.max(i + 1);
+    }
+
+    cuts
+}
+
+/// Делит диапазон по символам на части, примерно укладывающиеся в ограничение.
+fn char_cuts(text: &str, range: Range<usize>, tokens: usize, max_tokens: usize) -> Vec<usize> {
+    let positions: Vec<usize> = text[range.clone()]
+        .char_indices()
+        .map(|(i, _)| range.start + i)
+        .collect();
+    if positions.len() < 2 {
+        return Vec::new();
+    }
+
+    let parts = tokens.div_ceil(max_tokens).clamp(2, positions.len());
+    let chars_per_part = positions.len().div_ceil(parts);
+    positions
+        .into_iter()
+        .skip(chars_per_part)
+        .step_by(chars_per_part)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Разделитель, считающий один токен на символ.
+    fn splitter(max_tokens: usize) -> TextSplitter {
+        TextSplitter::new(max_tokens).with_counter(TokenEstimator::new(1.0))
+    }
+
+    async fn split(splitter: &TextSplitter, text: &str) -> Vec<String> {
+        let chunks = splitter.split(text).await.unwrap();
+        for chunk in &chunks {
+            assert_eq!(text[chunk.byte_range.clone()], chunk.text);
+        }
+        chunks.into_iter().map(|chunk| chunk.text).collect()
+    }
+
+    #[tokio::test]
+    async fn empty_input() {
+        assert!(split(&splitter(10), "").await.is_empty());
+        assert!(split(&splitter(10), " \n\t ").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn short_text_is_single_chunk() {
+        assert_eq!(
+            split(&splitter(100), " Привет, мир! ").await,
+            ["Привет, мир!"]
+        );
+    }
+
+    #[tokio::test]
+    async fn splits_by_sections() {
+        let text = "# A\nтекст\n# B\nтекст";
+        let splitter = splitter(10).with_format(Format::Markdown);
+
+        assert_eq!(split(&splitter, text).await, ["# A\nтекст", "# B\nтекст"]);
+    }
+
+    #[tokio::test]
+    async fn splits_by_paragraphs() {
+        assert_eq!(split(&splitter(6), "aaaa\n\nbbbb").await, ["aaaa", "bbbb"]);
+    }
+
+    #[tokio::test]
+    async fn splits_by_lines() {
+        assert_eq!(split(&splitter(6), "aaaa\nbbbb").await, ["aaaa", "bbbb"]);
+    }
+
+    #[tokio::test]
+    async fn splits_by_sentences() {
+        assert_eq!(
+            split(&splitter(11), "Раз два. Три четыре.").await,
+            ["Раз два.", "Три четыре."]
+        );
+    }
+
+    #[tokio::test]
+    async fn splits_by_words() {
+        assert_eq!(
+            split(&splitter(5), "один два три").await,
+            ["один", "два", "три"]
+        );
+    }
+
+    #[tokio::test]
+    async fn long_word_is_split_by_characters() {
+        let text = "абвгдежзийклмно";
+        let chunks = splitter(4).split(text).await.unwrap();
+
+        assert!(chunks.iter().all(|chunk| chunk.tokens <= 4));
+        let joined: String = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(joined, text);
+    }
+
+    #[tokio::test]
+    async fn character_longer_than_limit() {
+        let splitter = TextSplitter::new(5).with_counter(TokenEstimator::new(10.0));
+
+        assert_eq!(split(&splitter, "ab").await, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn overlap_repeats_tail_of_previous_chunk() {
+        let chunks = splitter(6)
+            .with_overlap(2)
+            .split("a b c d e f g h")
+            .await
+            .unwrap();
+
+        assert!(chunks.len() > 1);
+        for pair in chunks.windows(2) {
+            assert!(pair[1].byte_range.start > pair[0].byte_range.start);
+            assert!(pair[1].byte_range.start < pair[0].byte_range.end);
+        }
+        assert!(chunks.last().unwrap().text.ends_with('h'));
+    }
+
+    #[tokio::test]
+    async fn overlap_larger_than_pieces_still_advances() {
+        let chunks = splitter(8)
+            .with_overlap(7)
+            .split("aaaa bbbb cccc dddd")
+            .await
+            .unwrap();
+
+        for pair in chunks.windows(2) {
+            assert!(pair[1].byte_range.start > pair[0].byte_range.start);
+        }
+        assert_eq!(chunks.last().unwrap().text, "dddd");
+    }
+
+    #[tokio::test]
+    async fn overlap_must_be_less_than_limit() {
+        let result = splitter(4).with_overlap(4).split("текст").await;
+
+        assert!(matches!(
+            result,
+            Err(error::Error::OverlapTooLarge {
+                overlap: 4,
+                max_tokens: 4
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn zero_limit_is_rejected() {
+        let result = splitter(0).split("текст").await;
+
+        assert!(matches!(result, Err(error::Error::MaxTokensIsZero)));
+    }
+}
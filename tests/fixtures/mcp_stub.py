@@ -0,0 +1,75 @@
+"""Minimal MCP server over stdio for integration tests.
+
+Implements `initialize`, `tools/list` and `tools/call` with newline-delimited
+JSON-RPC. The `broken` tool has an empty name after trimming and must be
+skipped by the client.
+"""
+
+import json
+import sys
+
+TOOLS = [
+    {
+        "name": "add",
+        "description": "Adds two numbers",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "a": {"type": "number", "description": "First addend"},
+                "b": {"type": "number", "description": "Second addend"},
+            },
+            "required": ["a", "b"],
+        },
+    },
+    {
+        "name": " ",
+        "description": "Tool with an invalid name",
+        "inputSchema": {"type": "object", "properties": {}},
+    },
+]
+
+
+def respond(request_id, result):
+    message = {"jsonrpc": "2.0", "id": request_id, "result": result}
+    sys.stdout.write(json.dumps(message) + "\n")
+    sys.stdout.flush()
+
+
+for line in sys.stdin:
+    if not line.strip():
+        continue
+    request = json.loads(line)
+    method = request.get("method")
+    if "id" not in request:
+        continue
+
+    if method == "initialize":
+        respond(
+            request["id"],
+            {
+                "protocolVersion": request["params"]["protocolVersion"],
+                "capabilities": {"tools": {}},
+                "serverInfo": {"name": "stub", "version": "0.1.0"},
+            },
+        )
+    elif method == "tools/list":
+        respond(request["id"], {"tools": TOOLS})
+    elif method == "tools/call":
+        arguments = request["params"].get("arguments") or {}
+        total = arguments.get("a", 0) + arguments.get("b", 0)
+        respond(
+            request["id"],
+            {"content": [{"type": "text", "text": json.dumps({"sum": total})}]},
+        )
+    else:
+        sys.stdout.write(
+            json.dumps(
+                {
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {"code": -32601, "message": "method not found"},
+                }
+            )
+            + "\n"
+        )
+        sys.stdout.flush()
@@ -0,0 +1,60 @@
+//! Проверка [`McpClient`] на локальном stdio-сервере `tests/fixtures/mcp_stub.py`.
+//!
+//! Требует `python3`; если интерпретатора нет, тесты пропускаются.
+#![cfg(feature = "mcp")]
+
+use gigachat_rust::{
+    generation::structures::{FunctionCallResponse, Message},
+    mcp::McpClient,
+};
+use serde_json::json;
+use tokio::process::Command;
+
+/// Подключается к серверу или возвращает `None`, если `python3` недоступен.
+async fn connect() -> Option<McpClient> {
+    let python = Command::new("python3").arg("--version").output().await;
+    if !python.is_ok_and(|output| output.status.success()) {
+        eprintln!("python3 is not available, skipping MCP stdio test");
+        return None;
+    }
+
+    let mut command = Command::new("python3");
+    command.arg(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/mcp_stub.py"
+    ));
+    Some(McpClient::connect_stdio(command).await.unwrap())
+}
+
+#[tokio::test]
+async fn invalid_tools_are_skipped() {
+    let Some(mcp) = connect().await else {
+        return;
+    };
+
+    let names: Vec<_> = mcp.functions().iter().map(|f| f.name()).collect();
+    assert_eq!(names, ["add"]);
+
+    mcp.close().await;
+}
+
+#[tokio::test]
+async fn call_tool() {
+    let Some(mcp) = connect().await else {
+        return;
+    };
+
+    let call: FunctionCallResponse = serde_json::from_value(json!({
+        "name": "add",
+        "arguments": { "a": 2, "b": 3 },
+    }))
+    .unwrap();
+    let message = mcp.call(&call).await.unwrap();
+
+    match message {
+        Message::Function { content, .. } => assert_eq!(content, json!({ "sum": 5 })),
+        other => panic!("unexpected message: {other:?}"),
+    }
+
+    mcp.close().await;
+}